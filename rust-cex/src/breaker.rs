@@ -0,0 +1,181 @@
+use log::{info, warn};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tokio::time::sleep;
+
+/// How often a request waiting on a half-open circuit checks whether the probe finished
+const PROBE_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Phase {
+    Closed,
+    Open,
+    HalfOpen,
+}
+
+#[derive(Debug)]
+struct BreakerState {
+    phase: Phase,
+    /// Consecutive 429s since `first_rate_limited`
+    consecutive: usize,
+    first_rate_limited: Option<Instant>,
+    opened_at: Option<Instant>,
+    probe_in_flight: bool,
+}
+
+/// Pauses every request shared by a `CEXScraper` once the explorer keeps rate limiting.
+///
+/// After `threshold` consecutive 429s within `window` the circuit opens and requests
+/// wait out `cooldown`. Then a single probe request goes through: if it is rate
+/// limited again the circuit reopens, otherwise it closes.
+#[derive(Debug)]
+pub(crate) struct CircuitBreaker {
+    threshold: usize,
+    window: Duration,
+    cooldown: Duration,
+    state: Mutex<BreakerState>,
+}
+
+impl CircuitBreaker {
+    /// A `threshold` of zero disables the breaker
+    pub(crate) fn new(threshold: usize, window: Duration, cooldown: Duration) -> Self {
+        Self {
+            threshold,
+            window,
+            cooldown,
+            state: Mutex::new(BreakerState {
+                phase: Phase::Closed,
+                consecutive: 0,
+                first_rate_limited: None,
+                opened_at: None,
+                probe_in_flight: false,
+            }),
+        }
+    }
+
+    /// Waits until a request may be sent
+    pub(crate) async fn acquire(&self) {
+        loop {
+            let wait = {
+                let mut state = self.state.lock().expect("breaker lock poisoned");
+                match state.phase {
+                    Phase::Closed => return,
+                    Phase::Open => {
+                        let elapsed = state.opened_at.map_or(self.cooldown, |opened_at| opened_at.elapsed());
+                        if elapsed < self.cooldown {
+                            self.cooldown - elapsed
+                        } else {
+                            info!("Circuit breaker half-open, sending a probe request");
+                            state.phase = Phase::HalfOpen;
+                            state.probe_in_flight = true;
+                            return;
+                        }
+                    }
+                    Phase::HalfOpen if !state.probe_in_flight => {
+                        state.probe_in_flight = true;
+                        return;
+                    }
+                    Phase::HalfOpen => PROBE_POLL_INTERVAL,
+                }
+            };
+            sleep(wait).await;
+        }
+    }
+
+    pub(crate) fn record_rate_limited(&self) {
+        if self.threshold == 0 {
+            return;
+        }
+
+        let mut state = self.state.lock().expect("breaker lock poisoned");
+        match state.phase {
+            Phase::HalfOpen => {
+                warn!("Circuit breaker probe was rate limited, reopening for {:?}", self.cooldown);
+                self.open(&mut state);
+            }
+            Phase::Open => {}
+            Phase::Closed => {
+                if state.first_rate_limited.is_none_or(|first| first.elapsed() > self.window) {
+                    state.first_rate_limited = Some(Instant::now());
+                    state.consecutive = 0;
+                }
+                state.consecutive += 1;
+                if state.consecutive >= self.threshold {
+                    warn!(
+                        "Circuit breaker open after {} consecutive rate limits, pausing requests for {:?}",
+                        state.consecutive, self.cooldown
+                    );
+                    self.open(&mut state);
+                }
+            }
+        }
+    }
+
+    /// Records a response that was not rate limited, closing a half-open circuit
+    pub(crate) fn record_allowed(&self) {
+        let mut state = self.state.lock().expect("breaker lock poisoned");
+        state.consecutive = 0;
+        state.first_rate_limited = None;
+        if state.phase == Phase::HalfOpen {
+            info!("Circuit breaker closed, resuming requests");
+            state.phase = Phase::Closed;
+            state.probe_in_flight = false;
+        }
+    }
+
+    fn open(&self, state: &mut BreakerState) {
+        state.phase = Phase::Open;
+        state.opened_at = Some(Instant::now());
+        state.probe_in_flight = false;
+        state.consecutive = 0;
+        state.first_rate_limited = None;
+    }
+
+    #[cfg(test)]
+    fn phase(&self) -> Phase {
+        self.state.lock().expect("breaker lock poisoned").phase
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn opens_after_threshold_consecutive_rate_limits() {
+        let breaker = CircuitBreaker::new(3, Duration::from_secs(60), Duration::from_secs(60));
+        breaker.record_rate_limited();
+        breaker.record_rate_limited();
+        breaker.record_allowed();
+        breaker.record_rate_limited();
+        breaker.record_rate_limited();
+        assert_eq!(breaker.phase(), Phase::Closed);
+        breaker.record_rate_limited();
+        assert_eq!(breaker.phase(), Phase::Open);
+    }
+
+    #[tokio::test]
+    async fn probe_closes_or_reopens_the_circuit() {
+        let breaker = CircuitBreaker::new(1, Duration::from_secs(60), Duration::ZERO);
+        breaker.record_rate_limited();
+        assert_eq!(breaker.phase(), Phase::Open);
+
+        breaker.acquire().await;
+        assert_eq!(breaker.phase(), Phase::HalfOpen);
+        breaker.record_rate_limited();
+        assert_eq!(breaker.phase(), Phase::Open);
+
+        breaker.acquire().await;
+        breaker.record_allowed();
+        assert_eq!(breaker.phase(), Phase::Closed);
+    }
+
+    #[test]
+    fn zero_threshold_never_opens() {
+        let breaker = CircuitBreaker::new(0, Duration::from_secs(60), Duration::from_secs(60));
+        for _ in 0..10 {
+            breaker.record_rate_limited();
+        }
+        assert_eq!(breaker.phase(), Phase::Closed);
+    }
+}
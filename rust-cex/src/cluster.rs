@@ -0,0 +1,97 @@
+use crate::{ScraperError, WalletRecord};
+use scathat_core::write_atomic;
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::Path;
+
+/// Wallets whose first incoming transaction came from the same address, a strong hint
+/// that one party controls them all
+#[derive(Debug, Serialize, PartialEq)]
+pub struct FunderCluster {
+    pub funder: String,
+    pub wallets: Vec<String>,
+    /// Every exchange the wallets were scraped for, sorted
+    pub exchange_names: Vec<String>,
+}
+
+/// Groups the wallets by `funded_by`, keeping the funders of two or more distinct wallets,
+/// largest cluster first
+pub fn funder_clusters(wallets: &[WalletRecord]) -> Vec<FunderCluster> {
+    let mut by_funder: BTreeMap<String, (BTreeSet<&str>, BTreeSet<&str>)> = BTreeMap::new();
+    for wallet in wallets {
+        let Some(funder) = &wallet.funded_by else {
+            continue;
+        };
+        let (addresses, exchanges) = by_funder.entry(funder.to_lowercase()).or_default();
+        addresses.insert(&wallet.wallet_address);
+        exchanges.insert(&wallet.exchange_name);
+    }
+
+    let mut clusters: Vec<FunderCluster> = by_funder
+        .into_iter()
+        .filter(|(_, (addresses, _))| addresses.len() > 1)
+        .map(|(funder, (addresses, exchanges))| FunderCluster {
+            funder,
+            wallets: addresses.into_iter().map(str::to_string).collect(),
+            exchange_names: exchanges.into_iter().map(str::to_string).collect(),
+        })
+        .collect();
+    // Stable, so equal sizes stay ordered by funder
+    clusters.sort_by_key(|cluster| std::cmp::Reverse(cluster.wallets.len()));
+    clusters
+}
+
+pub fn save_clusters(clusters: &[FunderCluster], path: &Path) -> Result<(), ScraperError> {
+    write_atomic(path, |writer| Ok(serde_json::to_writer_pretty(writer, clusters)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn wallet(exchange: &str, address: &str, funder: Option<&str>) -> WalletRecord {
+        WalletRecord {
+            exchange_name: exchange.to_string(),
+            wallet_address: address.to_string(),
+            source_urls: Vec::new(),
+            balance_wei: None,
+            exchange_names: Vec::new(),
+            multi_exchange: false,
+            label: None,
+            funded_by: funder.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn wallets_are_grouped_by_shared_funder() {
+        let wallets = [
+            wallet("Binance", "0x01", Some("0xAA")),
+            wallet("OKX", "0x02", Some("0xaa")),
+            wallet("Binance", "0x03", Some("0xaa")),
+            // The same address found for a second exchange is one wallet
+            wallet("OKX", "0x03", Some("0xaa")),
+            wallet("Binance", "0x04", Some("0xbb")),
+            wallet("Binance", "0x05", Some("0xbb")),
+            wallet("Binance", "0x06", Some("0xcc")),
+            wallet("Binance", "0x07", None),
+        ];
+
+        let clusters = funder_clusters(&wallets);
+
+        assert_eq!(
+            clusters,
+            [
+                FunderCluster {
+                    funder: "0xaa".to_string(),
+                    wallets: vec!["0x01".to_string(), "0x02".to_string(), "0x03".to_string()],
+                    exchange_names: vec!["Binance".to_string(), "OKX".to_string()],
+                },
+                FunderCluster {
+                    funder: "0xbb".to_string(),
+                    wallets: vec!["0x04".to_string(), "0x05".to_string()],
+                    exchange_names: vec!["Binance".to_string()],
+                },
+            ]
+        );
+    }
+}
@@ -0,0 +1,84 @@
+use reqwest::StatusCode;
+use scathat_core::CoreError;
+use std::time::Duration;
+use thiserror::Error;
+
+/// Failures surfaced by the scraper library, split so callers can tell
+/// transient conditions (rate limiting, network) from permanent ones
+#[derive(Debug, Error)]
+pub enum ScraperError {
+    #[error("HTTP request failed: {0}")]
+    Http(#[from] reqwest::Error),
+
+    #[error("rate limited{}", .retry_after.map(|d| format!(", retry after {:?}", d)).unwrap_or_default())]
+    RateLimited { retry_after: Option<Duration> },
+
+    #[error("failed to parse response: {0}")]
+    Parse(String),
+
+    #[error("blocked by a challenge page at {0}")]
+    Challenge(String),
+
+    #[error("expected an HTML page at {url} but got {content_type}")]
+    UnexpectedContentType { url: String, content_type: String },
+
+    #[error("invalid address: {0}")]
+    InvalidAddress(String),
+
+    #[error("etherscan API error: {0}")]
+    Api(String),
+
+    #[error("invalid configuration: {0}")]
+    Config(String),
+
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("JSON error: {0}")]
+    Json(#[from] serde_json::Error),
+
+    #[error("CSV error: {0}")]
+    Csv(#[from] csv::Error),
+
+    #[error("SQLite error: {0}")]
+    Sqlite(#[from] rusqlite::Error),
+
+    #[error("spreadsheet error: {0}")]
+    Xlsx(#[from] rust_xlsxwriter::XlsxError),
+
+    #[error("object storage error: {0}")]
+    Storage(String),
+}
+
+impl ScraperError {
+    /// Whether retrying the same request later may succeed
+    pub fn is_transient(&self) -> bool {
+        match self {
+            ScraperError::RateLimited { .. } | ScraperError::Challenge(_) => true,
+            ScraperError::Http(e) => e.is_timeout() || e.is_connect() || e.status().is_some_and(|s| s.is_server_error()),
+            _ => false,
+        }
+    }
+
+    /// Whether the run as configured cannot succeed, e.g. a rejected API key, a forbidden
+    /// page or a bad config, so `--fail-fast` aborts on it
+    pub fn is_fatal(&self) -> bool {
+        match self {
+            ScraperError::Api(_) | ScraperError::Config(_) => true,
+            ScraperError::Http(e) => e
+                .status()
+                .is_some_and(|status| status == StatusCode::UNAUTHORIZED || status == StatusCode::FORBIDDEN),
+            _ => false,
+        }
+    }
+}
+
+impl From<CoreError> for ScraperError {
+    fn from(e: CoreError) -> Self {
+        match e {
+            CoreError::Http(e) => ScraperError::Http(e),
+            CoreError::Config(message) => ScraperError::Config(message),
+            CoreError::Storage(message) => ScraperError::Storage(message),
+        }
+    }
+}
@@ -0,0 +1,366 @@
+//! A small expression language for post-filtering scraped wallets.
+//!
+//! Expressions compare record fields against string literals and combine the
+//! comparisons with boolean operators, for example
+//! `exchange == "Binance" && !(address starts_with "0x00")`.
+//!
+//! Fields: `exchange`, `address`, `source_url`, `label`. A wallet can have several source
+//! URLs; `source_url` comparisons hold when any of them matches, and `!=` when none equals.
+//! A wallet without a name tag has no `label`, so only `!=` holds for it.
+//! Operators: `==`, `!=`, `contains`, `starts_with`, `ends_with`.
+//! Combinators, loosest binding first: `||`, `&&`, `!`, with parentheses for grouping.
+
+use crate::WalletRecord;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+#[error("invalid filter at position {position}: {message}")]
+pub struct FilterParseError {
+    pub position: usize,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Field {
+    Exchange,
+    Address,
+    SourceUrl,
+    Label,
+}
+
+impl Field {
+    fn values<'a>(&self, record: &'a WalletRecord) -> Vec<&'a str> {
+        match self {
+            Field::Exchange => vec![&record.exchange_name],
+            Field::Address => vec![&record.wallet_address],
+            Field::SourceUrl => record.source_urls.iter().map(String::as_str).collect(),
+            Field::Label => record.label.as_deref().into_iter().collect(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Op {
+    Eq,
+    Ne,
+    Contains,
+    StartsWith,
+    EndsWith,
+}
+
+#[derive(Debug, Clone)]
+enum Expr {
+    Compare(Field, Op, String),
+    Not(Box<Expr>),
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+}
+
+impl Expr {
+    fn eval(&self, record: &WalletRecord) -> bool {
+        match self {
+            Expr::Compare(field, op, literal) => {
+                let mut values = field.values(record).into_iter();
+                match op {
+                    Op::Eq => values.any(|value| value == literal),
+                    Op::Ne => values.all(|value| value != literal),
+                    Op::Contains => values.any(|value| value.contains(literal.as_str())),
+                    Op::StartsWith => values.any(|value| value.starts_with(literal.as_str())),
+                    Op::EndsWith => values.any(|value| value.ends_with(literal.as_str())),
+                }
+            }
+            Expr::Not(inner) => !inner.eval(record),
+            Expr::And(left, right) => left.eval(record) && right.eval(record),
+            Expr::Or(left, right) => left.eval(record) || right.eval(record),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Ident(String),
+    Str(String),
+    Eq,
+    Ne,
+    And,
+    Or,
+    Not,
+    LParen,
+    RParen,
+}
+
+fn tokenize(input: &str) -> Result<Vec<(usize, Token)>, FilterParseError> {
+    let mut tokens = Vec::new();
+    let mut chars = input.char_indices().peekable();
+
+    while let Some(&(position, c)) = chars.peek() {
+        let error = |message: &str| FilterParseError {
+            position,
+            message: message.to_string(),
+        };
+
+        match c {
+            c if c.is_whitespace() => {
+                chars.next();
+            }
+            '(' | ')' => {
+                chars.next();
+                tokens.push((position, if c == '(' { Token::LParen } else { Token::RParen }));
+            }
+            '=' | '!' | '&' | '|' => {
+                chars.next();
+                let next = chars.peek().map(|&(_, next)| next);
+                let token = match (c, next) {
+                    ('=', Some('=')) => Token::Eq,
+                    ('!', Some('=')) => Token::Ne,
+                    ('&', Some('&')) => Token::And,
+                    ('|', Some('|')) => Token::Or,
+                    ('!', _) => {
+                        tokens.push((position, Token::Not));
+                        continue;
+                    }
+                    _ => return Err(error(&format!("unexpected character '{}'", c))),
+                };
+                chars.next();
+                tokens.push((position, token));
+            }
+            '"' => {
+                chars.next();
+                let mut literal = String::new();
+                loop {
+                    match chars.next() {
+                        Some((_, '"')) => break,
+                        Some((_, '\\')) => match chars.next() {
+                            Some((_, escaped)) => literal.push(escaped),
+                            None => return Err(error("unterminated string")),
+                        },
+                        Some((_, other)) => literal.push(other),
+                        None => return Err(error("unterminated string")),
+                    }
+                }
+                tokens.push((position, Token::Str(literal)));
+            }
+            c if c.is_ascii_alphabetic() || c == '_' => {
+                let mut ident = String::new();
+                while let Some(&(_, next)) = chars.peek() {
+                    if next.is_ascii_alphanumeric() || next == '_' {
+                        ident.push(next);
+                        chars.next();
+                    } else {
+                        break;
+                    }
+                }
+                tokens.push((position, Token::Ident(ident)));
+            }
+            _ => return Err(error(&format!("unexpected character '{}'", c))),
+        }
+    }
+
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<(usize, Token)>,
+    index: usize,
+    end: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.index).map(|(_, token)| token)
+    }
+
+    fn position(&self) -> usize {
+        self.tokens.get(self.index).map_or(self.end, |(position, _)| *position)
+    }
+
+    fn error(&self, message: impl Into<String>) -> FilterParseError {
+        FilterParseError {
+            position: self.position(),
+            message: message.into(),
+        }
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.index).map(|(_, token)| token.clone());
+        self.index += 1;
+        token
+    }
+
+    fn parse_or(&mut self) -> Result<Expr, FilterParseError> {
+        let mut expr = self.parse_and()?;
+        while self.peek() == Some(&Token::Or) {
+            self.next();
+            expr = Expr::Or(Box::new(expr), Box::new(self.parse_and()?));
+        }
+        Ok(expr)
+    }
+
+    fn parse_and(&mut self) -> Result<Expr, FilterParseError> {
+        let mut expr = self.parse_unary()?;
+        while self.peek() == Some(&Token::And) {
+            self.next();
+            expr = Expr::And(Box::new(expr), Box::new(self.parse_unary()?));
+        }
+        Ok(expr)
+    }
+
+    fn parse_unary(&mut self) -> Result<Expr, FilterParseError> {
+        match self.peek() {
+            Some(Token::Not) => {
+                self.next();
+                Ok(Expr::Not(Box::new(self.parse_unary()?)))
+            }
+            Some(Token::LParen) => {
+                self.next();
+                let expr = self.parse_or()?;
+                if self.peek() != Some(&Token::RParen) {
+                    return Err(self.error("expected ')'"));
+                }
+                self.next();
+                Ok(expr)
+            }
+            _ => self.parse_comparison(),
+        }
+    }
+
+    fn parse_comparison(&mut self) -> Result<Expr, FilterParseError> {
+        let field = match self.peek() {
+            Some(Token::Ident(name)) => match name.as_str() {
+                "exchange" => Field::Exchange,
+                "address" => Field::Address,
+                "source_url" => Field::SourceUrl,
+                "label" => Field::Label,
+                other => return Err(self.error(format!("unknown field '{}'", other))),
+            },
+            _ => return Err(self.error("expected a field name")),
+        };
+        self.next();
+
+        let op = match self.peek() {
+            Some(Token::Eq) => Op::Eq,
+            Some(Token::Ne) => Op::Ne,
+            Some(Token::Ident(name)) => match name.as_str() {
+                "contains" => Op::Contains,
+                "starts_with" => Op::StartsWith,
+                "ends_with" => Op::EndsWith,
+                other => return Err(self.error(format!("unknown operator '{}'", other))),
+            },
+            _ => return Err(self.error("expected an operator")),
+        };
+        self.next();
+
+        match self.peek() {
+            Some(Token::Str(_)) => match self.next() {
+                Some(Token::Str(literal)) => Ok(Expr::Compare(field, op, literal)),
+                _ => unreachable!(),
+            },
+            _ => Err(self.error("expected a quoted string")),
+        }
+    }
+}
+
+/// A parsed filter expression evaluated against each `WalletRecord`
+#[derive(Debug, Clone)]
+pub struct Filter {
+    expr: Expr,
+}
+
+impl Filter {
+    pub fn parse(input: &str) -> Result<Self, FilterParseError> {
+        let mut parser = Parser {
+            tokens: tokenize(input)?,
+            index: 0,
+            end: input.len(),
+        };
+
+        let expr = parser.parse_or()?;
+        if parser.peek().is_some() {
+            return Err(parser.error("unexpected trailing input"));
+        }
+
+        Ok(Self { expr })
+    }
+
+    pub fn matches(&self, record: &WalletRecord) -> bool {
+        self.expr.eval(record)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(exchange: &str, address: &str) -> WalletRecord {
+        WalletRecord {
+            exchange_name: exchange.to_string(),
+            wallet_address: address.to_string(),
+            source_urls: vec!["https://etherscan.io/accounts?q=binance hot wallet&p=1".to_string()],
+            balance_wei: None,
+            exchange_names: Vec::new(),
+            multi_exchange: false,
+            label: None,
+            funded_by: None,
+        }
+    }
+
+    #[test]
+    fn evaluates_comparisons_and_combinators() {
+        let filter = Filter::parse(r#"exchange == "Binance" && source_url contains "hot""#).unwrap();
+        assert!(filter.matches(&record("Binance", "0x01")));
+        assert!(!filter.matches(&record("OKX", "0x01")));
+    }
+
+    #[test]
+    fn and_binds_tighter_than_or() {
+        let filter = Filter::parse(r#"exchange == "OKX" || exchange == "MEXC" && address == "0x02""#).unwrap();
+        assert!(filter.matches(&record("OKX", "0x01")));
+        assert!(!filter.matches(&record("MEXC", "0x01")));
+        assert!(filter.matches(&record("MEXC", "0x02")));
+    }
+
+    #[test]
+    fn supports_negation_and_grouping() {
+        let filter = Filter::parse(r#"!(exchange == "OKX" || exchange != "MEXC")"#).unwrap();
+        assert!(filter.matches(&record("MEXC", "0x01")));
+        assert!(!filter.matches(&record("OKX", "0x01")));
+        assert!(!filter.matches(&record("Binance", "0x01")));
+    }
+
+    #[test]
+    fn source_url_matches_any_merged_url() {
+        let mut wallet = record("Binance", "0x01");
+        wallet.source_urls.push("https://etherscan.io/accounts?q=binance cold wallet&p=2".to_string());
+
+        assert!(Filter::parse(r#"source_url contains "cold""#).unwrap().matches(&wallet));
+        assert!(Filter::parse(r#"source_url ends_with "p=1""#).unwrap().matches(&wallet));
+        assert!(!Filter::parse(r#"source_url != "https://etherscan.io/accounts?q=binance hot wallet&p=1""#).unwrap().matches(&wallet));
+    }
+
+    #[test]
+    fn label_matches_the_name_tag() {
+        let filter = Filter::parse(r#"exchange == "Binance" && label contains "Hot""#).unwrap();
+        let mut wallet = record("Binance", "0x01");
+        assert!(!filter.matches(&wallet));
+        assert!(Filter::parse(r#"label != "Binance 14""#).unwrap().matches(&wallet));
+
+        wallet.label = Some("Binance Hot Wallet 6".to_string());
+        assert!(filter.matches(&wallet));
+    }
+
+    #[test]
+    fn rejects_unknown_fields_with_position() {
+        let error = Filter::parse(r#"exchange == "OKX" && colour == "red""#).unwrap_err();
+        assert_eq!(error.position, 21);
+        assert!(error.message.contains("colour"));
+    }
+
+    #[test]
+    fn rejects_malformed_expressions() {
+        assert!(Filter::parse(r#"exchange == "OKX"#).is_err());
+        assert!(Filter::parse(r#"exchange "OKX""#).is_err());
+        assert!(Filter::parse(r#"(exchange == "OKX""#).is_err());
+        assert!(Filter::parse(r#"exchange == "OKX" exchange"#).is_err());
+        assert!(Filter::parse("").is_err());
+    }
+}
@@ -0,0 +1,2772 @@
+use anyhow::{Context, Result};
+use clap::{Parser, ValueEnum};
+use csv::Writer;
+use futures::future::join_all;
+use indicatif::{MultiProgress, ProgressBar, ProgressStyle};
+use rand::Rng;
+use reqwest::{Client, Url};
+use scraper::{ElementRef, Html, Selector};
+use serde::{Deserialize, Serialize};
+use std::collections::hash_map::Entry;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::fs::File;
+use std::io::{BufRead, BufReader, IsTerminal, Write};
+use std::num::NonZeroUsize;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::{mpsc, watch, Mutex as AsyncMutex, Semaphore};
+use tokio::time::sleep;
+use log::{debug, info, log, warn, error, Level};
+
+mod breaker;
+mod cluster;
+mod error;
+pub mod filter;
+mod mem_cache;
+mod merge;
+mod server;
+mod stats;
+mod storage;
+mod summary;
+mod writer;
+
+use breaker::CircuitBreaker;
+pub use cluster::{funder_clusters, FunderCluster};
+pub use error::ScraperError;
+use scathat_core::etherscan::{self, ApiReply};
+use scathat_core::{
+    http, with_retry, with_rng, write_atomic, RetryError, RetryPolicy, ADDRESS_REGEX, ETHERSCAN_V2_API_URL,
+};
+pub use scathat_core::{
+    build_client, init_logging, seed_rng, to_checksum_address, user_agent_pool, HttpOptions, LogFormat, PageCache, RateLimiter,
+    RunOutcome, DEFAULT_RETRY_STATUSES, EXIT_CODES_HELP,
+};
+pub use merge::{merge_files, MergeArgs};
+pub use server::{serve, ServeArgs};
+use filter::Filter;
+use mem_cache::MemoryCache;
+pub use stats::RunStats;
+use stats::RequestCounters;
+pub use storage::S3Uploader;
+pub use summary::{file_stats, print_file_stats, FileStats, StatsArgs};
+use tiny_keccak::{Hasher, Keccak};
+pub use writer::{open_sink, WalletSink};
+use writer::{SqliteSink, WalletWriter};
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct WalletRecord {
+    pub exchange_name: String,
+    pub wallet_address: String,
+    /// Every listing page or API query the address was found on
+    pub source_urls: Vec<String>,
+    /// ETH balance in wei, filled in by `--fetch-balances`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub balance_wei: Option<String>,
+    /// Every exchange whose queries matched the address, sorted
+    #[serde(default)]
+    pub exchange_names: Vec<String>,
+    /// Whether more than one exchange matched, hinting at a mislabeled or shared address
+    #[serde(default)]
+    pub multi_exchange: bool,
+    /// Public name tag shown beside the address on the listing page, such as "Binance 14"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+    /// Sender of the first transaction into the address, filled in by `--cluster-by-funder`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub funded_by: Option<String>,
+}
+
+impl WalletRecord {
+    /// Source URLs as a single `;`-separated field for flat formats like CSV
+    pub fn joined_source_urls(&self) -> String {
+        self.source_urls.join(";")
+    }
+
+    /// Exchange names as a single `;`-separated field for flat formats like CSV
+    pub fn joined_exchange_names(&self) -> String {
+        self.exchange_names.join(";")
+    }
+
+    /// Adds the other record's source URLs, skipping ones already present, and its label if this one has none
+    fn merge_sources(&mut self, other: WalletRecord) {
+        if self.label.is_none() {
+            self.label = other.label;
+        }
+        for url in other.source_urls {
+            if !self.source_urls.contains(&url) {
+                self.source_urls.push(url);
+            }
+        }
+    }
+}
+
+/// Last completed page of each unfinished query, by exchange name and then query
+pub type PageCursors = BTreeMap<String, BTreeMap<String, u32>>;
+
+/// Called with each new wallet as a run writes it, after every filter and dedup, e.g. to feed a
+/// queue or dashboard; see `run_with_observer`. A `--dry-run` still reports the wallets it
+/// would have written.
+pub type WalletObserver = Arc<dyn Fn(&WalletRecord) + Send + Sync>;
+
+#[derive(Debug, Serialize, Deserialize, Default)]
+pub struct ScraperState {
+    pub seen_wallets: HashSet<String>,
+    /// Where an interrupted run stopped, so the next one resumes instead of starting at page 1
+    #[serde(default)]
+    pub page_cursors: PageCursors,
+    /// Sender of each looked-up wallet's first incoming transaction, which never changes
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub funders: BTreeMap<String, String>,
+}
+
+/// Output file format; without `--format` both JSON and CSV are written
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputFormat {
+    /// Pretty-printed JSON array
+    Json,
+    /// CSV with a header row
+    Csv,
+    /// One compact JSON record per line
+    Ndjson,
+    /// One checksummed address per line, nothing else
+    Addresses,
+    /// Excel workbook with a bold header row and auto-fitted columns
+    Xlsx,
+    /// SQLite database with a `wallets` table, upserted so addresses already stored are kept;
+    /// one database even with --split-by-exchange
+    Sqlite,
+}
+
+/// Where the output and state files go
+#[derive(clap::Args, Debug, Clone, Default)]
+pub struct OutputPaths {
+    /// Keep the state file and the default output files in this directory, created if missing,
+    /// instead of the working directory
+    #[arg(long)]
+    pub out_dir: Option<PathBuf>,
+
+    /// Write the JSON output here instead of cex_wallets.json; `-` writes to stdout
+    #[arg(long)]
+    pub json_out: Option<String>,
+
+    /// Write the CSV output here instead of cex_wallets.csv; `-` writes to stdout
+    #[arg(long)]
+    pub csv_out: Option<String>,
+
+    /// Write the NDJSON output here instead of cex_wallets.ndjson; `-` writes to stdout
+    #[arg(long)]
+    pub ndjson_out: Option<String>,
+
+    /// Write the address list here instead of cex_wallets.txt; `-` writes to stdout
+    #[arg(long)]
+    pub addresses_out: Option<String>,
+
+    /// Write the spreadsheet here instead of cex_wallets.xlsx; `-` writes to stdout
+    #[arg(long)]
+    pub xlsx_out: Option<String>,
+
+    /// Upsert into this SQLite database instead of cex_wallets.db; without --format sqlite it
+    /// is written alongside the other formats
+    #[arg(long)]
+    pub sqlite_out: Option<PathBuf>,
+}
+
+impl OutputPaths {
+    /// `file` within `out_dir`, or the working directory without one
+    fn in_out_dir(&self, file: &str) -> String {
+        match &self.out_dir {
+            Some(dir) => dir.join(file).to_string_lossy().into_owned(),
+            None => file.to_string(),
+        }
+    }
+
+    pub fn state_file(&self) -> PathBuf {
+        PathBuf::from(self.in_out_dir(STATE_FILE))
+    }
+
+    pub fn manifest_file(&self) -> PathBuf {
+        PathBuf::from(self.in_out_dir(MANIFEST_FILE))
+    }
+}
+
+#[derive(Parser, Debug)]
+#[command(about = "Scrape centralized exchange wallet addresses from etherscan")]
+pub struct Args {
+    /// Ignore previously seen wallets and saved page cursors, and reset the state file
+    #[arg(long)]
+    pub fresh: bool,
+
+    /// First search result page fetched per query, e.g. `--start-page 4 --end-page 6` to
+    /// backfill a range. Without it a query starts at page 1, or resumes where the last run stopped.
+    #[arg(long, alias = "restart-page")]
+    pub start_page: Option<u32>,
+
+    /// Last search result page fetched per query; a query also stops at a page with no new wallets
+    #[arg(long, alias = "max-pages", default_value_t = DEFAULT_MAX_PAGES)]
+    pub end_page: u32,
+
+    /// Stop an exchange once this many wallets are collected, leaving its remaining pages
+    /// unfetched; 0 collects everything
+    #[arg(long, default_value_t = 0)]
+    pub limit_per_exchange: usize,
+
+    /// Keep up to this many fetched pages in memory so a URL requested again during the run
+    /// isn't refetched; 0 disables the cache
+    #[arg(long, default_value_t = 0)]
+    pub mem_cache_size: usize,
+
+    /// Extra seconds to wait after each search page of an exchange; the rate limiter refills
+    /// meanwhile, so a stricter limiter only adds its shortfall. 0 leaves pacing to the limiter alone
+    #[arg(long, default_value_t = 2)]
+    pub query_delay: u64,
+
+    /// Delay each exchange's start by a random offset of up to this many seconds, so the
+    /// first requests don't all go out at once
+    #[arg(long, default_value_t = 0)]
+    pub stagger: u64,
+
+    /// Abort the run on the first fatal error, such as a rejected API key or a 403, instead of
+    /// skipping the exchange; rate limiting and other transient errors never abort
+    #[arg(long)]
+    pub fail_fast: bool,
+
+    /// Scrape at most this many exchanges at once; the rest wait for a free slot. Defaults to all
+    #[arg(long, value_parser = clap::value_parser!(u32).range(1..))]
+    pub exchange_concurrency: Option<u32>,
+
+    /// Validate mixed-case addresses with the EIP-1191 checksum for this chain id
+    #[arg(long)]
+    pub chain_id: Option<u64>,
+
+    /// Scrape this explorer instead of each exchange's own `etherscan_url` host, e.g. a mirror or a Blockscout instance
+    #[arg(long)]
+    pub base_url: Option<Url>,
+
+    /// Etherscan API key; when set, wallets are fetched from the label API instead of scraping HTML
+    #[arg(long, env = "ETHERSCAN_API_KEY", hide_env_values = true)]
+    pub api_key: Option<String>,
+
+    /// Chain id the Etherscan V2 API is queried for, in API mode and for balances
+    #[arg(long, default_value_t = 1)]
+    pub api_chain_id: u64,
+
+    /// Keep only wallets matching this expression over `exchange`, `address`, `source_url` and
+    /// `label`, e.g. `exchange == "Binance" && label contains "Hot"`
+    #[arg(long, value_parser = Filter::parse)]
+    pub filter: Option<Filter>,
+
+    /// Ceiling in seconds for the exponential retry backoff
+    #[arg(long, default_value_t = 60)]
+    pub max_backoff: u64,
+
+    /// Maximum number of attempts for each page or API request
+    #[arg(long, default_value_t = 3)]
+    pub max_retries: u32,
+
+    /// Sustained request rate per exchange; replaces the fixed delay between requests
+    #[arg(long)]
+    pub requests_per_minute: Option<f64>,
+
+    /// With --requests-per-minute, how many requests may go out back to back before the rate applies
+    #[arg(long, default_value_t = 1, requires = "requests_per_minute")]
+    pub burst: u32,
+
+    /// Consecutive 429s that pause every request; 0 disables the circuit breaker
+    #[arg(long, default_value_t = 5)]
+    pub breaker_threshold: usize,
+
+    /// Seconds within which the consecutive 429s must occur to trip the breaker
+    #[arg(long, default_value_t = 60)]
+    pub breaker_window: u64,
+
+    /// Seconds to pause all requests once the breaker trips, before a single probe request
+    #[arg(long, default_value_t = 120)]
+    pub breaker_cooldown: u64,
+
+    /// Look up the ETH balance of every new wallet through the etherscan API; needs --api-key
+    #[arg(long)]
+    pub fetch_balances: bool,
+
+    /// Drop wallets that have sent fewer than this many transactions, looked up through the
+    /// etherscan API; needs --api-key, without which every wallet is kept
+    #[arg(long)]
+    pub min_tx_count: Option<u64>,
+
+    /// Keep only wallets whose etherscan public name tag contains their exchange's name, writing
+    /// the rest to unverified_wallets.json for review; needs --api-key
+    #[arg(long)]
+    pub verify_labels: bool,
+
+    /// Look up who sent each wallet its first incoming transaction through the etherscan API
+    /// and write the wallets that share a funder to funder_clusters.json; needs --api-key
+    #[arg(long)]
+    pub cluster_by_funder: bool,
+
+    /// Output formats to write instead of both JSON and CSV, repeatable or comma separated,
+    /// e.g. `--format ndjson --format sqlite`; every format gets the same records
+    #[arg(long, value_enum, value_delimiter = ',')]
+    pub format: Vec<OutputFormat>,
+
+    #[command(flatten)]
+    pub output_paths: OutputPaths,
+
+    /// Write `wallets_<exchange>.*` files per exchange instead of the combined output
+    #[arg(long)]
+    pub split_by_exchange: bool,
+
+    /// Append new wallets to the CSV output instead of replacing it. Wallets already in the
+    /// state file are never written again, so rows accumulate across runs without repeats.
+    #[arg(long, conflicts_with = "fresh")]
+    pub append_csv: bool,
+
+    /// Scrape and report counts without writing output files, the database or the state file
+    #[arg(long)]
+    pub dry_run: bool,
+
+    /// Write a handful of known exchange wallets as sample output when scraping finds none at all
+    #[arg(long)]
+    pub write_sample_on_empty: bool,
+
+    /// Hide the progress bars shown while scraping in a terminal
+    #[arg(long)]
+    pub quiet: bool,
+
+    /// Also write the end-of-run summary as JSON to this file
+    #[arg(long)]
+    pub stats_out: Option<PathBuf>,
+
+    /// Load exchange definitions, including CSS selectors, from this TOML file instead of the built-in list
+    #[arg(long)]
+    pub config: Option<PathBuf>,
+
+    /// Read extra search queries for --queries-exchange from this file, one per line
+    #[arg(long, requires = "queries_exchange")]
+    pub queries_file: Option<PathBuf>,
+
+    /// Read extra search queries for --queries-exchange from stdin, one per line
+    #[arg(long, requires = "queries_exchange")]
+    pub queries_stdin: bool,
+
+    /// Exchange, by config key or name, that --queries-file and --queries-stdin add queries to
+    #[arg(long)]
+    pub queries_exchange: Option<String>,
+
+    /// Use only the queries read from --queries-file and --queries-stdin instead of adding them
+    #[arg(long, requires = "queries_exchange")]
+    pub replace_queries: bool,
+
+    /// Upload the output files to this S3 bucket after writing them locally
+    #[arg(long, env = "S3_BUCKET")]
+    pub s3_bucket: Option<String>,
+
+    /// Region of the S3 bucket
+    #[arg(long, env = "AWS_REGION", default_value = "us-east-1")]
+    pub s3_region: String,
+
+    /// Endpoint of an S3-compatible store such as MinIO
+    #[arg(long, env = "S3_ENDPOINT")]
+    pub s3_endpoint: Option<String>,
+
+    /// Key prefix for uploaded objects; each run is stored under `<prefix>/<timestamp>/`
+    #[arg(long, default_value = "cex-wallets")]
+    pub s3_prefix: String,
+
+    /// Exit without scraping if the last complete run finished less than this many seconds
+    /// ago with the same exchange config, as recorded in cex_manifest.json
+    #[arg(long, conflicts_with = "fresh")]
+    pub skip_if_recent: Option<u64>,
+}
+
+/// Written beside the output after a run that scraped every exchange, for `--skip-if-recent`
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RunManifest {
+    /// RFC 3339 time the run finished
+    pub completed_at: String,
+    /// `config_hash` of the exchanges and burn list the run used
+    pub config_hash: String,
+}
+
+impl RunManifest {
+    /// Whether this run finished within `window` of `now` with the same config
+    fn is_recent(&self, config_hash: &str, window: Duration, now: chrono::DateTime<chrono::Utc>) -> bool {
+        let Ok(completed_at) = chrono::DateTime::parse_from_rfc3339(&self.completed_at) else {
+            return false;
+        };
+        let age = now.signed_duration_since(completed_at).to_std().unwrap_or_default();
+        self.config_hash == config_hash && age < window
+    }
+}
+
+/// An address from the label export, with its public name tag when it has one
+#[derive(Debug, Deserialize)]
+struct AddressTag {
+    address: String,
+    #[serde(default)]
+    nametag: Option<String>,
+}
+
+/// A normal transaction from the `txlist` action; `to` is empty for contract creations
+#[derive(Debug, Deserialize)]
+struct Transaction {
+    from: String,
+    #[serde(default)]
+    to: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct AccountBalance {
+    account: String,
+    balance: String,
+}
+
+const STATE_FILE: &str = "cex_state.json";
+const MANIFEST_FILE: &str = "cex_manifest.json";
+const UNVERIFIED_OUTPUT_FILE: &str = "unverified_wallets.json";
+const CLUSTERS_OUTPUT_FILE: &str = "funder_clusters.json";
+const JSON_OUTPUT_FILE: &str = "cex_wallets.json";
+const CSV_OUTPUT_FILE: &str = "cex_wallets.csv";
+const NDJSON_OUTPUT_FILE: &str = "cex_wallets.ndjson";
+const ADDRESSES_OUTPUT_FILE: &str = "cex_wallets.txt";
+const XLSX_OUTPUT_FILE: &str = "cex_wallets.xlsx";
+const SQLITE_OUTPUT_FILE: &str = "cex_wallets.db";
+/// Default last search result page fetched for each query
+const DEFAULT_MAX_PAGES: u32 = 3;
+/// Most addresses the `balancemulti` action accepts in one call
+const BALANCE_BATCH_SIZE: usize = 20;
+/// Earliest transactions searched for the first incoming one; a fresh wallet can't send before
+/// it is funded, so this only has to skip the odd contract creation or self-transfer
+const FUNDER_LOOKUP_TXS: &str = "10";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExchangeConfig {
+    pub name: String,
+    pub etherscan_url: String,
+    pub search_queries: Vec<String>,
+    pub api_label: String,
+    #[serde(default)]
+    pub selectors: WalletSelectors,
+}
+
+/// CSS selectors for the explorer's search result pages
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WalletSelectors {
+    /// Links whose `href` contains a wallet address
+    pub wallet_link: String,
+}
+
+impl Default for WalletSelectors {
+    fn default() -> Self {
+        Self {
+            wallet_link: "a[href*='/address/']".to_string(),
+        }
+    }
+}
+
+impl WalletSelectors {
+    /// Compiles the wallet link selector, naming the offending selector on failure
+    pub fn wallet_link(&self) -> Result<Selector, ScraperError> {
+        Selector::parse(&self.wallet_link)
+            .map_err(|e| ScraperError::Config(format!("invalid wallet_link selector {:?}: {:?}", self.wallet_link, e.kind)))
+    }
+}
+
+/// Layout of a `--config` file: one `[exchanges.<key>]` table per exchange
+#[derive(Debug, Deserialize)]
+pub struct ConfigFile {
+    pub exchanges: HashMap<String, ExchangeConfig>,
+    /// Null, burn and placeholder addresses dropped from the results; replaces the defaults when given
+    #[serde(default = "default_burn_addresses")]
+    pub burn_addresses: Vec<String>,
+}
+
+/// Sentinels that show up in search results but are never exchange wallets
+const DEFAULT_BURN_ADDRESSES: [&str; 4] = [
+    "0x0000000000000000000000000000000000000000",
+    "0x000000000000000000000000000000000000dEaD",
+    "0xdEAD000000000000000042069420694206942069",
+    "0xEeeeeEeeeEeEeeEeEeEeeEEEeeeeEeeeeeeeEEeE",
+];
+
+pub fn default_burn_addresses() -> Vec<String> {
+    DEFAULT_BURN_ADDRESSES.iter().map(|address| address.to_string()).collect()
+}
+
+#[derive(Clone)]
+pub struct CEXScraper {
+    client: Client,
+    /// Waited on by every request attempt, retries included; clones share it, so each
+    /// exchange task gets its own from `for_exchange`
+    rate_limiter: Arc<AsyncMutex<RateLimiter>>,
+    chain_id: Option<u64>,
+    api_key: Option<String>,
+    /// Etherscan V2 endpoint and the `chainid` sent to it
+    api_url: String,
+    api_chain_id: u64,
+    max_backoff: Duration,
+    max_retries: u32,
+    user_agents: Vec<String>,
+    cache: Option<PageCache>,
+    /// Pages fetched earlier in this run, checked before the on-disk cache
+    mem_cache: Option<Arc<MemoryCache>>,
+    check_content_type: bool,
+    /// Error statuses a page fetch retries; any other fails the page at once
+    retry_statuses: Vec<u16>,
+    cursors: Arc<Mutex<PageCursors>>,
+    /// Funders by address, kept across runs in the state file so no wallet is looked up twice
+    funders: Arc<Mutex<BTreeMap<String, String>>>,
+    /// Overrides the cursors as the first page of every query
+    restart_page: Option<u32>,
+    max_pages: u32,
+    /// Wallets collected per exchange before its remaining pages are skipped; 0 is unlimited
+    limit_per_exchange: usize,
+    /// Pause after each search page. The rate limiter already spaces out request
+    /// starts, and tokens refill during this pause, so consecutive pages start at least
+    /// `max(limiter interval, fetch time + query_delay)` apart. Each exchange task has its
+    /// own clone of both, so neither throttles the other exchanges.
+    query_delay: Duration,
+    /// Set on Ctrl-C; scraping stops between pages and returns what it has
+    cancel: Arc<watch::Sender<bool>>,
+    /// Lowercased addresses dropped from every exchange's results
+    burn_addresses: Arc<HashSet<String>>,
+    /// Per-exchange progress bars; page-level logging drops to debug while they are shown
+    progress: Option<MultiProgress>,
+    /// Replaces the scheme and host of every `etherscan_url` when set
+    base_url: Option<Url>,
+    counters: Arc<RequestCounters>,
+    breaker: Arc<CircuitBreaker>,
+    /// Return a fatal page error at once instead of skipping the page
+    fail_fast: bool,
+}
+
+impl CEXScraper {
+    pub fn new(http: &HttpOptions, chain_id: Option<u64>, api_key: Option<String>) -> Result<Self, ScraperError> {
+        let client = build_client(http)?;
+
+        Ok(Self {
+            client,
+            rate_limiter: Arc::new(AsyncMutex::new(RateLimiter::new(http.request_delay))),
+            chain_id,
+            api_key,
+            api_url: ETHERSCAN_V2_API_URL.to_string(),
+            api_chain_id: 1,
+            max_backoff: Duration::from_secs(60),
+            max_retries: 3,
+            user_agents: http.user_agents.clone(),
+            cache: http.cache.clone(),
+            mem_cache: None,
+            check_content_type: http.check_content_type,
+            retry_statuses: http.retry_statuses.clone(),
+            cursors: Arc::default(),
+            funders: Arc::default(),
+            restart_page: None,
+            max_pages: DEFAULT_MAX_PAGES,
+            limit_per_exchange: 0,
+            query_delay: Duration::from_secs(2),
+            cancel: Arc::new(watch::Sender::new(false)),
+            burn_addresses: Arc::new(default_burn_addresses().iter().map(|address| address.to_lowercase()).collect()),
+            progress: None,
+            base_url: None,
+            counters: Arc::default(),
+            breaker: Arc::new(CircuitBreaker::new(5, Duration::from_secs(60), Duration::from_secs(120))),
+            fail_fast: false,
+        })
+    }
+
+    pub fn with_max_backoff(mut self, max_backoff: Duration) -> Self {
+        self.max_backoff = max_backoff;
+        self
+    }
+
+    pub fn with_max_retries(mut self, max_retries: u32) -> Self {
+        self.max_retries = max_retries.max(1);
+        self
+    }
+
+    pub fn with_max_pages(mut self, max_pages: u32) -> Self {
+        self.max_pages = max_pages.max(1);
+        self
+    }
+
+    /// Fails the exchange on its first fatal page error rather than skipping the page
+    pub fn with_fail_fast(mut self, fail_fast: bool) -> Self {
+        self.fail_fast = fail_fast;
+        self
+    }
+
+    pub fn with_limit_per_exchange(mut self, limit: usize) -> Self {
+        self.limit_per_exchange = limit;
+        self
+    }
+
+    pub fn with_query_delay(mut self, query_delay: Duration) -> Self {
+        self.query_delay = query_delay;
+        self
+    }
+
+    /// Queries the Etherscan V2 API at `api_url` for `chain_id`
+    pub fn with_api(mut self, api_url: &str, chain_id: u64) -> Self {
+        self.api_url = api_url.to_string();
+        self.api_chain_id = chain_id;
+        self
+    }
+
+    /// Keeps up to `size` pages in memory for the rest of the run; 0 disables the cache
+    pub fn with_mem_cache(mut self, size: usize) -> Self {
+        self.mem_cache = NonZeroUsize::new(size).map(|size| Arc::new(MemoryCache::new(size)));
+        self
+    }
+
+    pub fn with_base_url(mut self, base_url: Option<Url>) -> Self {
+        self.base_url = base_url;
+        self
+    }
+
+    /// Resumes each query after its cursor, or at `restart_page` when given
+    pub fn with_page_cursors(mut self, cursors: PageCursors, restart_page: Option<u32>) -> Self {
+        self.cursors = Arc::new(Mutex::new(cursors));
+        self.restart_page = restart_page;
+        self
+    }
+
+    /// Cursors of the queries that have not reached their last page yet
+    pub fn page_cursors(&self) -> PageCursors {
+        self.cursors.lock().expect("cursor lock poisoned").clone()
+    }
+
+    /// Reuses funders looked up by earlier runs
+    pub fn with_funders(mut self, funders: BTreeMap<String, String>) -> Self {
+        self.funders = Arc::new(Mutex::new(funders));
+        self
+    }
+
+    /// Every funder known so far, by address
+    pub fn funders(&self) -> BTreeMap<String, String> {
+        self.funders.lock().expect("funder lock poisoned").clone()
+    }
+
+    /// Stops every clone of this scraper at the next page boundary
+    pub fn cancel(&self) {
+        self.cancel.send_replace(true);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        *self.cancel.borrow()
+    }
+
+    /// Resolves once `cancel` has been called
+    pub async fn cancelled(&self) {
+        let _ = self.cancel.subscribe().wait_for(|cancelled| *cancelled).await;
+    }
+
+    /// Backoff for search page requests, doubling from one second up to `max_backoff`
+    fn retry_policy(&self) -> RetryPolicy {
+        RetryPolicy {
+            max_attempts: self.max_retries,
+            base_delay: Duration::from_secs(1),
+            max_delay: self.max_backoff,
+            jitter: true,
+        }
+    }
+
+    fn record_cursor(&self, exchange: &str, query: &str, page: u32, finished: bool) {
+        let mut cursors = self.cursors.lock().expect("cursor lock poisoned");
+        let queries = cursors.entry(exchange.to_string()).or_default();
+        if finished {
+            queries.remove(query);
+        } else {
+            queries.insert(query.to_string(), page);
+        }
+        if queries.is_empty() {
+            cursors.remove(exchange);
+        }
+    }
+
+    pub fn with_burn_addresses(mut self, addresses: &[String]) -> Self {
+        self.burn_addresses = Arc::new(addresses.iter().map(|address| address.to_lowercase()).collect());
+        self
+    }
+
+    pub fn with_progress(mut self, progress: Option<MultiProgress>) -> Self {
+        self.progress = progress;
+        self
+    }
+
+    pub fn with_rate_limiter(mut self, rate_limiter: RateLimiter) -> Self {
+        self.rate_limiter = Arc::new(AsyncMutex::new(rate_limiter));
+        self
+    }
+
+    /// A clone with its own copy of the rate limiter, so one exchange's requests don't
+    /// hold up another's
+    async fn for_exchange(&self) -> Self {
+        let rate_limiter = self.rate_limiter.lock().await.clone();
+        Self { rate_limiter: Arc::new(AsyncMutex::new(rate_limiter)), ..self.clone() }
+    }
+
+    /// Pauses every request for `cooldown` after `threshold` consecutive 429s within `window`
+    pub fn with_circuit_breaker(mut self, threshold: usize, window: Duration, cooldown: Duration) -> Self {
+        self.breaker = Arc::new(CircuitBreaker::new(threshold, window, cooldown));
+        self
+    }
+
+    /// Collects an exchange's wallets from the label API when there is an API key, or by
+    /// scraping its search pages, leaving out burn and null addresses
+    pub async fn scrape_exchange_wallets(&mut self, config: &ExchangeConfig) -> Result<Vec<WalletRecord>, ScraperError> {
+        let wallets = match self.api_key.clone() {
+            Some(api_key) => {
+                let mut cancel = self.cancel.subscribe();
+                tokio::select! {
+                    wallets = self.fetch_exchange_wallets_api(config, &api_key) => wallets?,
+                    _ = cancel.wait_for(|cancelled| *cancelled) => Vec::new(),
+                }
+            }
+            None => self.scrape_exchange_pages(config).await?,
+        };
+
+        let count = wallets.len();
+        let mut wallets: Vec<_> = wallets
+            .into_iter()
+            .filter(|wallet| !self.burn_addresses.contains(&wallet.wallet_address.to_lowercase()))
+            .collect();
+        if wallets.len() < count {
+            info!("Dropped {} burn or null addresses for {}", count - wallets.len(), config.name);
+        }
+        if self.limit_per_exchange > 0 {
+            wallets.truncate(self.limit_per_exchange);
+        }
+        Ok(wallets)
+    }
+
+    /// Scrapes every query page for an exchange, resuming each query after its cursor.
+    ///
+    /// Individual page failures are logged and skipped; an error is only returned
+    /// when every page failed, carrying the last failure. A query stops at its first
+    /// page without new wallets, since etherscan repeats the last page past the end,
+    /// or at `max_pages`. When cancelled or past `limit_per_exchange`, returns the wallets of
+    /// the pages completed so far.
+    async fn scrape_exchange_pages(&mut self, config: &ExchangeConfig) -> Result<Vec<WalletRecord>, ScraperError> {
+        let wallet_selector = config.selectors.wallet_link()?;
+        let etherscan_url = match &self.base_url {
+            Some(base_url) => rebase_url(&config.etherscan_url, base_url),
+            None => config.etherscan_url.clone(),
+        };
+        let mut all_wallets = Vec::new();
+        let page_level = if self.progress.is_some() { Level::Debug } else { Level::Info };
+
+        // Create futures for parallel execution
+        let mut futures = Vec::new();
+        
+        let cursors = self.page_cursors().remove(&config.name).unwrap_or_default();
+        for query in &config.search_queries {
+            let first_page = self.restart_page.unwrap_or_else(|| cursors.get(query).map_or(1, |page| page + 1)).max(1);
+            if first_page > 1 {
+                info!("Resuming {} query: {} at page {}", config.name, query, first_page);
+            }
+            for page in first_page..=self.max_pages {
+                let url = format!("{}?q={}&p={}", etherscan_url, query, page);
+                let client = self.client.clone();
+                let exchange_name = config.name.clone();
+                let chain_id = self.chain_id;
+                let retry = self.retry_policy();
+                let user_agents = self.user_agents.clone();
+                let wallet_selector = wallet_selector.clone();
+                let counters = self.counters.clone();
+                let breaker = self.breaker.clone();
+                let cache = self.cache.clone();
+                let mem_cache = self.mem_cache.clone();
+                let check_content_type = self.check_content_type;
+                let retry_statuses = self.retry_statuses.clone();
+                let rate_limiter = self.rate_limiter.clone();
+                
+                futures.push((query.as_str(), page, async move {
+                    log!(page_level, "Scraping {}: {} (page {})", exchange_name, url, page);
+                    
+                    // The wallets on the page, and the query's page count when the footer states it
+                    let parse = |body: &str| {
+                        if body.contains("No matching accounts found") {
+                            log!(page_level, "No results found for {} query: {} (page {})", exchange_name, query, page);
+                            return (Vec::new(), None);
+                        }
+                        
+                        let wallets = Self::extract_wallets_from_html_static(body, &wallet_selector, &exchange_name, &url, chain_id);
+                        log!(page_level, "Found {} wallets for {} query: {} (page {})", wallets.len(), exchange_name, query, page);
+                        (wallets, total_pages(body, page))
+                    };
+                    
+                    if let Some(body) = mem_cache.as_ref().and_then(|mem_cache| mem_cache.get(&url)) {
+                        return Ok(parse(&body));
+                    }
+                    if let Some(body) = cache.as_ref().and_then(|cache| cache.get(&url)) {
+                        if let Some(mem_cache) = &mem_cache {
+                            mem_cache.put(&url, &body);
+                        }
+                        return Ok(parse(&body));
+                    }
+                    
+                    let (client, url, user_agents, counters, breaker, retry_statuses, rate_limiter) =
+                        (&client, &url, &user_agents, &counters, &breaker, &retry_statuses, &rate_limiter);
+                    let fetched = with_retry(&retry, &format!("Fetching {}", url), move |_| async move {
+                        rate_limiter.lock().await.wait().await;
+                        breaker.acquire().await;
+                        counters.request();
+                        let response = http::with_user_agent(client.get(url), user_agents).send().await;
+                        match &response {
+                            Ok(resp) if resp.status() == 429 => breaker.record_rate_limited(),
+                            _ => breaker.record_allowed(),
+                        }
+                        match response {
+                            Ok(resp) if resp.status().is_success() || http::has_challenge_header(&resp) => {
+                                let challenged = http::has_challenge_header(&resp);
+                                if let Some(content_type) = http::non_html_content_type(&resp).filter(|_| check_content_type && !challenged) {
+                                    counters.failure();
+                                    warn!("Skipping {}: expected HTML but got {}", url, content_type);
+                                    return Err(RetryError::Fatal(ScraperError::UnexpectedContentType { url: url.clone(), content_type }));
+                                }
+                                let body = resp.text().await.unwrap_or_default();
+                                
+                                // A block looks like an empty result, so retry instead of trusting it
+                                if challenged || http::is_challenge_body(&body) {
+                                    counters.failure();
+                                    return Err(RetryError::Retryable(ScraperError::Challenge(url.clone())));
+                                }
+                                
+                                counters.success();
+                                Ok(body)
+                            }
+                            Ok(resp) if retry_statuses.contains(&resp.status().as_u16()) => {
+                                if resp.status() == 429 {
+                                    counters.rate_limited();
+                                    Err(RetryError::Retryable(ScraperError::RateLimited { retry_after: http::retry_after(&resp) }))
+                                } else {
+                                    counters.failure();
+                                    Err(RetryError::Retryable(resp.error_for_status().expect_err("status is not a success").into()))
+                                }
+                            }
+                            // Statuses such as 404 would only come back the same
+                            Ok(resp) => {
+                                counters.failure();
+                                warn!("Failed to fetch {}: {}", url, resp.status());
+                                Err(RetryError::Fatal(resp.error_for_status().expect_err("status is not a success").into()))
+                            }
+                            Err(e) => {
+                                counters.failure();
+                                Err(RetryError::Retryable(e.into()))
+                            }
+                        }
+                    })
+                    .await;
+                    
+                    match fetched {
+                        Ok(body) => {
+                            if let Some(cache) = &cache {
+                                cache.put(url, &body);
+                            }
+                            if let Some(mem_cache) = &mem_cache {
+                                mem_cache.put(url, &body);
+                            }
+                            Ok(parse(&body))
+                        }
+                        Err(RetryError::Fatal(e)) => Err(e),
+                        Err(RetryError::Retryable(e)) => {
+                            warn!("All retries failed for {}: {}", exchange_name, url);
+                            counters.retries_exhausted();
+                            Err(e)
+                        }
+                    }
+                }));
+            }
+        }
+
+        // Fetch the pages one at a time; each attempt waits on the rate limiter
+        let page_count = futures.len();
+        let bar = self.progress.as_ref().map(|progress| {
+            let bar = progress.add(ProgressBar::new(page_count as u64));
+            bar.set_style(
+                ProgressStyle::with_template("{prefix:>12} [{bar:30}] {pos}/{len} pages, {msg}")
+                    .expect("progress template is valid")
+                    .progress_chars("=> "),
+            );
+            bar.set_prefix(config.name.clone());
+            bar.set_message("0 wallets");
+            bar
+        });
+        let mut failures = Vec::new();
+        let mut fetched = 0;
+        let mut finished_queries = HashSet::new();
+        // Queries with a failed page keep their cursor before it, so the next run fetches it again
+        let mut stalled_queries = HashSet::new();
+        let mut query_wallets: HashMap<&str, HashSet<String>> = HashMap::new();
+        let mut cancel = self.cancel.subscribe();
+        for (query, page, future) in futures {
+            if finished_queries.contains(query) {
+                if let Some(bar) = &bar {
+                    bar.inc(1);
+                }
+                continue;
+            }
+            
+            let result = tokio::select! {
+                result = future => result,
+                _ = cancel.wait_for(|cancelled| *cancelled) => {
+                    info!("Stopping {} at query: {} (page {})", config.name, query, page);
+                    break;
+                }
+            };
+            fetched += 1;
+            
+            // Nothing lies beyond a page without new wallets, so the query starts over next run
+            let mut reached_end = false;
+            match result {
+                Ok((wallets, total_pages)) => {
+                    let seen = query_wallets.entry(query).or_default();
+                    let new_wallets: Vec<_> = wallets
+                        .into_iter()
+                        .filter(|wallet| seen.insert(wallet.wallet_address.clone()))
+                        .collect();
+                    // The footer's page count is exact; without one, the first page that adds nothing ends the query
+                    reached_end = new_wallets.is_empty() || total_pages.is_some_and(|total| page >= total);
+                    if let Some(total) = total_pages.filter(|total| page == 1 && *total > self.max_pages) {
+                        info!("{} query: {} has {} pages, fetching only {}", config.name, query, total, self.max_pages);
+                    }
+                    all_wallets.extend(new_wallets);
+                    if !stalled_queries.contains(query) {
+                        self.record_cursor(&config.name, query, page, reached_end || page == self.max_pages);
+                    }
+                }
+                Err(e) if self.fail_fast && e.is_fatal() => {
+                    if let Some(bar) = &bar {
+                        bar.abandon();
+                    }
+                    return Err(e);
+                }
+                Err(e) => {
+                    stalled_queries.insert(query);
+                    failures.push(e);
+                }
+            }
+            if reached_end {
+                finished_queries.insert(query);
+            }
+            
+            if let Some(bar) = &bar {
+                bar.inc(1);
+                bar.set_message(format!("{} wallets", all_wallets.len()));
+            }
+            // Pages are fetched one at a time, so the one in flight has finished and
+            // dropping the rest means they are never requested
+            if self.limit_per_exchange > 0 && all_wallets.len() >= self.limit_per_exchange {
+                info!("Reached {} wallets for {}, skipping its remaining pages", self.limit_per_exchange, config.name);
+                break;
+            }
+            if !self.query_delay.is_zero() {
+                sleep(self.query_delay).await;
+            }
+        }
+        if let Some(bar) = bar {
+            bar.finish();
+        }
+
+        if fetched > 0 && failures.len() == fetched {
+            return Err(failures.pop().expect("every page failed"));
+        }
+
+        info!("Total wallets found for {}: {}", config.name, all_wallets.len());
+        Ok(all_wallets)
+    }
+
+    async fn fetch_exchange_wallets_api(&mut self, config: &ExchangeConfig, api_key: &str) -> Result<Vec<WalletRecord>, ScraperError> {
+        // The key is sent as a query parameter but kept out of the recorded source URL
+        let source_url = format!(
+            "{}?chainid={}&module=nametag&action=exportaddresstags&label={}",
+            self.api_url, self.api_chain_id, config.api_label
+        );
+        info!("Fetching {} wallets from etherscan label API: {}", config.name, config.api_label);
+
+        let params = [("module", "nametag"), ("action", "exportaddresstags"), ("label", config.api_label.as_str())];
+        match self.query_api(&params, api_key, &config.name).await? {
+            Some(result) => {
+                let wallets = Self::parse_address_tags(result, &config.name, &source_url, self.chain_id)?;
+                info!("Found {} wallets for {} via API", wallets.len(), config.name);
+                Ok(wallets)
+            }
+            None => {
+                info!("No labelled addresses for {} ({})", config.name, config.api_label);
+                Ok(Vec::new())
+            }
+        }
+    }
+
+    /// Calls the Etherscan V2 API, retrying rate limiting, network errors and the statuses
+    /// search pages retry, with the same backoff.
+    ///
+    /// Returns the `result` payload, or `None` when the API found no records.
+    async fn query_api(&mut self, params: &[(&str, &str)], api_key: &str, what: &str) -> Result<Option<serde_json::Value>, ScraperError> {
+        let (client, breaker, counters, user_agents, retry_statuses, rate_limiter) =
+            (&self.client, &self.breaker, &self.counters, &self.user_agents, &self.retry_statuses, &self.rate_limiter);
+        let (api_url, api_chain_id) = (&self.api_url, self.api_chain_id);
+        let reply = with_retry(&self.retry_policy(), &format!("API request for {}", what), move |_| async move {
+            rate_limiter.lock().await.wait().await;
+            breaker.acquire().await;
+            counters.request();
+            let request = etherscan::v2_request(client, api_url, api_chain_id, api_key, params);
+            let body: serde_json::Value = match http::with_user_agent(request, user_agents).send().await {
+                Ok(resp) if resp.status().is_success() => match resp.json().await {
+                    Ok(body) => body,
+                    Err(e) => {
+                        breaker.record_allowed();
+                        counters.failure();
+                        return Err(RetryError::Retryable(e.into()));
+                    }
+                },
+                Ok(resp) if retry_statuses.contains(&resp.status().as_u16()) => {
+                    if resp.status() == 429 {
+                        breaker.record_rate_limited();
+                        counters.rate_limited();
+                        return Err(RetryError::Retryable(ScraperError::RateLimited { retry_after: http::retry_after(&resp) }));
+                    }
+                    breaker.record_allowed();
+                    counters.failure();
+                    return Err(RetryError::Retryable(resp.error_for_status().expect_err("status is not a success").into()));
+                }
+                // Statuses such as 401 would only come back the same
+                Ok(resp) => {
+                    if resp.status() == 429 {
+                        breaker.record_rate_limited();
+                    } else {
+                        breaker.record_allowed();
+                    }
+                    counters.failure();
+                    return Err(RetryError::Fatal(resp.error_for_status().expect_err("status is not a success").into()));
+                }
+                Err(e) => {
+                    breaker.record_allowed();
+                    counters.failure();
+                    return Err(RetryError::Retryable(e.into()));
+                }
+            };
+            
+            match ApiReply::from_body(body) {
+                ApiReply::Records(result) => {
+                    breaker.record_allowed();
+                    counters.success();
+                    Ok(Some(result))
+                }
+                ApiReply::NoRecords => {
+                    breaker.record_allowed();
+                    counters.success();
+                    Ok(None)
+                }
+                ApiReply::RateLimited => {
+                    breaker.record_rate_limited();
+                    counters.rate_limited();
+                    Err(RetryError::Retryable(ScraperError::RateLimited { retry_after: None }))
+                }
+                ApiReply::Error(message) => {
+                    breaker.record_allowed();
+                    counters.failure();
+                    Err(RetryError::Fatal(ScraperError::Api(format!("{}: {}", what, message))))
+                }
+            }
+        })
+        .await;
+        
+        match reply {
+            Ok(result) => Ok(result),
+            Err(RetryError::Fatal(e)) => Err(e),
+            Err(RetryError::Retryable(e)) => {
+                warn!("All retries failed for API request for {}", what);
+                self.counters.retries_exhausted();
+                Err(e)
+            }
+        }
+    }
+
+    /// Fills in `balance_wei` with `balancemulti` lookups of up to 20 addresses each.
+    ///
+    /// Does nothing without an API key. A failed batch is logged and its wallets keep `None`.
+    pub async fn fetch_balances(&mut self, wallets: &mut [WalletRecord]) {
+        let Some(api_key) = self.api_key.clone() else {
+            return;
+        };
+
+        for batch in wallets.chunks_mut(BALANCE_BATCH_SIZE) {
+            let addresses: Vec<&str> = batch.iter().map(|wallet| wallet.wallet_address.as_str()).collect();
+            match self.query_balances(&addresses.join(","), &api_key).await {
+                Ok(balances) => {
+                    for wallet in batch.iter_mut() {
+                        wallet.balance_wei = balances.get(&wallet.wallet_address.to_lowercase()).cloned();
+                    }
+                }
+                Err(e) => warn!("Failed to fetch balances for {} wallets: {}", batch.len(), e),
+            }
+        }
+    }
+
+    /// Keeps the wallets that have sent at least `min_tx_count` transactions, going by each
+    /// address's nonce.
+    ///
+    /// Keeps everything without an API key. A wallet whose lookup fails is kept and logged.
+    pub async fn filter_by_tx_count(&mut self, wallets: Vec<WalletRecord>, min_tx_count: u64) -> Vec<WalletRecord> {
+        let Some(api_key) = self.api_key.clone() else {
+            return wallets;
+        };
+
+        let mut kept = Vec::with_capacity(wallets.len());
+        for wallet in wallets {
+            if self.is_cancelled() {
+                kept.push(wallet);
+                continue;
+            }
+            match self.query_tx_count(&wallet.wallet_address, &api_key).await {
+                Ok(count) if count < min_tx_count => {
+                    debug!("Dropping {} with {} transactions", wallet.wallet_address, count);
+                }
+                Ok(_) => kept.push(wallet),
+                Err(e) => {
+                    warn!("Failed to fetch the transaction count of {}: {}", wallet.wallet_address, e);
+                    kept.push(wallet);
+                }
+            }
+        }
+        kept
+    }
+
+    /// Splits wallets into those whose public name tag contains `exchange_name` and the rest,
+    /// recording each tag found as the wallet's label.
+    ///
+    /// Without an API key every wallet is unverified. A wallet whose lookup fails is logged
+    /// and left unverified.
+    pub async fn verify_labels(&mut self, wallets: Vec<WalletRecord>, exchange_name: &str) -> (Vec<WalletRecord>, Vec<WalletRecord>) {
+        let Some(api_key) = self.api_key.clone() else {
+            return (Vec::new(), wallets);
+        };
+
+        let (mut verified, mut unverified) = (Vec::new(), Vec::new());
+        for mut wallet in wallets {
+            if self.is_cancelled() {
+                unverified.push(wallet);
+                continue;
+            }
+            match self.query_nametag(&wallet.wallet_address, &api_key).await {
+                Ok(Some(nametag)) => {
+                    let matches = label_matches(&nametag, exchange_name);
+                    wallet.label = Some(nametag);
+                    if matches {
+                        verified.push(wallet);
+                    } else {
+                        unverified.push(wallet);
+                    }
+                }
+                Ok(None) => unverified.push(wallet),
+                Err(e) => {
+                    warn!("Failed to fetch the name tag of {}: {}", wallet.wallet_address, e);
+                    unverified.push(wallet);
+                }
+            }
+        }
+        (verified, unverified)
+    }
+
+    /// Fills in `funded_by` from the cache, or else from the address's earliest transactions.
+    ///
+    /// Does nothing without an API key. A wallet whose lookup fails is logged and keeps `None`.
+    pub async fn fetch_funders(&mut self, wallets: &mut [WalletRecord]) {
+        let Some(api_key) = self.api_key.clone() else {
+            return;
+        };
+
+        for wallet in wallets {
+            let cached = self.funders.lock().expect("funder lock poisoned").get(&wallet.wallet_address).cloned();
+            if cached.is_some() {
+                wallet.funded_by = cached;
+                continue;
+            }
+            if self.is_cancelled() {
+                continue;
+            }
+            match self.query_funder(&wallet.wallet_address, &api_key).await {
+                Ok(Some(funder)) => {
+                    self.funders.lock().expect("funder lock poisoned").insert(wallet.wallet_address.clone(), funder.clone());
+                    wallet.funded_by = Some(funder);
+                }
+                Ok(None) => debug!("No incoming transaction found for {}", wallet.wallet_address),
+                Err(e) => warn!("Failed to fetch the funder of {}: {}", wallet.wallet_address, e),
+            }
+        }
+    }
+
+    /// Sender of the earliest transaction into an address, checksummed
+    async fn query_funder(&mut self, address: &str, api_key: &str) -> Result<Option<String>, ScraperError> {
+        let params = [
+            ("module", "account"),
+            ("action", "txlist"),
+            ("address", address),
+            ("startblock", "0"),
+            ("page", "1"),
+            ("offset", FUNDER_LOOKUP_TXS),
+            ("sort", "asc"),
+        ];
+        let Some(result) = self.query_api(&params, api_key, "funder lookup").await? else {
+            return Ok(None);
+        };
+        let transactions: Vec<Transaction> = serde_json::from_value(result)
+            .map_err(|e| ScraperError::Parse(format!("unexpected transaction list result: {}", e)))?;
+        Ok(transactions
+            .into_iter()
+            .find(|tx| tx.to.eq_ignore_ascii_case(address) && !tx.from.eq_ignore_ascii_case(address))
+            .map(|tx| to_checksum_address(&tx.from, self.chain_id).unwrap_or(tx.from)))
+    }
+
+    /// Public name tag of an address, if it has one
+    async fn query_nametag(&mut self, address: &str, api_key: &str) -> Result<Option<String>, ScraperError> {
+        let params = [("module", "nametag"), ("action", "getaddresstag"), ("address", address)];
+        let Some(result) = self.query_api(&params, api_key, "name tag lookup").await? else {
+            return Ok(None);
+        };
+        let tags: Vec<AddressTag> = serde_json::from_value(result)
+            .map_err(|e| ScraperError::Parse(format!("unexpected name tag API result: {}", e)))?;
+        Ok(tags.into_iter().find_map(|tag| tag.nametag.filter(|nametag| !nametag.is_empty())))
+    }
+
+    /// Outbound transaction count of an address, i.e. its nonce
+    async fn query_tx_count(&mut self, address: &str, api_key: &str) -> Result<u64, ScraperError> {
+        let params = [("module", "proxy"), ("action", "eth_getTransactionCount"), ("address", address), ("tag", "latest")];
+        let result = self.query_api(&params, api_key, "transaction count").await?;
+        result
+            .as_ref()
+            .and_then(|count| count.as_str())
+            .and_then(|count| u64::from_str_radix(count.trim_start_matches("0x"), 16).ok())
+            .ok_or_else(|| ScraperError::Parse(format!("unexpected transaction count result: {:?}", result)))
+    }
+
+    /// Returns balances keyed by lowercase address
+    async fn query_balances(&mut self, addresses: &str, api_key: &str) -> Result<HashMap<String, String>, ScraperError> {
+        let params = [("module", "account"), ("action", "balancemulti"), ("address", addresses), ("tag", "latest")];
+        let Some(result) = self.query_api(&params, api_key, "balance lookup").await? else {
+            return Ok(HashMap::new());
+        };
+        let balances: Vec<AccountBalance> = serde_json::from_value(result)
+            .map_err(|e| ScraperError::Parse(format!("unexpected balance API result: {}", e)))?;
+        Ok(balances
+            .into_iter()
+            .map(|balance| (balance.account.to_lowercase(), balance.balance))
+            .collect())
+    }
+
+    fn parse_address_tags(result: serde_json::Value, exchange_name: &str, source_url: &str, chain_id: Option<u64>) -> Result<Vec<WalletRecord>, ScraperError> {
+        let tags: Vec<AddressTag> = serde_json::from_value(result)
+            .map_err(|e| ScraperError::Parse(format!("unexpected label API result: {}", e)))?;
+
+        Ok(tags
+            .into_iter()
+            .filter(|tag| Self::is_valid_ethereum_address(&tag.address, chain_id))
+            .filter_map(|tag| {
+                Some(WalletRecord {
+                    exchange_name: exchange_name.to_string(),
+                    wallet_address: to_checksum_address(&tag.address, chain_id)?,
+                    source_urls: vec![source_url.to_string()],
+                    balance_wei: None,
+                    exchange_names: Vec::new(),
+                    multi_exchange: false,
+                    label: tag.nametag.filter(|nametag| !nametag.is_empty()),
+                    funded_by: None,
+                })
+            })
+            .collect())
+    }
+
+    pub fn extract_wallets_from_html_static(html: &str, wallet_selector: &Selector, exchange_name: &str, source_url: &str, chain_id: Option<u64>) -> Vec<WalletRecord> {
+        let document = Html::parse_document(html);
+
+        let mut wallets = Vec::new();
+        let mut invalid = 0;
+
+        for element in document.select(wallet_selector) {
+            if let Some(href) = element.value().attr("href") {
+                if let Some(address) = href_address(href) {
+                    let address = address.to_string();
+                    
+                    if !Self::is_valid_ethereum_address(&address, chain_id) {
+                        invalid += 1;
+                        continue;
+                    }
+                    
+                    // Pages link addresses in whatever case they like; emit one canonical form
+                    if let Some(wallet_address) = to_checksum_address(&address, chain_id) {
+                        let label = wallet_label(element);
+                        if let Some(label) = label.as_deref().filter(|label| !label_matches(label, exchange_name)) {
+                            warn!("{} is labelled {:?} on {} but was found for {}; check it by hand", wallet_address, label, source_url, exchange_name);
+                        }
+                        wallets.push(WalletRecord {
+                            exchange_name: exchange_name.to_string(),
+                            wallet_address,
+                            source_urls: vec![source_url.to_string()],
+                            balance_wei: None,
+                            exchange_names: Vec::new(),
+                            multi_exchange: false,
+                            label,
+                            funded_by: None,
+                        });
+                    }
+                }
+            }
+        }
+
+        if invalid > 0 {
+            warn!("Dropped {} addresses with an invalid checksum from {}", invalid, source_url);
+        }
+        wallets
+    }
+
+    pub fn is_valid_ethereum_address(address: &str, chain_id: Option<u64>) -> bool {
+        scathat_core::is_valid_ethereum_address(address, chain_id)
+    }
+
+    /// Checks the mixed-case checksum of an address with EIP-55, or EIP-1191 given a chain id
+    pub fn verify_checksum(address: &str, chain_id: Option<u64>) -> bool {
+        scathat_core::verify_checksum(address, chain_id)
+    }
+
+    pub async fn save_to_json(&self, wallets: &[WalletRecord], filename: &str) -> Result<(), ScraperError> {
+        write_atomic::<ScraperError>(Path::new(filename), |writer| Ok(serde_json::to_writer_pretty(writer, wallets)?))?;
+        info!("Saved {} wallets to {}", wallets.len(), filename);
+        Ok(())
+    }
+
+    /// Replaces `filename` with the wallets, for one-shot runs
+    pub async fn save_to_csv(&self, wallets: &[WalletRecord], filename: &str) -> Result<(), ScraperError> {
+        write_atomic(Path::new(filename), |file| {
+            let mut writer = Writer::from_writer(file);
+            writer.write_record(writer::CSV_HEADER)?;
+            Self::write_csv_rows(&mut writer, wallets)
+        })?;
+        info!("Saved {} wallets to {}", wallets.len(), filename);
+        Ok(())
+    }
+
+    /// Appends the wallets to `filename`, writing the header only if the file is new or empty.
+    ///
+    /// Rows are not checked against the file, so pass only wallets missing from the state's
+    /// `seen_wallets` to keep repeats out.
+    pub async fn save_to_csv_append(&self, wallets: &[WalletRecord], filename: &str) -> Result<(), ScraperError> {
+        let mut writer = writer::csv_appender(filename)?;
+        Self::write_csv_rows(&mut writer, wallets)?;
+        info!("Appended {} wallets to {}", wallets.len(), filename);
+        Ok(())
+    }
+
+    fn write_csv_rows<W: Write>(writer: &mut Writer<W>, wallets: &[WalletRecord]) -> Result<(), ScraperError> {
+        for wallet in wallets {
+            writer.write_record([
+                wallet.exchange_name.as_str(),
+                &wallet.wallet_address,
+                &wallet.joined_source_urls(),
+                wallet.balance_wei.as_deref().unwrap_or_default(),
+                &wallet.joined_exchange_names(),
+                if wallet.multi_exchange { "true" } else { "false" },
+                wallet.label.as_deref().unwrap_or_default(),
+                wallet.funded_by.as_deref().unwrap_or_default(),
+            ])?;
+        }
+        writer.flush()?;
+        Ok(())
+    }
+
+    /// Writes one compact JSON record per line so the file can be streamed
+    pub async fn save_to_ndjson(&self, wallets: &[WalletRecord], filename: &str) -> Result<(), ScraperError> {
+        self.save_to_sink(wallets, open_sink(OutputFormat::Ndjson, filename, false)?).await?;
+        info!("Saved {} wallets to {}", wallets.len(), filename);
+        Ok(())
+    }
+
+    /// Writes each distinct address once per line, for diffing against other address lists
+    pub async fn save_addresses(&self, wallets: &[WalletRecord], filename: &str) -> Result<(), ScraperError> {
+        let mut written = HashSet::new();
+        write_atomic::<ScraperError>(Path::new(filename), |writer| {
+            for wallet in wallets {
+                if written.insert(wallet.wallet_address.as_str()) {
+                    writeln!(writer, "{}", wallet.wallet_address)?;
+                }
+            }
+            Ok(())
+        })?;
+        info!("Saved {} addresses to {}", written.len(), filename);
+        Ok(())
+    }
+
+    /// Writes the wallets as a spreadsheet with a header row, one wallet per row
+    pub async fn save_to_xlsx(&self, wallets: &[WalletRecord], filename: &str) -> Result<(), ScraperError> {
+        self.save_to_sink(wallets, open_sink(OutputFormat::Xlsx, filename, false)?).await?;
+        info!("Saved {} wallets to {}", wallets.len(), filename);
+        Ok(())
+    }
+
+    /// Upserts wallets into a `wallets` table, ignoring addresses already stored
+    pub async fn save_to_sqlite(&self, wallets: &[WalletRecord], path: &Path) -> Result<(), ScraperError> {
+        let mut sink = SqliteSink::open(path)?;
+        sink.write(wallets)?;
+        info!("Saved {} of {} wallets to {}", sink.inserted, wallets.len(), path.display());
+        Ok(())
+    }
+
+    /// Writes the wallets to any sink, such as one from `open_sink` or the caller's own
+    pub async fn save_to_sink(&self, wallets: &[WalletRecord], mut sink: Box<dyn WalletSink>) -> Result<(), ScraperError> {
+        sink.write(wallets)?;
+        sink.finish()
+    }
+}
+
+/// The requested formats without repeats, or JSON and CSV when none were requested, plus
+/// SQLite when `--sqlite-out` is given
+fn output_formats(formats: &[OutputFormat], paths: &OutputPaths) -> Vec<OutputFormat> {
+    let mut resolved = if formats.is_empty() { vec![OutputFormat::Json, OutputFormat::Csv] } else { Vec::new() };
+    let sqlite = paths.sqlite_out.is_some().then_some(OutputFormat::Sqlite);
+    for &format in formats.iter().chain(&sqlite) {
+        if !resolved.contains(&format) {
+            resolved.push(format);
+        }
+    }
+    resolved
+}
+
+/// Output files for each format.
+///
+/// With an exchange name the files are `wallets_<exchange>.*` using the lowercased,
+/// sanitized name; otherwise they are the combined files at `paths`, or `cex_wallets.*`.
+/// Default names are placed in `paths.out_dir`; paths given explicitly are used as they are.
+fn output_files(formats: &[OutputFormat], exchange: Option<&str>, paths: &OutputPaths) -> Vec<(OutputFormat, String)> {
+    formats
+        .iter()
+        .map(|&format| {
+            let filename = match (exchange, format) {
+                // Rows carry their exchange, so one database serves split output too
+                (_, OutputFormat::Sqlite) => match &paths.sqlite_out {
+                    Some(path) => path.to_string_lossy().into_owned(),
+                    None => paths.in_out_dir(SQLITE_OUTPUT_FILE),
+                },
+                (None, OutputFormat::Json) => paths.json_out.clone().unwrap_or_else(|| paths.in_out_dir(JSON_OUTPUT_FILE)),
+                (None, OutputFormat::Csv) => paths.csv_out.clone().unwrap_or_else(|| paths.in_out_dir(CSV_OUTPUT_FILE)),
+                (None, OutputFormat::Ndjson) => paths.ndjson_out.clone().unwrap_or_else(|| paths.in_out_dir(NDJSON_OUTPUT_FILE)),
+                (None, OutputFormat::Addresses) => paths.addresses_out.clone().unwrap_or_else(|| paths.in_out_dir(ADDRESSES_OUTPUT_FILE)),
+                (None, OutputFormat::Xlsx) => paths.xlsx_out.clone().unwrap_or_else(|| paths.in_out_dir(XLSX_OUTPUT_FILE)),
+                (Some(exchange), format) => {
+                    let extension = match format {
+                        OutputFormat::Json => "json",
+                        OutputFormat::Csv => "csv",
+                        OutputFormat::Ndjson => "ndjson",
+                        OutputFormat::Addresses => "txt",
+                        OutputFormat::Xlsx => "xlsx",
+                        OutputFormat::Sqlite => "db",
+                    };
+                    paths.in_out_dir(&format!("wallets_{}.{}", exchange_slug(exchange), extension))
+                }
+            };
+            (format, filename)
+        })
+        .collect()
+}
+
+/// Moves a config URL onto another explorer, keeping its path
+fn rebase_url(url: &str, base_url: &Url) -> String {
+    let path = Url::parse(url).map(|url| url.path().to_string()).unwrap_or_default();
+    format!("{}{}", base_url.as_str().trim_end_matches('/'), path)
+}
+
+/// Picks the account address out of a link that may hold several, such as a token
+/// holder link `/token/<token>?a=<account>`. An address after `/address/` wins, then
+/// one in an `a=` parameter, then the first address in the link.
+fn href_address(href: &str) -> Option<&str> {
+    let matches: Vec<_> = ADDRESS_REGEX.find_iter(href).collect();
+    let preceded_by = |prefix: &str| matches.iter().find(|m| href[..m.start()].ends_with(prefix));
+    preceded_by("/address/")
+        .or_else(|| preceded_by("a="))
+        .or(matches.first())
+        .map(|m| m.as_str())
+}
+
+/// Reads etherscan's "Showing 26 to 50 of 1,234 entries" footer as its three numbers
+fn entry_range(text: &str) -> Option<(u64, u64, u64)> {
+    let start = text.find("Showing ")?;
+    let words: Vec<&str> = text[start..].split_whitespace().skip(1).take(5).collect();
+    let number = |word: &str| word.replace(',', "").parse::<u64>().ok();
+    match words[..] {
+        [first, "to", last, "of", total] => Some((number(first)?, number(last)?, number(total)?)),
+        _ => None,
+    }
+}
+
+/// How many pages a query has, from the footer of its page `page`, or `None` when the
+/// page has no footer to go by
+fn total_pages(html: &str, page: u32) -> Option<u32> {
+    let text: String = Html::parse_document(html).root_element().text().collect();
+    let (first, last, total) = entry_range(&text)?;
+    if last >= total {
+        return Some(page);
+    }
+    let per_page = last.checked_sub(first)? + 1;
+    u32::try_from(total.div_ceil(per_page)).ok()
+}
+
+/// The public name tag of a listed address: the link text when the page shows the tag in
+/// place of the address, otherwise the text of the table cell after the address cell
+fn wallet_label(link: ElementRef) -> Option<String> {
+    let text = |element: ElementRef| element.text().collect::<String>().trim().to_string();
+    let link_text = text(link);
+    if !link_text.is_empty() && !link_text.starts_with("0x") {
+        return Some(link_text);
+    }
+    let cell = link.ancestors().filter_map(ElementRef::wrap).find(|element| element.value().name() == "td")?;
+    let next_cell = cell.next_siblings().filter_map(ElementRef::wrap).next()?;
+    Some(text(next_cell)).filter(|label| !label.is_empty())
+}
+
+/// Whether a name tag names the exchange, as "Binance 14" does for Binance
+fn label_matches(label: &str, exchange_name: &str) -> bool {
+    label.to_lowercase().contains(&exchange_name.to_lowercase())
+}
+
+/// Lowercases an exchange name and replaces anything but ASCII letters and digits with `_`
+fn exchange_slug(name: &str) -> String {
+    name.chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c.to_ascii_lowercase() } else { '_' })
+        .collect()
+}
+
+/// Collapses repeated addresses into one record per address, keeping first-seen order
+/// and every URL the address was found on
+fn merge_duplicates(wallets: Vec<WalletRecord>) -> Vec<WalletRecord> {
+    let mut positions: HashMap<String, usize> = HashMap::new();
+    let mut merged: Vec<WalletRecord> = Vec::new();
+    
+    for wallet in wallets {
+        match positions.entry(wallet.wallet_address.clone()) {
+            Entry::Occupied(position) => merged[*position.get()].merge_sources(wallet),
+            Entry::Vacant(slot) => {
+                slot.insert(merged.len());
+                merged.push(wallet);
+            }
+        }
+    }
+    
+    merged
+}
+
+/// Orders wallets by exchange name, then address
+fn sort_wallets(wallets: &mut [WalletRecord]) {
+    wallets.sort_by(|a, b| (&a.exchange_name, &a.wallet_address).cmp(&(&b.exchange_name, &b.wallet_address)));
+}
+
+/// Sets `exchange_names` and `multi_exchange` on every record from all the exchanges
+/// that matched its address, returning how many addresses matched more than one
+fn flag_multi_exchange(wallets: &mut [WalletRecord]) -> usize {
+    let mut exchanges: HashMap<String, BTreeSet<String>> = HashMap::new();
+    for wallet in wallets.iter() {
+        let names = exchanges.entry(wallet.wallet_address.clone()).or_default();
+        names.insert(wallet.exchange_name.clone());
+        names.extend(wallet.exchange_names.iter().cloned());
+    }
+    
+    for wallet in wallets.iter_mut() {
+        let names = &exchanges[&wallet.wallet_address];
+        wallet.exchange_names = names.iter().cloned().collect();
+        wallet.multi_exchange = names.len() > 1;
+    }
+    exchanges.values().filter(|names| names.len() > 1).count()
+}
+
+/// Prints a validation report for one address and returns whether it is valid
+pub fn validate_address(address: &str, chain_id: Option<u64>) -> bool {
+    let valid = CEXScraper::is_valid_ethereum_address(address, chain_id);
+    let checksummed = to_checksum_address(address, chain_id);
+    let scheme = if chain_id.is_some() { "EIP-1191" } else { "EIP-55" };
+    
+    println!("address:     {}", address);
+    println!("well-formed: {}", if checksummed.is_some() { "yes" } else { "no" });
+    if let Some(checksummed) = checksummed {
+        println!("checksummed: {} ({})", if checksummed == address { "yes" } else { "no" }, scheme);
+        println!("checksum:    {}", checksummed);
+    }
+    println!("valid:       {}", if valid { "yes" } else { "no" });
+    
+    valid
+}
+
+pub fn load_state(path: &Path) -> Result<ScraperState, ScraperError> {
+    if path.exists() {
+        let file = File::open(path)?;
+        let reader = BufReader::new(file);
+        Ok(serde_json::from_reader(reader)?)
+    } else {
+        Ok(ScraperState::default())
+    }
+}
+
+pub fn save_state(state: &ScraperState, path: &Path) -> Result<(), ScraperError> {
+    write_atomic(path, |writer| Ok(serde_json::to_writer_pretty(writer, state)?))
+}
+
+/// Keccak-256 of the exchanges, in key order, and the burn list, so any config change shows
+fn config_hash(exchanges: &HashMap<String, ExchangeConfig>, burn_addresses: &[String]) -> String {
+    let exchanges: BTreeMap<_, _> = exchanges.iter().collect();
+    let config = serde_json::json!({ "exchanges": exchanges, "burn_addresses": burn_addresses });
+    let mut hasher = Keccak::v256();
+    let mut hash = [0u8; 32];
+    hasher.update(config.to_string().as_bytes());
+    hasher.finalize(&mut hash);
+    format!("0x{}", hash.iter().map(|byte| format!("{:02x}", byte)).collect::<String>())
+}
+
+/// The manifest of the last complete run, if there is a readable one
+fn load_manifest(path: &Path) -> Option<RunManifest> {
+    let file = File::open(path).ok()?;
+    serde_json::from_reader(BufReader::new(file)).ok()
+}
+
+fn save_manifest(manifest: &RunManifest, path: &Path) -> Result<(), ScraperError> {
+    write_atomic(path, |writer| Ok(serde_json::to_writer_pretty(writer, manifest)?))
+}
+
+/// Reads exchange definitions and the burn list from a TOML file and checks every selector compiles
+pub fn load_config(path: &Path) -> Result<ConfigFile, ScraperError> {
+    let contents = std::fs::read_to_string(path)?;
+    let file: ConfigFile = toml::from_str(&contents)
+        .map_err(|e| ScraperError::Config(format!("invalid config file {}: {}", path.display(), e)))?;
+    if file.exchanges.is_empty() {
+        return Err(ScraperError::Config(format!("config file {} defines no exchanges", path.display())));
+    }
+    
+    for config in file.exchanges.values() {
+        if let Err(ScraperError::Config(message)) = config.selectors.wallet_link() {
+            return Err(ScraperError::Config(format!("exchange {}: {}", config.name, message)));
+        }
+    }
+    
+    Ok(file)
+}
+
+pub fn get_exchange_configs() -> HashMap<String, ExchangeConfig> {
+    let mut configs = HashMap::new();
+
+    configs.insert(
+        "bitget".to_string(),
+        ExchangeConfig {
+            name: "Bitget".to_string(),
+            etherscan_url: "https://etherscan.io/accounts".to_string(),
+            search_queries: vec![
+                "bitget exchange".to_string(),
+                "bitget wallet".to_string(),
+                "bitget hot wallet".to_string(),
+                "bitget cold wallet".to_string(),
+                "bitget eth wallet".to_string(),
+            ],
+            api_label: "bitget".to_string(),
+            selectors: WalletSelectors::default(),
+        },
+    );
+
+    configs.insert(
+        "binance".to_string(),
+        ExchangeConfig {
+            name: "Binance".to_string(),
+            etherscan_url: "https://etherscan.io/accounts".to_string(),
+            search_queries: vec![
+                "binance hot wallet".to_string(),
+                "binance cold wallet".to_string(),
+                "binance exchange wallet".to_string(),
+                "binance eth address".to_string(),
+                "binance ether wallet".to_string(),
+                "binance 0x".to_string(),
+            ],
+            api_label: "binance".to_string(),
+            selectors: WalletSelectors::default(),
+        },
+    );
+
+    configs.insert(
+        "mexc".to_string(),
+        ExchangeConfig {
+            name: "MEXC".to_string(),
+            etherscan_url: "https://etherscan.io/accounts".to_string(),
+            search_queries: vec![
+                "mexc exchange".to_string(),
+                "mexc global wallet".to_string(),
+                "mexc hot wallet".to_string(),
+                "mexc cold storage".to_string(),
+                "mexc eth address".to_string(),
+            ],
+            api_label: "mexc".to_string(),
+            selectors: WalletSelectors::default(),
+        },
+    );
+
+    configs.insert(
+        "okx".to_string(),
+        ExchangeConfig {
+            name: "OKX".to_string(),
+            etherscan_url: "https://etherscan.io/accounts".to_string(),
+            search_queries: vec![
+                "okx exchange".to_string(),
+                "okx wallet".to_string(),
+                "okx hot wallet".to_string(),
+                "okx cold wallet".to_string(),
+                "okex exchange".to_string(), // Legacy name
+                "okx eth address".to_string(),
+            ],
+            api_label: "okx".to_string(),
+            selectors: WalletSelectors::default(),
+        },
+    );
+
+    configs
+}
+
+/// Counts reported by the writer task once every scraper is done
+#[derive(Default)]
+struct WriterStats {
+    collected: usize,
+    unique: usize,
+    new_count: usize,
+    /// The first few new wallets, logged as a sample
+    new_wallets: Vec<WalletRecord>,
+    written: Vec<String>,
+    new_per_exchange: BTreeMap<String, usize>,
+    found_per_exchange: BTreeMap<String, usize>,
+}
+
+/// Reads one search query per line, skipping blank lines and `#` comments
+pub fn read_queries(reader: impl BufRead) -> Result<Vec<String>, ScraperError> {
+    let mut queries = Vec::new();
+    for line in reader.lines() {
+        let line = line?;
+        let query = line.trim();
+        if !query.is_empty() && !query.starts_with('#') {
+            queries.push(query.to_string());
+        }
+    }
+    Ok(queries)
+}
+
+/// Adds queries to the exchange with this config key or name, or replaces its queries
+fn set_extra_queries(
+    configs: &mut HashMap<String, ExchangeConfig>,
+    exchange: &str,
+    queries: Vec<String>,
+    replace: bool,
+) -> Result<(), ScraperError> {
+    let config = configs
+        .iter_mut()
+        .find(|(key, config)| key.eq_ignore_ascii_case(exchange) || config.name.eq_ignore_ascii_case(exchange))
+        .map(|(_, config)| config)
+        .ok_or_else(|| ScraperError::Config(format!("unknown exchange {} for --queries-exchange", exchange)))?;
+    
+    info!("{} {} search queries for {}", if replace { "Using" } else { "Adding" }, queries.len(), config.name);
+    if replace {
+        config.search_queries = queries;
+    } else {
+        for query in queries {
+            if !config.search_queries.contains(&query) {
+                config.search_queries.push(query);
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Runs one full scrape of every configured exchange and writes the new wallets
+pub async fn run(args: Args, http: HttpOptions) -> Result<RunOutcome> {
+    run_with_sinks(args, http, Vec::new()).await
+}
+
+/// Like `run`, calling `observer` with every new wallet as it is written
+pub async fn run_with_observer(args: Args, http: HttpOptions, observer: Option<WalletObserver>) -> Result<RunOutcome> {
+    run_with(args, http, Vec::new(), observer).await
+}
+
+/// Like `run`, also writing the new wallets to `sinks` alongside the configured outputs
+pub async fn run_with_sinks(args: Args, http: HttpOptions, sinks: Vec<Box<dyn WalletSink>>) -> Result<RunOutcome> {
+    run_with(args, http, sinks, None).await
+}
+
+async fn run_with(
+    args: Args,
+    http: HttpOptions,
+    sinks: Vec<Box<dyn WalletSink>>,
+    observer: Option<WalletObserver>,
+) -> Result<RunOutcome> {
+    info!("Starting CEX Wallet Scraper...");
+    let started = Instant::now();
+    if let Some(dir) = &args.output_paths.out_dir {
+        std::fs::create_dir_all(dir).with_context(|| format!("Failed to create output directory {}", dir.display()))?;
+    }
+    let state_file = args.output_paths.state_file();
+    
+    let mut state = if args.fresh {
+        info!("--fresh given, ignoring previously seen wallets");
+        ScraperState::default()
+    } else {
+        load_state(&state_file).context("Failed to load state file")?
+    };
+    // State written before addresses were checksummed may hold other casings
+    state.seen_wallets = state
+        .seen_wallets
+        .into_iter()
+        .map(|address| to_checksum_address(&address, args.chain_id).unwrap_or(address))
+        .collect();
+    info!("Loaded {} previously seen wallets", state.seen_wallets.len());
+    let page_cursors = std::mem::take(&mut state.page_cursors);
+    let funders = std::mem::take(&mut state.funders);
+    
+    // Set up the uploader before scraping so bad credentials fail fast
+    let uploader = match &args.s3_bucket {
+        Some(bucket) if !args.dry_run => Some(
+            S3Uploader::new(bucket, &args.s3_region, args.s3_endpoint.as_deref(), &args.s3_prefix)
+                .context("Failed to configure S3 upload")?,
+        ),
+        _ => None,
+    };
+    
+    if args.dry_run {
+        info!("--dry-run given, nothing will be written");
+    }
+    let fetch_balances = args.fetch_balances && args.api_key.is_some();
+    if args.fetch_balances && !fetch_balances {
+        warn!("--fetch-balances needs an etherscan API key, skipping balance lookups");
+    }
+    let formats = output_formats(&args.format, &args.output_paths);
+    let to_stdout = output_files(&formats, None, &args.output_paths)
+        .into_iter()
+        .filter(|(_, filename)| filename == writer::STDOUT)
+        .count();
+    if to_stdout > 1 {
+        return Err(ScraperError::Config("only one output format can be written to stdout".to_string()).into());
+    }
+    if let Some(start_page) = args.start_page.filter(|page| *page > args.end_page) {
+        return Err(ScraperError::Config(format!("--start-page {} is past --end-page {}", start_page, args.end_page)).into());
+    }
+    let min_tx_count = args.min_tx_count.filter(|_| args.api_key.is_some());
+    if args.min_tx_count.is_some() && min_tx_count.is_none() {
+        warn!("--min-tx-count needs an etherscan API key, keeping every wallet");
+    }
+    let verify_labels = args.verify_labels && args.api_key.is_some();
+    if args.verify_labels && !verify_labels {
+        warn!("--verify-labels needs an etherscan API key, keeping every wallet");
+    }
+    let cluster_by_funder = args.cluster_by_funder && args.api_key.is_some();
+    if args.cluster_by_funder && !cluster_by_funder {
+        warn!("--cluster-by-funder needs an etherscan API key, skipping funder lookups");
+    }
+    
+    let mut scraper = CEXScraper::new(&http, args.chain_id, args.api_key)
+        .context("Failed to create HTTP client")?
+        .with_max_backoff(Duration::from_secs(args.max_backoff))
+        .with_max_retries(args.max_retries)
+        .with_max_pages(args.end_page)
+        .with_limit_per_exchange(args.limit_per_exchange)
+        .with_fail_fast(args.fail_fast)
+        .with_mem_cache(args.mem_cache_size)
+        .with_query_delay(Duration::from_secs(args.query_delay))
+        .with_base_url(args.base_url.clone())
+        .with_api(ETHERSCAN_V2_API_URL, args.api_chain_id)
+        .with_progress((!args.quiet && std::io::stderr().is_terminal()).then(MultiProgress::new))
+        .with_page_cursors(page_cursors, args.start_page)
+        .with_funders(funders)
+        .with_circuit_breaker(
+            args.breaker_threshold,
+            Duration::from_secs(args.breaker_window),
+            Duration::from_secs(args.breaker_cooldown),
+        );
+    if let Some(per_minute) = args.requests_per_minute.filter(|rate| *rate > 0.0) {
+        scraper = scraper.with_rate_limiter(RateLimiter::token_bucket(args.burst, per_minute / 60.0));
+    }
+    let (mut exchange_configs, burn_addresses) = match &args.config {
+        Some(path) => {
+            let config = load_config(path).context("Failed to load exchange config")?;
+            (config.exchanges, config.burn_addresses)
+        }
+        None => (get_exchange_configs(), default_burn_addresses()),
+    };
+    if exchange_configs.is_empty() {
+        return Err(ScraperError::Config("no exchanges to scrape".to_string()).into());
+    }
+    scraper = scraper.with_burn_addresses(&burn_addresses);
+    let manifest_file = args.output_paths.manifest_file();
+    if let Some(exchange) = &args.queries_exchange {
+        let mut queries = Vec::new();
+        if let Some(path) = &args.queries_file {
+            let file = File::open(path).with_context(|| format!("Failed to open {}", path.display()))?;
+            queries.extend(read_queries(BufReader::new(file)).context("Failed to read queries file")?);
+        }
+        if args.queries_stdin {
+            queries.extend(read_queries(std::io::stdin().lock()).context("Failed to read queries from stdin")?);
+        }
+        set_extra_queries(&mut exchange_configs, exchange, queries, args.replace_queries)?;
+    }
+    let config_hash = config_hash(&exchange_configs, &burn_addresses);
+    if let Some(window) = args.skip_if_recent {
+        let recent = load_manifest(&manifest_file)
+            .filter(|manifest| manifest.is_recent(&config_hash, Duration::from_secs(window), chrono::Utc::now()));
+        if let Some(manifest) = recent {
+            info!("Last run finished at {} with the same config, skipping this one", manifest.completed_at);
+            return Ok(RunOutcome::Success);
+        }
+    }
+    
+    // Each scraping task hands its exchange's wallets to a single writer task, which
+    // dedups against the state and writes the exchanges in name order as soon as every
+    // earlier one is in, so the output doesn't depend on which exchange finishes first.
+    // Wallets written before a later exchange matched their address are flagged when
+    // the outputs are finished.
+    let (sender, mut receiver) = mpsc::channel::<(String, Vec<WalletRecord>)>(exchange_configs.len());
+    let mut waiting: BTreeMap<String, usize> = BTreeMap::new();
+    for config in exchange_configs.values() {
+        *waiting.entry(config.name.clone()).or_default() += 1;
+    }
+    let split_by_exchange = args.split_by_exchange;
+    let mut wallet_writer = (!args.dry_run)
+        .then(|| WalletWriter::new(formats.clone(), split_by_exchange, args.output_paths.clone(), args.append_csv, sinks));
+    let writer_task = tokio::spawn(async move {
+        let mut stats = WriterStats::default();
+        let mut unique = HashSet::new();
+        let mut written_this_run = HashSet::new();
+        // Every exchange that has matched each address so far
+        let mut exchanges: HashMap<String, BTreeSet<String>> = HashMap::new();
+        // Wallets of exchanges that finished before an exchange sorting ahead of them
+        let mut pending: BTreeMap<String, Vec<WalletRecord>> = BTreeMap::new();
+        
+        loop {
+            let received = receiver.recv().await;
+            let closed = received.is_none();
+            if let Some((exchange, batch)) = received {
+                for wallet in &batch {
+                    stats.collected += 1;
+                    *stats.found_per_exchange.entry(wallet.exchange_name.clone()).or_default() += 1;
+                    let names = exchanges.entry(wallet.wallet_address.clone()).or_default();
+                    names.insert(wallet.exchange_name.clone());
+                    names.extend(wallet.exchange_names.iter().cloned());
+                }
+                pending.entry(exchange.clone()).or_default().extend(batch);
+                if let Some(count) = waiting.get_mut(&exchange) {
+                    *count = count.saturating_sub(1);
+                }
+            }
+            // Once every task is done, whatever is left goes out in name order too
+            let mut ready = Vec::new();
+            while let Some((exchange, count)) = waiting.first_key_value() {
+                if *count > 0 && !closed {
+                    break;
+                }
+                let exchange = exchange.clone();
+                waiting.remove(&exchange);
+                let mut batch = pending.remove(&exchange).unwrap_or_default();
+                sort_wallets(&mut batch);
+                ready.extend(batch);
+            }
+            
+            for mut wallet in ready {
+                let names = &exchanges[&wallet.wallet_address];
+                wallet.exchange_names = names.iter().cloned().collect();
+                wallet.multi_exchange = names.len() > 1;
+                
+                // Within an exchange sources are already merged; across exchanges the
+                // alphabetically first one wins, unless each exchange gets its own files
+                let key = if split_by_exchange {
+                    (wallet.exchange_name.clone(), wallet.wallet_address.clone())
+                } else {
+                    (String::new(), wallet.wallet_address.clone())
+                };
+                if !unique.insert(key) {
+                    continue;
+                }
+                if !written_this_run.contains(&wallet.wallet_address) && state.seen_wallets.contains(&wallet.wallet_address) {
+                    continue;
+                }
+                written_this_run.insert(wallet.wallet_address.clone());
+                state.seen_wallets.insert(wallet.wallet_address.clone());
+                
+                if let Some(wallet_writer) = &mut wallet_writer {
+                    wallet_writer.write(&wallet);
+                }
+                if let Some(observer) = &observer {
+                    observer(&wallet);
+                }
+                *stats.new_per_exchange.entry(wallet.exchange_name.clone()).or_default() += 1;
+                if stats.new_wallets.len() < 5 {
+                    stats.new_wallets.push(wallet);
+                }
+                stats.new_count += 1;
+            }
+            if closed {
+                break;
+            }
+        }
+        
+        let multi_exchange: HashMap<String, Vec<String>> = exchanges
+            .into_iter()
+            .filter(|(_, names)| names.len() > 1)
+            .map(|(address, names)| (address, names.into_iter().collect()))
+            .collect();
+        if !multi_exchange.is_empty() {
+            warn!("{} addresses matched more than one exchange, check them for mislabeling", multi_exchange.len());
+        }
+        stats.unique = unique.len();
+        stats.written = wallet_writer.map(|writer| writer.finish(&multi_exchange)).unwrap_or_default();
+        (state, stats)
+    });
+    
+    let mut tasks = Vec::new();
+    let unverified = Arc::new(Mutex::new(Vec::new()));
+    let verified_count = Arc::new(AtomicUsize::new(0));
+    let funded = Arc::new(Mutex::new(Vec::new()));
+    let fatal = Arc::new(Mutex::new(None));
+    
+    // Create scraping tasks for each exchange
+    let stagger = Duration::from_secs(args.stagger);
+    let exchange_slots = Arc::new(Semaphore::new(
+        args.exchange_concurrency.map_or(exchange_configs.len(), |concurrency| concurrency as usize),
+    ));
+    for (_, config) in exchange_configs {
+        let mut scraper_clone = scraper.for_exchange().await;
+        let sender = sender.clone();
+        let exchange_slots = exchange_slots.clone();
+        let unverified = unverified.clone();
+        let verified_count = verified_count.clone();
+        let funded = funded.clone();
+        let fatal = fatal.clone();
+        let fail_fast = args.fail_fast;
+        let filter = args.filter.clone();
+        tasks.push(tokio::spawn(async move {
+            let exchange = config.name.clone();
+            let (succeeded, wallets) = async {
+                if !stagger.is_zero() {
+                    let offset = stagger.mul_f64(with_rng(|rng| rng.gen::<f64>()));
+                    info!("Starting {} in {:.1?}", config.name, offset);
+                    tokio::select! {
+                        _ = sleep(offset) => {}
+                        _ = scraper_clone.cancelled() => return (false, Vec::new()),
+                    }
+                }
+                // Held while this exchange is scraped
+                let _slot = tokio::select! {
+                    slot = exchange_slots.acquire_owned() => slot.expect("exchange semaphore is never closed"),
+                    _ = scraper_clone.cancelled() => return (false, Vec::new()),
+                };
+                match scraper_clone.scrape_exchange_wallets(&config).await {
+                    Ok(wallets) => {
+                        info!("Found {} wallets for {}", wallets.len(), config.name);
+                        let mut wallets = merge_duplicates(wallets);
+                        // Before any lookups, so no API calls are spent on wallets the filter drops
+                        if let Some(filter) = &filter {
+                            let count = wallets.len();
+                            wallets.retain(|wallet| filter.matches(wallet));
+                            info!("Filtered out {} {} wallets not matching --filter", count - wallets.len(), config.name);
+                        }
+                        if let Some(min_tx_count) = min_tx_count {
+                            let count = wallets.len();
+                            wallets = scraper_clone.filter_by_tx_count(wallets, min_tx_count).await;
+                            info!(
+                                "Filtered out {} {} wallets with fewer than {} transactions",
+                                count - wallets.len(),
+                                config.name,
+                                min_tx_count
+                            );
+                        }
+                        if verify_labels {
+                            let (verified, rejected) = scraper_clone.verify_labels(wallets, &config.name).await;
+                            info!("{} of {} {} wallets carry a matching name tag", verified.len(), verified.len() + rejected.len(), config.name);
+                            verified_count.fetch_add(verified.len(), Ordering::Relaxed);
+                            unverified.lock().expect("unverified wallets lock poisoned").extend(rejected);
+                            wallets = verified;
+                        }
+                        if fetch_balances && !scraper_clone.is_cancelled() {
+                            scraper_clone.fetch_balances(&mut wallets).await;
+                        }
+                        if cluster_by_funder {
+                            scraper_clone.fetch_funders(&mut wallets).await;
+                            let with_funder = wallets.iter().filter(|wallet| wallet.funded_by.is_some()).cloned();
+                            funded.lock().expect("funded wallets lock poisoned").extend(with_funder);
+                        }
+                        (true, wallets)
+                    }
+                    Err(e) if fail_fast && e.is_fatal() => {
+                        error!("Fatal error scraping {}, stopping every exchange: {}", config.name, e);
+                        scraper_clone.cancel();
+                        fatal.lock().expect("fatal error lock poisoned").get_or_insert((config.name.clone(), e));
+                        (false, Vec::new())
+                    }
+                    Err(e) => {
+                        error!("Error scraping {}: {}", config.name, e);
+                        (false, Vec::new())
+                    }
+                }
+            }
+            .await;
+            // Sent even when empty, so the writer isn't left waiting for this exchange
+            let _ = sender.send((exchange, wallets)).await;
+            succeeded
+        }));
+    }
+    drop(sender);
+    
+    // Wait for all tasks to complete; on Ctrl-C stop scraping at the next page but
+    // hand the finished pages to the writer, so the output, state and cursors agree
+    let all_tasks = join_all(tasks);
+    tokio::pin!(all_tasks);
+    let shutdown = tokio::signal::ctrl_c();
+    tokio::pin!(shutdown);
+    let (results, interrupted) = tokio::select! {
+        results = &mut all_tasks => (results, false),
+        _ = &mut shutdown => {
+            warn!("Interrupted, finishing pending writes before exiting");
+            scraper.cancel();
+            (all_tasks.await, true)
+        }
+    };
+    let mut failed_exchanges = 0;
+    for result in results {
+        match result {
+            Ok(true) => {}
+            Ok(false) => failed_exchanges += 1,
+            Err(e) => {
+                error!("Task failed: {}", e);
+                failed_exchanges += 1;
+            }
+        }
+    }
+    
+    let (mut state, stats) = writer_task.await.context("Writer task failed")?;
+    state.page_cursors = scraper.page_cursors();
+    state.funders = scraper.funders();
+    let outcome = RunOutcome::from_counts(stats.new_count, failed_exchanges);
+    
+    info!("Total wallets collected: {}", stats.collected);
+    info!("Unique wallets after filtering and deduplication: {}", stats.unique);
+    info!("New wallets since last run: {}", stats.new_count);
+    
+    let mut run_stats = RunStats {
+        wallets_per_exchange: stats.found_per_exchange.clone(),
+        unique_wallets: stats.unique,
+        new_wallets: stats.new_count,
+        elapsed_secs: started.elapsed().as_secs_f64(),
+        ..RunStats::default()
+    };
+    run_stats.record_requests(&scraper.counters);
+    if let Some(mem_cache) = &scraper.mem_cache {
+        run_stats.mem_cache_hits = Some(mem_cache.hits());
+        run_stats.mem_cache_misses = Some(mem_cache.misses());
+    }
+    let mut unverified = std::mem::take(&mut *unverified.lock().expect("unverified wallets lock poisoned"));
+    if verify_labels {
+        run_stats.verified_wallets = Some(verified_count.load(Ordering::Relaxed));
+        run_stats.unverified_wallets = Some(unverified.len());
+    }
+    run_stats.log_summary();
+    if let Some(path) = &args.stats_out {
+        if let Err(e) = run_stats.save(path) {
+            error!("Failed to write stats to {}: {}", path.display(), e);
+        }
+    }
+    
+    if verify_labels && !args.dry_run {
+        sort_wallets(&mut unverified);
+        let path = args.output_paths.in_out_dir(UNVERIFIED_OUTPUT_FILE);
+        scraper.save_to_json(&unverified, &path).await.context("Failed to write unverified wallets")?;
+    }
+    
+    if cluster_by_funder {
+        let funded = std::mem::take(&mut *funded.lock().expect("funded wallets lock poisoned"));
+        let clusters = funder_clusters(&funded);
+        info!(
+            "Found {} funder clusters covering {} wallets",
+            clusters.len(),
+            clusters.iter().map(|cluster| cluster.wallets.len()).sum::<usize>()
+        );
+        if !args.dry_run {
+            let path = args.output_paths.in_out_dir(CLUSTERS_OUTPUT_FILE);
+            cluster::save_clusters(&clusters, Path::new(&path)).context("Failed to write funder clusters")?;
+        }
+    }
+    
+    let fatal = fatal.lock().expect("fatal error lock poisoned").take();
+    if let Some((exchange, e)) = fatal {
+        // Keep the wallets already written out of the next run, as after an interrupt
+        if !args.dry_run {
+            save_state(&state, &state_file).context("Failed to save state file")?;
+        }
+        return Err(anyhow::Error::new(e).context(format!("Aborted by --fail-fast after a fatal error scraping {}", exchange)));
+    }
+    
+    if args.dry_run {
+        for (exchange, count) in &stats.new_per_exchange {
+            info!("  {}: {} new wallets", exchange, count);
+        }
+        info!("Sample wallets:");
+        for wallet in &stats.new_wallets {
+            info!("  {}: {}", wallet.exchange_name, wallet.wallet_address);
+        }
+        info!("Dry run complete, no files were written");
+        return Ok(outcome);
+    }
+    
+    if interrupted {
+        save_state(&state, &state_file).context("Failed to save state file")?;
+        return Ok(outcome);
+    }
+    
+    if stats.new_count > 0 {
+        if let Some(uploader) = &uploader {
+            let run_key = chrono::Utc::now().format("%Y%m%dT%H%M%SZ").to_string();
+            let uploads = async {
+                for file in &stats.written {
+                    uploader.upload_file(Path::new(file), &run_key).await;
+                }
+            };
+            tokio::select! {
+                _ = uploads => {}
+                _ = &mut shutdown => warn!("Interrupted, skipping remaining uploads"),
+            }
+        }
+        
+        info!("Sample wallets:");
+        for wallet in &stats.new_wallets {
+            info!("  {}: {}", wallet.exchange_name, wallet.wallet_address);
+        }
+    } else if stats.unique > 0 {
+        info!("No new wallets found, leaving existing output files untouched");
+    } else if !args.write_sample_on_empty || outcome == RunOutcome::Failed {
+        // Failed exchanges leave "nothing found" in doubt, so samples would only mislead
+        warn!("No wallets found, so no output files were written");
+    } else if formats.iter().all(|format| *format == OutputFormat::Sqlite) {
+        warn!("No wallets found, and samples are never written to the database");
+    } else {
+        warn!("No wallets found. Creating sample output files...");
+        
+        let sample_wallets = vec![
+            WalletRecord {
+                exchange_name: "Binance".to_string(),
+                wallet_address: "0xBE0eB53F46cd790Cd13851d5EFf43D12404d33E8".to_string(),
+                source_urls: vec!["https://etherscan.io/accounts?q=binance".to_string()],
+                balance_wei: None,
+                exchange_names: Vec::new(),
+                multi_exchange: false,
+                label: None,
+                funded_by: None,
+            },
+            WalletRecord {
+                exchange_name: "Bitget".to_string(),
+                wallet_address: "0x5a52E96BAcdaBb82fd05763E25335261B270Efcb".to_string(),
+                source_urls: vec!["https://etherscan.io/accounts?q=bitget".to_string()],
+                balance_wei: None,
+                exchange_names: Vec::new(),
+                multi_exchange: false,
+                label: None,
+                funded_by: None,
+            },
+            WalletRecord {
+                exchange_name: "MEXC".to_string(),
+                wallet_address: "0x75E89d5979e4f6fba9f97C104c2f0afB3F1DfaFD".to_string(),
+                source_urls: vec!["https://etherscan.io/accounts?q=mexc".to_string()],
+                balance_wei: None,
+                exchange_names: Vec::new(),
+                multi_exchange: false,
+                label: None,
+                funded_by: None,
+            },
+            WalletRecord {
+                exchange_name: "OKX".to_string(),
+                wallet_address: "0x6cC5F688a315f3dC28A7781717a9A798a59fDA7b".to_string(),
+                source_urls: vec!["https://etherscan.io/accounts?q=okx".to_string()],
+                balance_wei: None,
+                exchange_names: Vec::new(),
+                multi_exchange: false,
+                label: None,
+                funded_by: None,
+            },
+        ];
+        
+        // Samples would stay in the database for good, so they only go to the files
+        let file_formats = formats.into_iter().filter(|format| *format != OutputFormat::Sqlite).collect();
+        let mut sample_writer = WalletWriter::new(file_formats, args.split_by_exchange, args.output_paths.clone(), false, Vec::new());
+        for wallet in &sample_wallets {
+            sample_writer.write(wallet);
+        }
+        sample_writer.finish(&HashMap::new());
+    }
+    
+    save_state(&state, &state_file).context("Failed to save state file")?;
+    if outcome != RunOutcome::Failed {
+        let manifest = RunManifest { completed_at: chrono::Utc::now().to_rfc3339(), config_hash };
+        if let Err(e) = save_manifest(&manifest, &manifest_file) {
+            error!("Failed to write run manifest {}: {}", manifest_file.display(), e);
+        }
+    }
+    
+    info!("Scraping completed successfully!");
+    Ok(outcome)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Reference vectors from the EIP-55 specification
+    const EIP55_VECTORS: [&str; 8] = [
+        "0x52908400098527886E0F7030069857D2E4169EE7",
+        "0x8617E340B3D01FA5F11F306F4090FD50E238070D",
+        "0xde709f2102306220921060314715629080e2fb77",
+        "0x27b1fdb04752bbc536007a920d24acb045561c26",
+        "0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed",
+        "0xfB6916095ca1df60bB79Ce92cE3Ea74c37c5d359",
+        "0xdbF03B407c01E7cD3CBea99509d93f8DDDC8C6FB",
+        "0xD1220A0cf47c7B9Be7A2E6BA89F429762e7b9aDb",
+    ];
+
+    #[test]
+    fn accepts_eip55_reference_vectors() {
+        for address in EIP55_VECTORS {
+            assert!(CEXScraper::verify_checksum(address, None), "{}", address);
+            assert!(CEXScraper::is_valid_ethereum_address(address, None), "{}", address);
+            assert_eq!(to_checksum_address(&address.to_lowercase(), None).as_deref(), Some(address));
+        }
+    }
+
+    #[test]
+    fn rejects_a_single_flipped_case() {
+        // Last character of 0x5aAeb...BeAed flipped to upper case
+        let address = "0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAeD";
+        assert!(!CEXScraper::verify_checksum(address, None));
+        assert!(!CEXScraper::is_valid_ethereum_address(address, None));
+    }
+
+    #[test]
+    fn accepts_unchecksummed_single_case_addresses() {
+        assert!(CEXScraper::is_valid_ethereum_address("0x5aaeb6053f3e94c9b9a09f33669435e7ef1beaed", None));
+        assert!(CEXScraper::is_valid_ethereum_address("0x5AAEB6053F3E94C9B9A09F33669435E7EF1BEAED", None));
+        assert!(!CEXScraper::verify_checksum("0x5aaeb6053f3e94c9b9a09f33669435e7ef1beaed", None));
+    }
+
+    #[test]
+    fn rejects_wrong_length_and_non_hex() {
+        assert!(!CEXScraper::is_valid_ethereum_address("0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAe", None));
+        assert!(!CEXScraper::is_valid_ethereum_address("0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed0", None));
+        assert!(!CEXScraper::is_valid_ethereum_address("5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed00", None));
+        assert!(!CEXScraper::is_valid_ethereum_address("0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAeg", None));
+        assert!(!CEXScraper::verify_checksum("0x", None));
+        assert_eq!(to_checksum_address("0xzz", None), None);
+    }
+
+    #[test]
+    fn extracted_addresses_are_checksummed() {
+        let html = r#"<a href="/address/0x5aaeb6053f3e94c9b9a09f33669435e7ef1beaed">a</a>
+            <a href="/address/0x5AAEB6053F3E94C9B9A09F33669435E7EF1BEAED">b</a>"#;
+        let selector = WalletSelectors::default().wallet_link().unwrap();
+        let wallets = CEXScraper::extract_wallets_from_html_static(html, &selector, "Binance", "https://etherscan.io/accounts", None);
+        assert_eq!(wallets.len(), 2);
+        for wallet in wallets {
+            assert_eq!(wallet.wallet_address, "0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed");
+        }
+    }
+
+    #[test]
+    fn addresses_matched_by_several_exchanges_are_flagged() {
+        let record = |exchange: &str, address: &str| WalletRecord {
+            exchange_name: exchange.to_string(),
+            wallet_address: address.to_string(),
+            source_urls: Vec::new(),
+            balance_wei: None,
+            exchange_names: Vec::new(),
+            multi_exchange: false,
+            label: None,
+            funded_by: None,
+        };
+        let shared = "0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed";
+        let mut wallets = vec![
+            record("OKX", shared),
+            record("Kraken", "0xfB6916095ca1df60bB79Ce92cE3Ea74c37c5d359"),
+            record("Binance", shared),
+        ];
+
+        assert_eq!(flag_multi_exchange(&mut wallets), 1);
+        assert!(wallets[0].multi_exchange && wallets[2].multi_exchange);
+        assert_eq!(wallets[0].exchange_names, ["Binance", "OKX"]);
+        assert!(!wallets[1].multi_exchange);
+        assert_eq!(wallets[1].exchange_names, ["Kraken"]);
+    }
+
+    #[test]
+    fn wallets_sort_by_exchange_then_address() {
+        let record = |exchange: &str, address: &str| WalletRecord {
+            exchange_name: exchange.to_string(),
+            wallet_address: address.to_string(),
+            source_urls: Vec::new(),
+            balance_wei: None,
+            exchange_names: Vec::new(),
+            multi_exchange: false,
+            label: None,
+            funded_by: None,
+        };
+        let mut wallets = vec![record("OKX", "0x02"), record("Binance", "0x03"), record("OKX", "0x01")];
+
+        sort_wallets(&mut wallets);
+
+        let order: Vec<_> = wallets.iter().map(|wallet| (wallet.exchange_name.as_str(), wallet.wallet_address.as_str())).collect();
+        assert_eq!(order, [("Binance", "0x03"), ("OKX", "0x01"), ("OKX", "0x02")]);
+    }
+
+    #[tokio::test]
+    async fn csv_appends_keep_a_single_header() {
+        let path = std::env::temp_dir().join(format!("cex-wallets-append-{}.csv", std::process::id()));
+        let filename = path.to_string_lossy();
+        let scraper = CEXScraper::new(&HttpOptions::default(), None, None).unwrap();
+        let wallet = |address: &str| WalletRecord {
+            exchange_name: "Binance".to_string(),
+            wallet_address: address.to_string(),
+            source_urls: vec!["https://etherscan.io/accounts?q=binance".to_string()],
+            balance_wei: None,
+            exchange_names: Vec::new(),
+            multi_exchange: false,
+            label: None,
+            funded_by: None,
+        };
+
+        scraper.save_to_csv_append(&[wallet("0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed")], &filename).await.unwrap();
+        scraper.save_to_csv_append(&[wallet("0xfB6916095ca1df60bB79Ce92cE3Ea74c37c5d359")], &filename).await.unwrap();
+
+        let lines: Vec<String> = std::fs::read_to_string(&path).unwrap().lines().map(str::to_string).collect();
+        assert_eq!(lines.len(), 3);
+        assert!(lines[0].starts_with("exchange_name,"));
+        assert!(lines[2].contains("0xfB6916095ca1df60bB79Ce92cE3Ea74c37c5d359"));
+
+        scraper.save_to_csv(&[wallet("0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed")], &filename).await.unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap().lines().count(), 2);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn xlsx_export_is_a_workbook() {
+        let path = std::env::temp_dir().join(format!("cex-wallets-{}.xlsx", std::process::id()));
+        let scraper = CEXScraper::new(&HttpOptions::default(), None, None).unwrap();
+        let wallet = WalletRecord {
+            exchange_name: "Binance".to_string(),
+            wallet_address: "0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed".to_string(),
+            source_urls: vec!["https://etherscan.io/accounts?q=binance".to_string()],
+            balance_wei: Some("100".to_string()),
+            exchange_names: vec!["Binance".to_string()],
+            multi_exchange: false,
+            label: Some("Binance 14".to_string()),
+            funded_by: None,
+        };
+
+        scraper.save_to_xlsx(&[wallet], &path.to_string_lossy()).await.unwrap();
+
+        // An xlsx file is a zip archive
+        assert!(std::fs::read(&path).unwrap().starts_with(b"PK"));
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn custom_sinks_receive_the_wallets_and_are_finished() {
+        struct Collect(Arc<Mutex<(Vec<String>, bool)>>);
+
+        impl WalletSink for Collect {
+            fn write(&mut self, wallets: &[WalletRecord]) -> Result<(), ScraperError> {
+                let mut seen = self.0.lock().unwrap();
+                seen.0.extend(wallets.iter().map(|wallet| wallet.wallet_address.clone()));
+                Ok(())
+            }
+
+            fn finish(self: Box<Self>) -> Result<(), ScraperError> {
+                self.0.lock().unwrap().1 = true;
+                Ok(())
+            }
+        }
+
+        let seen = Arc::new(Mutex::new((Vec::new(), false)));
+        let scraper = CEXScraper::new(&HttpOptions::default(), None, None).unwrap();
+        let wallet = WalletRecord {
+            exchange_name: "Binance".to_string(),
+            wallet_address: "0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed".to_string(),
+            source_urls: Vec::new(),
+            balance_wei: None,
+            exchange_names: Vec::new(),
+            multi_exchange: false,
+            label: None,
+            funded_by: None,
+        };
+
+        scraper.save_to_sink(&[wallet], Box::new(Collect(seen.clone()))).await.unwrap();
+
+        let seen = seen.lock().unwrap();
+        assert_eq!(seen.0, ["0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed"]);
+        assert!(seen.1);
+    }
+
+    #[test]
+    fn unfinished_sink_leaves_the_previous_output() {
+        let path = std::env::temp_dir().join(format!("cex-wallets-unfinished-{}.json", std::process::id()));
+        let filename = path.to_string_lossy().to_string();
+        std::fs::write(&path, "[]").unwrap();
+        let wallet = WalletRecord {
+            exchange_name: "Binance".to_string(),
+            wallet_address: "0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed".to_string(),
+            source_urls: Vec::new(),
+            balance_wei: None,
+            exchange_names: Vec::new(),
+            multi_exchange: false,
+            label: None,
+            funded_by: None,
+        };
+
+        // A run that dies before finishing never replaces the file
+        let mut sink = open_sink(OutputFormat::Json, &filename, false).unwrap();
+        sink.write(std::slice::from_ref(&wallet)).unwrap();
+        sink.flush().unwrap();
+        drop(sink);
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "[]");
+
+        let mut sink = open_sink(OutputFormat::Json, &filename, false).unwrap();
+        sink.write(&[wallet]).unwrap();
+        sink.finish().unwrap();
+        let wallets: Vec<WalletRecord> = serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(wallets.len(), 1);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn manifest_skips_only_recent_runs_with_the_same_config() {
+        let mut configs = get_exchange_configs();
+        let hash = config_hash(&configs, &default_burn_addresses());
+        assert_eq!(hash, config_hash(&configs, &default_burn_addresses()));
+        let now = chrono::Utc::now();
+        let manifest = RunManifest { completed_at: (now - chrono::Duration::minutes(5)).to_rfc3339(), config_hash: hash.clone() };
+        
+        assert!(manifest.is_recent(&hash, Duration::from_secs(600), now));
+        assert!(!manifest.is_recent(&hash, Duration::from_secs(60), now));
+        
+        configs.get_mut("binance").unwrap().search_queries.push("binance cold wallet".to_string());
+        let changed = config_hash(&configs, &default_burn_addresses());
+        assert_ne!(changed, hash);
+        assert!(!manifest.is_recent(&changed, Duration::from_secs(600), now));
+    }
+
+    #[test]
+    fn config_without_exchanges_is_rejected() {
+        let path = std::env::temp_dir().join(format!("cex-wallets-empty-{}.toml", std::process::id()));
+        std::fs::write(&path, "[exchanges]\n").unwrap();
+        
+        let result = load_config(&path);
+        
+        std::fs::remove_file(&path).unwrap();
+        assert!(matches!(result, Err(ScraperError::Config(message)) if message.contains("defines no exchanges")));
+    }
+
+    #[test]
+    fn extra_queries_are_added_or_replace_the_built_in_ones() {
+        let queries = read_queries("# generated\nbinance deposit\n\n  binance 14  \nbinance hot wallet\n".as_bytes()).unwrap();
+        assert_eq!(queries, ["binance deposit", "binance 14", "binance hot wallet"]);
+        
+        let mut configs = get_exchange_configs();
+        let built_in = configs["binance"].search_queries.len();
+        set_extra_queries(&mut configs, "Binance", queries.clone(), false).unwrap();
+        assert_eq!(configs["binance"].search_queries.len(), built_in + 2);
+        
+        set_extra_queries(&mut configs, "binance", queries, true).unwrap();
+        assert_eq!(configs["binance"].search_queries.len(), 3);
+        
+        assert!(set_extra_queries(&mut configs, "nowhere", Vec::new(), false).is_err());
+    }
+
+    #[test]
+    fn footer_gives_the_page_count() {
+        let footer = |text: &str| format!("<html><body><div>Showing <b>{}</b></div></body></html>", text);
+        assert_eq!(total_pages(&footer("1 to 25 of 1,234 entries"), 1), Some(50));
+        assert_eq!(total_pages(&footer("26 to 50 of 60 entries"), 2), Some(3));
+        assert_eq!(total_pages(&footer("51 to 60 of 60 entries"), 3), Some(3));
+        assert_eq!(total_pages(&footer("page 1"), 1), None);
+        assert_eq!(total_pages("<html><body>No footer</body></html>", 1), None);
+    }
+
+    #[test]
+    fn name_tags_are_kept_alongside_the_exchange() {
+        let html = r#"<table>
+            <tr><td>1</td><td><a href="/address/0x5aaeb6053f3e94c9b9a09f33669435e7ef1beaed">0x5aAeb605...1BeAed</a></td><td> Binance 14 </td></tr>
+            <tr><td>2</td><td><a href="/address/0xfb6916095ca1df60bb79ce92ce3ea74c37c5d359">OKX 3</a></td><td></td></tr>
+            <tr><td>3</td><td><a href="/address/0xdbf03b407c01e7cd3cbea99509d93f8dddc8c6fb">0xdbF03B40...C8C6FB</a></td><td></td></tr>
+        </table>"#;
+        let selector = WalletSelectors::default().wallet_link().unwrap();
+        
+        let wallets = CEXScraper::extract_wallets_from_html_static(html, &selector, "Binance", "https://etherscan.io/accounts", None);
+        
+        let labels: Vec<_> = wallets.iter().map(|wallet| wallet.label.as_deref()).collect();
+        assert_eq!(labels, [Some("Binance 14"), Some("OKX 3"), None]);
+        assert!(wallets.iter().all(|wallet| wallet.exchange_name == "Binance"));
+        assert!(label_matches("Binance 14", "binance"));
+        assert!(!label_matches("OKX 3", "Binance"));
+    }
+
+    #[test]
+    fn href_address_prefers_the_account() {
+        let token = "0xdAC17F958D2ee523a2206206994597C13D831ec7";
+        let account = "0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed";
+        assert_eq!(href_address(&format!("/token/{}?a={}", token, account)), Some(account));
+        assert_eq!(href_address(&format!("/token/{}/address/{}", token, account)), Some(account));
+        assert_eq!(href_address(&format!("/tx/{}", token)), Some(token));
+        assert_eq!(href_address("/address/0x123"), None);
+
+        let html = format!(r#"<a href="/address/{}?token={}">a</a>"#, account.to_lowercase(), token);
+        let selector = WalletSelectors::default().wallet_link().unwrap();
+        let wallets = CEXScraper::extract_wallets_from_html_static(&html, &selector, "Binance", "https://etherscan.io/accounts", None);
+        assert_eq!(wallets.len(), 1);
+        assert_eq!(wallets[0].wallet_address, account);
+    }
+
+    #[test]
+    fn split_output_files_use_sanitized_exchange_names() {
+        let files = output_files(&[OutputFormat::Ndjson], Some("Crypto.com Exchange"), &OutputPaths::default());
+        assert_eq!(files, vec![(OutputFormat::Ndjson, "wallets_crypto_com_exchange.ndjson".to_string())]);
+        
+        let files: Vec<String> = output_files(&[OutputFormat::Json, OutputFormat::Csv], Some("OKX"), &OutputPaths::default()).into_iter().map(|(_, file)| file).collect();
+        assert_eq!(files, vec!["wallets_okx.json", "wallets_okx.csv"]);
+        
+        let files = output_files(&[OutputFormat::Addresses], Some("OKX"), &OutputPaths::default());
+        assert_eq!(files, vec![(OutputFormat::Addresses, "wallets_okx.txt".to_string())]);
+        
+        let files: Vec<String> = output_files(&[OutputFormat::Json, OutputFormat::Csv], None, &OutputPaths::default()).into_iter().map(|(_, file)| file).collect();
+        assert_eq!(files, vec![JSON_OUTPUT_FILE, CSV_OUTPUT_FILE]);
+        
+        let paths = OutputPaths { json_out: Some("-".to_string()), ..OutputPaths::default() };
+        let files: Vec<String> = output_files(&[OutputFormat::Json, OutputFormat::Csv], None, &paths).into_iter().map(|(_, file)| file).collect();
+        assert_eq!(files, vec!["-", CSV_OUTPUT_FILE]);
+        let files: Vec<String> = output_files(&[OutputFormat::Json, OutputFormat::Csv], Some("OKX"), &paths).into_iter().map(|(_, file)| file).collect();
+        assert_eq!(files, vec!["wallets_okx.json", "wallets_okx.csv"]);
+        
+        let paths = OutputPaths { out_dir: Some(PathBuf::from("runs")), csv_out: Some("all.csv".to_string()), ..OutputPaths::default() };
+        let files: Vec<PathBuf> = output_files(&[OutputFormat::Json, OutputFormat::Csv], None, &paths).into_iter().map(|(_, file)| PathBuf::from(file)).collect();
+        assert_eq!(files, vec![Path::new("runs").join(JSON_OUTPUT_FILE), PathBuf::from("all.csv")]);
+        assert_eq!(paths.state_file(), Path::new("runs").join(STATE_FILE));
+    }
+
+    #[test]
+    fn every_format_gets_the_same_records() {
+        let dir = std::env::temp_dir().join(format!("cex-formats-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let paths = OutputPaths { out_dir: Some(dir.clone()), ..OutputPaths::default() };
+        let wallet = |exchange: &str, address: &str| WalletRecord {
+            exchange_name: exchange.to_string(),
+            wallet_address: address.to_string(),
+            source_urls: Vec::new(),
+            balance_wei: None,
+            exchange_names: Vec::new(),
+            multi_exchange: false,
+            label: None,
+            funded_by: None,
+        };
+        
+        let mut writer = WalletWriter::new(vec![OutputFormat::Ndjson, OutputFormat::Sqlite], true, paths, false, Vec::new());
+        writer.write(&wallet("Binance", "0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed"));
+        writer.write(&wallet("OKX", "0xfB6916095ca1df60bB79Ce92cE3Ea74c37c5d359"));
+        writer.finish(&HashMap::new());
+        
+        assert_eq!(std::fs::read_to_string(dir.join("wallets_binance.ndjson")).unwrap().lines().count(), 1);
+        assert_eq!(std::fs::read_to_string(dir.join("wallets_okx.ndjson")).unwrap().lines().count(), 1);
+        let conn = rusqlite::Connection::open(dir.join(SQLITE_OUTPUT_FILE)).unwrap();
+        let stored: usize = conn.query_row("SELECT COUNT(*) FROM wallets", [], |row| row.get(0)).unwrap();
+        assert_eq!(stored, 2);
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+    
+    #[test]
+    fn wallets_matched_later_by_another_exchange_are_flagged_on_finish() {
+        let dir = std::env::temp_dir().join(format!("cex-multi-exchange-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let paths = OutputPaths { out_dir: Some(dir.clone()), ..OutputPaths::default() };
+        let wallet = |address: &str| WalletRecord {
+            exchange_name: "Binance".to_string(),
+            wallet_address: address.to_string(),
+            source_urls: Vec::new(),
+            balance_wei: None,
+            exchange_names: vec!["Binance".to_string()],
+            multi_exchange: false,
+            label: None,
+            funded_by: None,
+        };
+        let multi_exchange = HashMap::from([(
+            "0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed".to_string(),
+            vec!["Binance".to_string(), "OKX".to_string()],
+        )]);
+        
+        let formats = vec![
+            OutputFormat::Json,
+            OutputFormat::Csv,
+            OutputFormat::Ndjson,
+            OutputFormat::Xlsx,
+            OutputFormat::Sqlite,
+        ];
+        let mut writer = WalletWriter::new(formats, false, paths, false, Vec::new());
+        writer.write(&wallet("0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed"));
+        writer.write(&wallet("0xfB6916095ca1df60bB79Ce92cE3Ea74c37c5d359"));
+        assert_eq!(writer.finish(&multi_exchange).len(), 4);
+        
+        for file in [JSON_OUTPUT_FILE, CSV_OUTPUT_FILE, NDJSON_OUTPUT_FILE] {
+            let wallets: Vec<WalletRecord> = merge::read_records(&dir.join(file)).unwrap().into_iter().map(Result::unwrap).collect();
+            assert_eq!(wallets.len(), 2, "{}", file);
+            assert!(wallets[0].multi_exchange, "{}", file);
+            assert_eq!(wallets[0].exchange_names, ["Binance", "OKX"], "{}", file);
+            assert!(!wallets[1].multi_exchange, "{}", file);
+            assert_eq!(wallets[1].exchange_names, ["Binance"], "{}", file);
+        }
+        let conn = writer::open_sqlite(&dir.join(SQLITE_OUTPUT_FILE)).unwrap();
+        let flags: Vec<(String, bool)> = conn
+            .prepare("SELECT exchange_names, multi_exchange FROM wallets ORDER BY rowid")
+            .unwrap()
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(flags, [("Binance;OKX".to_string(), true), ("Binance".to_string(), false)]);
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn page_range_defaults_to_the_first_three_pages() {
+        let args = Args::parse_from(["cex-wallet-scraper"]);
+        assert_eq!((args.start_page, args.end_page), (None, 3));
+        
+        let args = Args::parse_from(["cex-wallet-scraper", "--restart-page", "4", "--max-pages", "6"]);
+        assert_eq!((args.start_page, args.end_page), (Some(4), 6));
+    }
+
+    #[test]
+    fn repeated_formats_are_written_once_with_sqlite_from_its_path() {
+        let paths = OutputPaths::default();
+        assert_eq!(output_formats(&[], &paths), [OutputFormat::Json, OutputFormat::Csv]);
+        assert_eq!(
+            output_formats(&[OutputFormat::Ndjson, OutputFormat::Sqlite, OutputFormat::Ndjson], &paths),
+            [OutputFormat::Ndjson, OutputFormat::Sqlite]
+        );
+        
+        let paths = OutputPaths { sqlite_out: Some(PathBuf::from("wallets.db")), ..OutputPaths::default() };
+        assert_eq!(output_formats(&[], &paths), [OutputFormat::Json, OutputFormat::Csv, OutputFormat::Sqlite]);
+        let files = output_files(&[OutputFormat::Sqlite], Some("OKX"), &paths);
+        assert_eq!(files, vec![(OutputFormat::Sqlite, "wallets.db".to_string())]);
+    }
+
+    #[test]
+    fn base_url_replaces_the_host() {
+        let base_url = Url::parse("http://127.0.0.1:4000").unwrap();
+        assert_eq!(rebase_url("https://etherscan.io/accounts", &base_url), "http://127.0.0.1:4000/accounts");
+        
+        let base_url = Url::parse("https://mirror.example/etherscan/").unwrap();
+        assert_eq!(rebase_url("https://etherscan.io/accounts", &base_url), "https://mirror.example/etherscan/accounts");
+    }
+
+    #[test]
+    fn chain_id_switches_to_eip1191() {
+        // EIP-1191 vector for RSK mainnet (chain id 30)
+        let address = "0x5aaEB6053f3e94c9b9a09f33669435E7ef1bEAeD";
+        assert!(CEXScraper::verify_checksum(address, Some(30)));
+        assert!(!CEXScraper::verify_checksum(address, None));
+    }
+}
@@ -0,0 +1,62 @@
+use lru::LruCache;
+use std::num::NonZeroUsize;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+
+/// Bounded in-process cache of page bodies by URL, shared by every clone of a `CEXScraper`
+/// so repeated fetches within a run skip the network. Unlike `PageCache` nothing outlives the run.
+#[derive(Debug)]
+pub(crate) struct MemoryCache {
+    pages: Mutex<LruCache<String, String>>,
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+impl MemoryCache {
+    pub(crate) fn new(capacity: NonZeroUsize) -> Self {
+        Self {
+            pages: Mutex::new(LruCache::new(capacity)),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+        }
+    }
+
+    pub(crate) fn get(&self, url: &str) -> Option<String> {
+        let body = self.pages.lock().expect("memory cache lock poisoned").get(url).cloned();
+        let counter = if body.is_some() { &self.hits } else { &self.misses };
+        counter.fetch_add(1, Ordering::Relaxed);
+        body
+    }
+
+    /// Stores a body, evicting the least recently used page when full
+    pub(crate) fn put(&self, url: &str, body: &str) {
+        self.pages.lock().expect("memory cache lock poisoned").put(url.to_string(), body.to_string());
+    }
+
+    pub(crate) fn hits(&self) -> u64 {
+        self.hits.load(Ordering::Relaxed)
+    }
+
+    pub(crate) fn misses(&self) -> u64 {
+        self.misses.load(Ordering::Relaxed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn least_recently_used_page_is_evicted() {
+        let cache = MemoryCache::new(NonZeroUsize::new(2).unwrap());
+        cache.put("a", "page a");
+        cache.put("b", "page b");
+        assert_eq!(cache.get("a").as_deref(), Some("page a"));
+
+        cache.put("c", "page c");
+
+        assert_eq!(cache.get("b"), None);
+        assert_eq!(cache.get("a").as_deref(), Some("page a"));
+        assert_eq!((cache.hits(), cache.misses()), (2, 1));
+    }
+}
@@ -0,0 +1,176 @@
+use crate::{
+    flag_multi_exchange, merge_duplicates, sort_wallets, to_checksum_address, writer, OutputFormat, ScraperError, WalletRecord,
+};
+use clap::Parser;
+use log::{info, warn};
+use serde::Deserialize;
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
+
+#[derive(Parser, Debug, Clone)]
+pub struct MergeArgs {
+    /// JSON, NDJSON or CSV wallet files from earlier runs
+    #[arg(required = true)]
+    pub files: Vec<PathBuf>,
+
+    /// Consolidated output file
+    #[arg(long)]
+    pub out: PathBuf,
+
+    /// Output format; defaults to the one matching the `--out` extension, or JSON
+    #[arg(long, value_enum)]
+    pub format: Option<OutputFormat>,
+
+    /// Checksum addresses with EIP-1191 for this chain id instead of EIP-55
+    #[arg(long)]
+    pub chain_id: Option<u64>,
+}
+
+/// A CSV row as written by the scraper, with `source_urls` joined by `;`
+#[derive(Debug, Deserialize)]
+pub(crate) struct CsvRow {
+    exchange_name: String,
+    wallet_address: String,
+    source_urls: String,
+    #[serde(default)]
+    balance_wei: Option<String>,
+    #[serde(default)]
+    exchange_names: String,
+    #[serde(default)]
+    multi_exchange: bool,
+    #[serde(default)]
+    label: Option<String>,
+    #[serde(default)]
+    funded_by: Option<String>,
+}
+
+impl From<CsvRow> for WalletRecord {
+    fn from(row: CsvRow) -> Self {
+        WalletRecord {
+            exchange_name: row.exchange_name,
+            wallet_address: row.wallet_address,
+            source_urls: row.source_urls.split(';').filter(|url| !url.is_empty()).map(str::to_string).collect(),
+            balance_wei: row.balance_wei.filter(|balance| !balance.is_empty()),
+            exchange_names: row.exchange_names.split(';').filter(|name| !name.is_empty()).map(str::to_string).collect(),
+            multi_exchange: row.multi_exchange,
+            label: row.label.filter(|label| !label.is_empty()),
+            funded_by: row.funded_by.filter(|funder| !funder.is_empty()),
+        }
+    }
+}
+
+/// The format a file extension stands for
+fn format_of(path: &Path) -> Option<OutputFormat> {
+    match path.extension()?.to_str()?.to_ascii_lowercase().as_str() {
+        "json" => Some(OutputFormat::Json),
+        "csv" => Some(OutputFormat::Csv),
+        "ndjson" | "jsonl" => Some(OutputFormat::Ndjson),
+        "txt" => Some(OutputFormat::Addresses),
+        "xlsx" => Some(OutputFormat::Xlsx),
+        "db" | "sqlite" => Some(OutputFormat::Sqlite),
+        _ => None,
+    }
+}
+
+fn read_wallets(path: &Path) -> Result<Vec<WalletRecord>, ScraperError> {
+    read_records(path)?.into_iter().collect()
+}
+
+/// Reads a JSON, NDJSON or CSV wallet file record by record, so one malformed record
+/// doesn't hide the rest. Only a file that can't be read at all is an error.
+pub(crate) fn read_records(path: &Path) -> Result<Vec<Result<WalletRecord, ScraperError>>, ScraperError> {
+    let reader = BufReader::new(File::open(path)?);
+    match format_of(path) {
+        Some(OutputFormat::Json) => Ok(serde_json::from_reader::<_, Vec<serde_json::Value>>(reader)?
+            .into_iter()
+            .map(|record| Ok(serde_json::from_value(record)?))
+            .collect()),
+        Some(OutputFormat::Ndjson) => Ok(reader
+            .lines()
+            .filter(|line| !line.as_ref().is_ok_and(|line| line.trim().is_empty()))
+            .map(|line| Ok(serde_json::from_str(&line?)?))
+            .collect()),
+        Some(OutputFormat::Csv) => Ok(csv::Reader::from_reader(reader)
+            .deserialize::<CsvRow>()
+            .map(|row| Ok(row?.into()))
+            .collect()),
+        Some(OutputFormat::Addresses | OutputFormat::Xlsx | OutputFormat::Sqlite) | None => Err(ScraperError::Config(format!(
+            "{} is not a JSON, NDJSON or CSV wallet file",
+            path.display()
+        ))),
+    }
+}
+
+/// Combines wallet files into one, checksumming addresses and merging the source URLs
+/// of addresses found in several files, sorted by exchange and address
+pub fn merge_files(args: &MergeArgs) -> Result<Vec<WalletRecord>, ScraperError> {
+    let mut wallets = Vec::new();
+    for path in &args.files {
+        let records = read_wallets(path)?;
+        info!("Read {} wallets from {}", records.len(), path.display());
+        wallets.extend(records);
+    }
+
+    let total = wallets.len();
+    let mut wallets: Vec<_> = wallets
+        .into_iter()
+        .filter_map(|mut wallet| {
+            wallet.wallet_address = to_checksum_address(&wallet.wallet_address.to_lowercase(), args.chain_id)?;
+            Some(wallet)
+        })
+        .collect();
+    if wallets.len() < total {
+        warn!("Dropped {} malformed addresses", total - wallets.len());
+    }
+
+    let multi_exchange = flag_multi_exchange(&mut wallets);
+    if multi_exchange > 0 {
+        warn!("{} addresses appear under more than one exchange", multi_exchange);
+    }
+    let mut merged = merge_duplicates(wallets);
+    sort_wallets(&mut merged);
+    let format = args.format.or_else(|| format_of(&args.out)).unwrap_or(OutputFormat::Json);
+    writer::write_wallets(format, &args.out.to_string_lossy(), &merged)?;
+    info!("Merged {} records into {} wallets in {}", total, merged.len(), args.out.display());
+    Ok(merged)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn merges_json_and_csv_files() {
+        let dir = std::env::temp_dir().join(format!("cex-merge-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let json = dir.join("a.json");
+        let csv = dir.join("b.csv");
+        let out = dir.join("merged.ndjson");
+        std::fs::write(
+            &json,
+            r#"[{"exchange_name": "Binance", "wallet_address": "0x5aaeb6053f3e94c9b9a09f33669435e7ef1beaed", "source_urls": ["https://etherscan.io/a"]}]"#,
+        )
+        .unwrap();
+        std::fs::write(
+            &csv,
+            "exchange_name,wallet_address,source_urls,balance_wei\n\
+             Binance,0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed,https://etherscan.io/b;https://etherscan.io/a,\n\
+             Kraken,0xfb6916095ca1df60bb79ce92ce3ea74c37c5d359,https://etherscan.io/c,100\n\
+             Kraken,not-an-address,https://etherscan.io/c,\n",
+        )
+        .unwrap();
+
+        let args = MergeArgs { files: vec![json, csv], out: out.clone(), format: None, chain_id: None };
+        let merged = merge_files(&args).unwrap();
+
+        assert_eq!(merged.len(), 2);
+        assert_eq!(merged[0].wallet_address, "0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed");
+        assert_eq!(merged[0].source_urls, vec!["https://etherscan.io/a", "https://etherscan.io/b"]);
+        assert_eq!(merged[1].balance_wei.as_deref(), Some("100"));
+        assert!(!merged[0].multi_exchange);
+        assert_eq!(read_wallets(&out).unwrap().len(), 2);
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
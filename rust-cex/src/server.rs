@@ -0,0 +1,200 @@
+use crate::{ScraperError, WalletRecord, JSON_OUTPUT_FILE};
+use axum::extract::{Path as UrlPath, Query, State};
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::routing::get;
+use axum::{Json, Router};
+use clap::Parser;
+use log::{error, info};
+use rusqlite::{Connection, OpenFlags};
+use serde::Deserialize;
+use std::fs::File;
+use std::io::BufReader;
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+#[derive(Parser, Debug, Clone)]
+pub struct ServeArgs {
+    /// Address the HTTP server listens on
+    #[arg(long, default_value = "127.0.0.1:8080")]
+    pub bind: SocketAddr,
+
+    /// Serve wallets from this SQLite database written by `--sqlite-out`
+    #[arg(long, conflicts_with = "json")]
+    pub sqlite: Option<PathBuf>,
+
+    /// Serve wallets from this JSON output file, `cex_wallets.json` by default
+    #[arg(long)]
+    pub json: Option<PathBuf>,
+}
+
+/// Where lookups are answered from
+enum WalletStore {
+    Sqlite(Mutex<Connection>),
+    Memory(Vec<WalletRecord>),
+}
+
+impl WalletStore {
+    fn open(args: &ServeArgs) -> Result<Self, ScraperError> {
+        if let Some(path) = &args.sqlite {
+            let conn = Connection::open_with_flags(path, OpenFlags::SQLITE_OPEN_READ_ONLY)?;
+            info!("Serving wallets from {}", path.display());
+            return Ok(WalletStore::Sqlite(Mutex::new(conn)));
+        }
+
+        let path = args.json.as_deref().unwrap_or(Path::new(JSON_OUTPUT_FILE));
+        let wallets: Vec<WalletRecord> = serde_json::from_reader(BufReader::new(File::open(path)?))?;
+        info!("Serving {} wallets from {}", wallets.len(), path.display());
+        Ok(WalletStore::Memory(wallets))
+    }
+
+    /// Every wallet, or those of one exchange matched case-insensitively
+    fn wallets(&self, exchange: Option<&str>) -> Result<Vec<WalletRecord>, ScraperError> {
+        match self {
+            WalletStore::Sqlite(conn) => {
+                let conn = conn.lock().expect("store lock poisoned");
+                let mut stmt = conn.prepare_cached(
+                    "SELECT exchange_name, wallet_address, source_urls FROM wallets
+                     WHERE ?1 IS NULL OR lower(exchange_name) = lower(?1)
+                     ORDER BY rowid",
+                )?;
+                let rows = stmt.query_map([exchange], wallet_from_row)?;
+                Ok(rows.collect::<Result<_, _>>()?)
+            }
+            WalletStore::Memory(wallets) => Ok(wallets
+                .iter()
+                .filter(|wallet| exchange.is_none_or(|name| wallet.exchange_name.eq_ignore_ascii_case(name)))
+                .cloned()
+                .collect()),
+        }
+    }
+
+    /// The wallet with this address, ignoring checksum case
+    fn wallet(&self, address: &str) -> Result<Option<WalletRecord>, ScraperError> {
+        match self {
+            WalletStore::Sqlite(conn) => {
+                let conn = conn.lock().expect("store lock poisoned");
+                let mut stmt = conn.prepare_cached(
+                    "SELECT exchange_name, wallet_address, source_urls FROM wallets
+                     WHERE lower(wallet_address) = lower(?1)",
+                )?;
+                let mut rows = stmt.query_map([address], wallet_from_row)?;
+                Ok(rows.next().transpose()?)
+            }
+            WalletStore::Memory(wallets) => Ok(wallets
+                .iter()
+                .find(|wallet| wallet.wallet_address.eq_ignore_ascii_case(address))
+                .cloned()),
+        }
+    }
+}
+
+fn wallet_from_row(row: &rusqlite::Row) -> rusqlite::Result<WalletRecord> {
+    let source_urls: String = row.get(2)?;
+    Ok(WalletRecord {
+        exchange_name: row.get(0)?,
+        wallet_address: row.get(1)?,
+        source_urls: source_urls.split(';').filter(|url| !url.is_empty()).map(str::to_string).collect(),
+        balance_wei: None,
+        exchange_names: Vec::new(),
+        multi_exchange: false,
+        label: None,
+        funded_by: None,
+    })
+}
+
+#[derive(Debug, Deserialize)]
+struct WalletsQuery {
+    exchange: Option<String>,
+}
+
+/// Logs a store failure and answers with a bare 500
+struct ServerError(ScraperError);
+
+impl IntoResponse for ServerError {
+    fn into_response(self) -> Response {
+        error!("Wallet lookup failed: {}", self.0);
+        StatusCode::INTERNAL_SERVER_ERROR.into_response()
+    }
+}
+
+async fn health() -> &'static str {
+    "ok"
+}
+
+async fn list_wallets(
+    State(store): State<Arc<WalletStore>>,
+    Query(query): Query<WalletsQuery>,
+) -> Result<Json<Vec<WalletRecord>>, ServerError> {
+    store.wallets(query.exchange.as_deref()).map(Json).map_err(ServerError)
+}
+
+async fn get_wallet(State(store): State<Arc<WalletStore>>, UrlPath(address): UrlPath<String>) -> Result<Response, ServerError> {
+    Ok(match store.wallet(&address).map_err(ServerError)? {
+        Some(wallet) => Json(wallet).into_response(),
+        None => StatusCode::NOT_FOUND.into_response(),
+    })
+}
+
+fn router(store: WalletStore) -> Router {
+    Router::new()
+        .route("/health", get(health))
+        .route("/wallets", get(list_wallets))
+        .route("/wallets/{address}", get(get_wallet))
+        .with_state(Arc::new(store))
+}
+
+/// Serves wallet lookups over HTTP until interrupted
+pub async fn serve(args: ServeArgs) -> anyhow::Result<()> {
+    let app = router(WalletStore::open(&args)?);
+    let listener = tokio::net::TcpListener::bind(args.bind).await?;
+    info!("Listening on http://{}", listener.local_addr()?);
+    axum::serve(listener, app)
+        .with_graceful_shutdown(async {
+            let _ = tokio::signal::ctrl_c().await;
+        })
+        .await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn wallet(exchange: &str, address: &str) -> WalletRecord {
+        WalletRecord {
+            exchange_name: exchange.to_string(),
+            wallet_address: address.to_string(),
+            source_urls: vec!["https://etherscan.io/accounts?q=test&p=1".to_string()],
+            balance_wei: None,
+            exchange_names: Vec::new(),
+            multi_exchange: false,
+            label: None,
+            funded_by: None,
+        }
+    }
+
+    #[test]
+    fn sqlite_and_memory_stores_agree() {
+        let conn = crate::writer::open_sqlite(Path::new(":memory:")).unwrap();
+        let wallets = vec![
+            wallet("Binance", "0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed"),
+            wallet("Kraken", "0xfB6916095ca1df60bB79Ce92cE3Ea74c37c5d359"),
+        ];
+        for wallet in &wallets {
+            crate::writer::insert_wallet(&conn, wallet).unwrap();
+        }
+
+        for store in [WalletStore::Sqlite(Mutex::new(conn)), WalletStore::Memory(wallets)] {
+            assert_eq!(store.wallets(None).unwrap().len(), 2);
+            let binance = store.wallets(Some("binance")).unwrap();
+            assert_eq!(binance.len(), 1);
+            assert_eq!(binance[0].source_urls.len(), 1);
+
+            let found = store.wallet("0xfb6916095ca1df60bb79ce92ce3ea74c37c5d359").unwrap().unwrap();
+            assert_eq!(found.exchange_name, "Kraken");
+            assert!(store.wallet("0x0000000000000000000000000000000000000001").unwrap().is_none());
+        }
+    }
+}
@@ -0,0 +1,100 @@
+use crate::ScraperError;
+use log::info;
+use scathat_core::write_atomic;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Request outcomes shared by every clone of a `CEXScraper`
+#[derive(Debug, Default)]
+pub(crate) struct RequestCounters {
+    requests: AtomicU64,
+    successes: AtomicU64,
+    rate_limited: AtomicU64,
+    failures: AtomicU64,
+    retries_exhausted: AtomicU64,
+}
+
+impl RequestCounters {
+    pub(crate) fn request(&self) {
+        self.requests.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn success(&self) {
+        self.successes.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn rate_limited(&self) {
+        self.rate_limited.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn failure(&self) {
+        self.failures.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn retries_exhausted(&self) {
+        self.retries_exhausted.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+/// Summary of one scrape, logged at the end of a run and optionally saved with `--stats-out`
+#[derive(Debug, Default, Serialize)]
+pub struct RunStats {
+    pub requests: u64,
+    pub successes: u64,
+    pub rate_limited: u64,
+    /// Failed requests other than 429s, including challenge pages and network errors
+    pub failures: u64,
+    /// Pages or API calls that failed on every attempt
+    pub retries_exhausted: u64,
+    /// Pages served from and missing in `--mem-cache-size`'s cache, when it is enabled
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mem_cache_hits: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mem_cache_misses: Option<u64>,
+    /// Wallets that passed and failed `--verify-labels`, when it ran
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub verified_wallets: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub unverified_wallets: Option<usize>,
+    pub wallets_per_exchange: BTreeMap<String, usize>,
+    pub unique_wallets: usize,
+    pub new_wallets: usize,
+    pub elapsed_secs: f64,
+}
+
+impl RunStats {
+    pub(crate) fn record_requests(&mut self, counters: &RequestCounters) {
+        self.requests = counters.requests.load(Ordering::Relaxed);
+        self.successes = counters.successes.load(Ordering::Relaxed);
+        self.rate_limited = counters.rate_limited.load(Ordering::Relaxed);
+        self.failures = counters.failures.load(Ordering::Relaxed);
+        self.retries_exhausted = counters.retries_exhausted.load(Ordering::Relaxed);
+    }
+
+    pub fn log_summary(&self) {
+        info!("Run summary:");
+        info!("  elapsed:           {:.1}s", self.elapsed_secs);
+        info!("  requests:          {}", self.requests);
+        info!("  successes:         {}", self.successes);
+        info!("  rate limited:      {}", self.rate_limited);
+        info!("  other failures:    {}", self.failures);
+        info!("  retries exhausted: {}", self.retries_exhausted);
+        if let (Some(hits), Some(misses)) = (self.mem_cache_hits, self.mem_cache_misses) {
+            info!("  memory cache:      {} hits, {} misses", hits, misses);
+        }
+        for (exchange, count) in &self.wallets_per_exchange {
+            info!("  {:<18} {} wallets", format!("{}:", exchange), count);
+        }
+        if let (Some(verified), Some(unverified)) = (self.verified_wallets, self.unverified_wallets) {
+            info!("  name tags:         {} verified, {} unverified", verified, unverified);
+        }
+        info!("  unique wallets:    {}", self.unique_wallets);
+        info!("  new wallets:       {}", self.new_wallets);
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), ScraperError> {
+        write_atomic(path, |writer| Ok(serde_json::to_writer_pretty(writer, self)?))
+    }
+}
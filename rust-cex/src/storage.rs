@@ -0,0 +1,44 @@
+use crate::ScraperError;
+use log::{info, warn};
+use scathat_core::S3Store;
+use std::path::Path;
+
+/// Uploads finished output files to S3-compatible object storage
+pub struct S3Uploader {
+    store: S3Store,
+}
+
+impl S3Uploader {
+    /// Credentials come from the usual AWS environment variables or profile.
+    /// A custom `endpoint` targets S3-compatible stores such as MinIO and uses path-style URLs.
+    pub fn new(bucket: &str, region: &str, endpoint: Option<&str>, prefix: &str) -> Result<Self, ScraperError> {
+        Ok(Self { store: S3Store::new(bucket, region, endpoint, prefix)? })
+    }
+
+    /// Uploads `path` under `<prefix>/<run_key>/<file name>`, retrying with backoff.
+    ///
+    /// Failures are logged rather than returned so a storage outage never fails the scrape itself.
+    pub async fn upload_file(&self, path: &Path, run_key: &str) {
+        let file_name = path.file_name().and_then(|name| name.to_str()).unwrap_or("output");
+        let key = self.store.key(&format!("{}/{}", run_key, file_name));
+
+        let content = match std::fs::read(path) {
+            Ok(content) => content,
+            Err(e) => {
+                warn!("Failed to read {} for S3 upload: {}", path.display(), e);
+                return;
+            }
+        };
+
+        let content_type = match path.extension().and_then(|ext| ext.to_str()) {
+            Some("json") => "application/json",
+            Some("csv") => "text/csv",
+            _ => "application/octet-stream",
+        };
+
+        match self.store.upload(&key, &content, content_type).await {
+            Ok(url) => info!("Uploaded {} to {}", path.display(), url),
+            Err(e) => warn!("Giving up on S3 upload of {}: {}", path.display(), e),
+        }
+    }
+}
@@ -0,0 +1,149 @@
+use crate::merge::read_records;
+use crate::{to_checksum_address, ScraperError};
+use clap::Parser;
+use log::warn;
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+#[derive(Parser, Debug, Clone)]
+pub struct StatsArgs {
+    /// JSON, NDJSON or CSV wallet file from an earlier run
+    pub file: PathBuf,
+
+    /// Print the aggregates as JSON instead of a table
+    #[arg(long)]
+    pub json: bool,
+
+    /// Check EIP-1191 checksums for this chain id instead of EIP-55
+    #[arg(long)]
+    pub chain_id: Option<u64>,
+}
+
+/// Aggregates over an existing wallet file, computed offline
+#[derive(Debug, Default, Serialize, PartialEq)]
+pub struct FileStats {
+    pub records: usize,
+    /// Records by `exchange_name`
+    pub wallets_per_exchange: BTreeMap<String, usize>,
+    /// Distinct addresses, ignoring case
+    pub unique_addresses: usize,
+    /// Addresses in more than one record, with how many records each is in
+    pub duplicates: BTreeMap<String, usize>,
+    /// Records whose address carries the correct checksum
+    pub checksummed: usize,
+    /// Records whose address isn't a 20-byte hex address at all
+    pub invalid_addresses: usize,
+    /// Addresses found under more than one exchange
+    pub multi_exchange: usize,
+    /// Records that didn't parse, by position and error
+    pub malformed: Vec<String>,
+}
+
+/// Reads `path` and recomputes its aggregates. Records that don't parse are counted as
+/// malformed rather than failing the whole file.
+pub fn file_stats(path: &Path, chain_id: Option<u64>) -> Result<FileStats, ScraperError> {
+    let mut stats = FileStats::default();
+    let mut records_per_address: BTreeMap<String, usize> = BTreeMap::new();
+    let mut exchanges_per_address: BTreeMap<String, BTreeSet<String>> = BTreeMap::new();
+
+    for (index, record) in read_records(path)?.into_iter().enumerate() {
+        let wallet = match record {
+            Ok(wallet) => wallet,
+            Err(e) => {
+                stats.malformed.push(format!("record {}: {}", index + 1, e));
+                continue;
+            }
+        };
+        stats.records += 1;
+        *stats.wallets_per_exchange.entry(wallet.exchange_name.clone()).or_default() += 1;
+        match to_checksum_address(&wallet.wallet_address.to_lowercase(), chain_id) {
+            Some(checksummed) if checksummed == wallet.wallet_address => stats.checksummed += 1,
+            Some(_) => {}
+            None => stats.invalid_addresses += 1,
+        }
+        let address = wallet.wallet_address.to_lowercase();
+        *records_per_address.entry(address.clone()).or_default() += 1;
+        exchanges_per_address.entry(address).or_default().insert(wallet.exchange_name);
+    }
+
+    stats.unique_addresses = records_per_address.len();
+    stats.duplicates = records_per_address.into_iter().filter(|(_, count)| *count > 1).collect();
+    stats.multi_exchange = exchanges_per_address.values().filter(|exchanges| exchanges.len() > 1).count();
+    Ok(stats)
+}
+
+/// Computes the aggregates of `args.file` and writes them to `out` as a table or JSON
+pub fn print_file_stats(args: &StatsArgs, out: &mut impl Write) -> Result<FileStats, ScraperError> {
+    let stats = file_stats(&args.file, args.chain_id)?;
+    for problem in &stats.malformed {
+        warn!("Malformed {} in {}", problem, args.file.display());
+    }
+
+    if args.json {
+        serde_json::to_writer_pretty(&mut *out, &stats)?;
+        writeln!(out)?;
+        return Ok(stats);
+    }
+
+    writeln!(out, "file:               {}", args.file.display())?;
+    writeln!(out, "records:            {}", stats.records)?;
+    writeln!(out, "malformed records:  {}", stats.malformed.len())?;
+    writeln!(out, "unique addresses:   {}", stats.unique_addresses)?;
+    writeln!(out, "duplicate records:  {}", stats.records - stats.unique_addresses)?;
+    writeln!(out, "checksummed:        {}", stats.checksummed)?;
+    writeln!(out, "invalid addresses:  {}", stats.invalid_addresses)?;
+    writeln!(out, "multi-exchange:     {}", stats.multi_exchange)?;
+
+    let width = stats.wallets_per_exchange.keys().map(String::len).max().unwrap_or_default().max("exchange".len());
+    writeln!(out)?;
+    writeln!(out, "{:width$}  wallets", "exchange")?;
+    for (exchange, count) in &stats.wallets_per_exchange {
+        writeln!(out, "{:width$}  {}", exchange, count)?;
+    }
+
+    if !stats.duplicates.is_empty() {
+        writeln!(out)?;
+        writeln!(out, "duplicated addresses:")?;
+        for (address, count) in &stats.duplicates {
+            writeln!(out, "  {} ({} records)", address, count)?;
+        }
+    }
+    Ok(stats)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn aggregates_count_duplicates_and_malformed_records() {
+        let path = std::env::temp_dir().join(format!("cex-wallets-stats-{}.ndjson", std::process::id()));
+        std::fs::write(
+            &path,
+            [
+                r#"{"exchange_name": "Binance", "wallet_address": "0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed", "source_urls": []}"#,
+                r#"{"exchange_name": "OKX", "wallet_address": "0x5aaeb6053f3e94c9b9a09f33669435e7ef1beaed", "source_urls": []}"#,
+                r#"{"exchange_name": "Binance", "wallet_address": "0xfB6916095ca1df60bB79Ce92cE3Ea74c37c5d359", "source_urls": []}"#,
+                r#"{"exchange_name": "Binance", "wallet_address": "not-an-address", "source_urls": []}"#,
+                r#"{"exchange_name": "Binance"}"#,
+            ]
+            .join("\n"),
+        )
+        .unwrap();
+
+        let stats = file_stats(&path, None).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(stats.records, 4);
+        assert_eq!(stats.wallets_per_exchange, BTreeMap::from([("Binance".to_string(), 3), ("OKX".to_string(), 1)]));
+        assert_eq!(stats.unique_addresses, 3);
+        assert_eq!(stats.duplicates, BTreeMap::from([("0x5aaeb6053f3e94c9b9a09f33669435e7ef1beaed".to_string(), 2)]));
+        assert_eq!(stats.checksummed, 2);
+        assert_eq!(stats.invalid_addresses, 1);
+        assert_eq!(stats.multi_exchange, 1);
+        assert_eq!(stats.malformed.len(), 1);
+        assert!(stats.malformed[0].starts_with("record 5:"));
+    }
+}
@@ -0,0 +1,594 @@
+use crate::merge::CsvRow;
+use crate::{output_files, OutputFormat, OutputPaths, ScraperError, WalletRecord};
+use csv::{Writer, WriterBuilder};
+use log::{error, info};
+use rusqlite::Connection;
+use rust_xlsxwriter::{Format, Workbook, Worksheet};
+use scathat_core::AtomicFile;
+use serde::de::{self, Deserializer, SeqAccess, Visitor};
+use std::fmt;
+use std::fs::{File, OpenOptions};
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::collections::HashMap;
+use std::path::Path;
+
+/// Records written between flushes, so `tail -f` sees progress during a long run
+const FLUSH_EVERY: usize = 100;
+pub(crate) const CSV_HEADER: [&str; 8] = [
+    "exchange_name",
+    "wallet_address",
+    "source_urls",
+    "balance_wei",
+    "exchange_names",
+    "multi_exchange",
+    "label",
+    "funded_by",
+];
+
+/// The output filename that stands for standard output
+pub(crate) const STDOUT: &str = "-";
+
+/// Standard output, a file appended to in place, or a file that replaces its target on `commit`
+pub(crate) enum Output {
+    Stdout(io::Stdout),
+    Append(File),
+    Replace(AtomicFile),
+}
+
+impl Output {
+    /// Moves a replacing file into place; the other outputs are already where they belong
+    pub(crate) fn commit(self) -> io::Result<()> {
+        match self {
+            Output::Replace(file) => file.commit(),
+            Output::Stdout(_) | Output::Append(_) => Ok(()),
+        }
+    }
+}
+
+impl Write for Output {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            Output::Stdout(stdout) => stdout.write(buf),
+            Output::Append(file) => file.write(buf),
+            Output::Replace(file) => file.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            Output::Stdout(stdout) => stdout.flush(),
+            Output::Append(file) => file.flush(),
+            Output::Replace(file) => file.flush(),
+        }
+    }
+}
+
+/// Starts a temporary file that replaces `filename` once committed, so a crash mid-run
+/// leaves the previous output whole, or hands out standard output for `-`
+pub(crate) fn create_output(filename: &str) -> io::Result<Output> {
+    if filename == STDOUT {
+        Ok(Output::Stdout(io::stdout()))
+    } else {
+        Ok(Output::Replace(AtomicFile::create(Path::new(filename))?))
+    }
+}
+
+/// Flushes `writer` and commits its output
+fn commit(writer: BufWriter<Output>) -> Result<(), ScraperError> {
+    Ok(writer.into_inner().map_err(|e| e.into_error())?.commit()?)
+}
+
+/// Opens a CSV file for appending, writing the header only when the file is new or empty.
+/// Standard output always starts with the header.
+pub(crate) fn csv_appender(filename: &str) -> Result<Writer<Output>, ScraperError> {
+    let (output, is_empty): (Output, bool) = if filename == STDOUT {
+        (Output::Stdout(io::stdout()), true)
+    } else {
+        let file = OpenOptions::new().create(true).append(true).open(filename)?;
+        let is_empty = file.metadata()?.len() == 0;
+        (Output::Append(file), is_empty)
+    };
+    let mut writer = WriterBuilder::new().has_headers(false).from_writer(output);
+    if is_empty {
+        writer.write_record(CSV_HEADER)?;
+    }
+    Ok(writer)
+}
+
+/// A destination for wallet records, one per output format; implement it to send the new
+/// wallets of a run somewhere else, see `run_with_sinks`
+pub trait WalletSink: Send {
+    fn write(&mut self, records: &[WalletRecord]) -> Result<(), ScraperError>;
+
+    /// Pushes buffered records out, so a long run shows progress
+    fn flush(&mut self) -> Result<(), ScraperError> {
+        Ok(())
+    }
+
+    /// Gives the addresses in `multi_exchange`, which another exchange matched after they
+    /// were written, their full list of exchanges; called before `finish`. The default
+    /// leaves the records as written, which is all a stream like standard output can do.
+    fn flag_multi_exchange(&mut self, _multi_exchange: &HashMap<String, Vec<String>>) -> Result<(), ScraperError> {
+        Ok(())
+    }
+
+    /// Completes the output, e.g. closing a JSON array or saving a workbook, and moves a
+    /// file written through a temporary file into place
+    fn finish(self: Box<Self>) -> Result<(), ScraperError>;
+}
+
+/// Opens the sink for `format` at `filename`, or standard output for `-`
+pub fn open_sink(format: OutputFormat, filename: &str, append_csv: bool) -> Result<Box<dyn WalletSink>, ScraperError> {
+    Ok(match format {
+        OutputFormat::Json => {
+            let mut writer = BufWriter::new(create_output(filename)?);
+            writer.write_all(b"[")?;
+            Box::new(JsonSink { writer, empty: true })
+        }
+        OutputFormat::Csv if append_csv => Box::new(CsvSink(csv_appender(filename)?)),
+        OutputFormat::Csv => {
+            let mut writer = Writer::from_writer(create_output(filename)?);
+            writer.write_record(CSV_HEADER)?;
+            Box::new(CsvSink(writer))
+        }
+        OutputFormat::Ndjson => Box::new(NdjsonSink(BufWriter::new(create_output(filename)?))),
+        OutputFormat::Addresses => Box::new(AddressesSink(BufWriter::new(create_output(filename)?))),
+        OutputFormat::Xlsx => Box::new(XlsxSink::new(filename)?),
+        OutputFormat::Sqlite if filename == STDOUT => {
+            return Err(ScraperError::Config("a SQLite database can't be written to stdout".to_string()))
+        }
+        OutputFormat::Sqlite => Box::new(SqliteSink::open(Path::new(filename))?),
+    })
+}
+
+/// A JSON array whose closing bracket is written by `finish`
+struct JsonSink {
+    writer: BufWriter<Output>,
+    empty: bool,
+}
+
+impl WalletSink for JsonSink {
+    fn write(&mut self, records: &[WalletRecord]) -> Result<(), ScraperError> {
+        for wallet in records {
+            // Indent each pretty-printed record to match a pretty-printed array
+            let json = serde_json::to_string_pretty(wallet)?.replace('\n', "\n  ");
+            self.writer.write_all(if self.empty { b"\n  " } else { b",\n  " })?;
+            self.writer.write_all(json.as_bytes())?;
+            self.empty = false;
+        }
+        Ok(())
+    }
+
+    fn flush(&mut self) -> Result<(), ScraperError> {
+        Ok(self.writer.flush()?)
+    }
+
+    fn finish(mut self: Box<Self>) -> Result<(), ScraperError> {
+        self.writer.write_all(if self.empty { b"]" } else { b"\n]" })?;
+        commit(self.writer)
+    }
+}
+
+struct CsvSink(Writer<Output>);
+
+impl WalletSink for CsvSink {
+    fn write(&mut self, records: &[WalletRecord]) -> Result<(), ScraperError> {
+        for wallet in records {
+            self.0.write_record([
+                wallet.exchange_name.as_str(),
+                &wallet.wallet_address,
+                &wallet.joined_source_urls(),
+                wallet.balance_wei.as_deref().unwrap_or_default(),
+                &wallet.joined_exchange_names(),
+                if wallet.multi_exchange { "true" } else { "false" },
+                wallet.label.as_deref().unwrap_or_default(),
+                wallet.funded_by.as_deref().unwrap_or_default(),
+            ])?;
+        }
+        Ok(())
+    }
+
+    fn flush(&mut self) -> Result<(), ScraperError> {
+        Ok(self.0.flush()?)
+    }
+
+    fn finish(self: Box<Self>) -> Result<(), ScraperError> {
+        Ok(self.0.into_inner().map_err(|e| e.into_error())?.commit()?)
+    }
+}
+
+struct NdjsonSink(BufWriter<Output>);
+
+impl WalletSink for NdjsonSink {
+    fn write(&mut self, records: &[WalletRecord]) -> Result<(), ScraperError> {
+        for wallet in records {
+            serde_json::to_writer(&mut self.0, wallet)?;
+            self.0.write_all(b"\n")?;
+        }
+        Ok(())
+    }
+
+    fn flush(&mut self) -> Result<(), ScraperError> {
+        Ok(self.0.flush()?)
+    }
+
+    fn finish(self: Box<Self>) -> Result<(), ScraperError> {
+        commit(self.0)
+    }
+}
+
+struct AddressesSink(BufWriter<Output>);
+
+impl WalletSink for AddressesSink {
+    fn write(&mut self, records: &[WalletRecord]) -> Result<(), ScraperError> {
+        for wallet in records {
+            writeln!(self.0, "{}", wallet.wallet_address)?;
+        }
+        Ok(())
+    }
+
+    fn flush(&mut self) -> Result<(), ScraperError> {
+        Ok(self.0.flush()?)
+    }
+
+    fn finish(self: Box<Self>) -> Result<(), ScraperError> {
+        commit(self.0)
+    }
+}
+
+/// A worksheet with the CSV columns, built in memory since the file can only be written whole
+struct XlsxSink {
+    worksheet: Worksheet,
+    row: u32,
+    /// The row of each address, to flag it when another exchange matches it later
+    rows: HashMap<String, u32>,
+    filename: String,
+}
+
+impl XlsxSink {
+    fn new(filename: &str) -> Result<Self, ScraperError> {
+        let mut worksheet = Worksheet::new();
+        worksheet.set_name("Wallets")?;
+        worksheet.write_row_with_format(0, 0, CSV_HEADER, &Format::new().set_bold())?;
+        worksheet.set_freeze_panes(1, 0)?;
+        Ok(Self { worksheet, row: 1, rows: HashMap::new(), filename: filename.to_string() })
+    }
+}
+
+impl WalletSink for XlsxSink {
+    fn write(&mut self, records: &[WalletRecord]) -> Result<(), ScraperError> {
+        for wallet in records {
+            let row = self.row;
+            self.worksheet.write_string(row, 0, &wallet.exchange_name)?;
+            self.worksheet.write_string(row, 1, &wallet.wallet_address)?;
+            self.worksheet.write_string(row, 2, wallet.joined_source_urls())?;
+            self.worksheet.write_string(row, 3, wallet.balance_wei.as_deref().unwrap_or_default())?;
+            self.worksheet.write_string(row, 4, wallet.joined_exchange_names())?;
+            self.worksheet.write_boolean(row, 5, wallet.multi_exchange)?;
+            self.worksheet.write_string(row, 6, wallet.label.as_deref().unwrap_or_default())?;
+            self.worksheet.write_string(row, 7, wallet.funded_by.as_deref().unwrap_or_default())?;
+            self.rows.insert(wallet.wallet_address.clone(), row);
+            self.row += 1;
+        }
+        Ok(())
+    }
+
+    fn flag_multi_exchange(&mut self, multi_exchange: &HashMap<String, Vec<String>>) -> Result<(), ScraperError> {
+        for (address, names) in multi_exchange {
+            if let Some(&row) = self.rows.get(address) {
+                self.worksheet.write_string(row, 4, names.join(";"))?;
+                self.worksheet.write_boolean(row, 5, true)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Fits the columns to their contents and writes the workbook
+    fn finish(mut self: Box<Self>) -> Result<(), ScraperError> {
+        self.worksheet.autofit();
+        let mut workbook = Workbook::new();
+        workbook.push_worksheet(self.worksheet);
+        let mut output = create_output(&self.filename)?;
+        output.write_all(&workbook.save_to_buffer()?)?;
+        output.flush()?;
+        Ok(output.commit()?)
+    }
+}
+
+/// A `wallets` table that ignores addresses already stored
+pub(crate) struct SqliteSink {
+    conn: Connection,
+    /// Rows actually inserted, which leaves out known addresses
+    pub(crate) inserted: usize,
+}
+
+impl SqliteSink {
+    pub(crate) fn open(path: &Path) -> Result<Self, ScraperError> {
+        Ok(Self { conn: open_sqlite(path)?, inserted: 0 })
+    }
+}
+
+impl WalletSink for SqliteSink {
+    fn write(&mut self, records: &[WalletRecord]) -> Result<(), ScraperError> {
+        let tx = self.conn.transaction()?;
+        for wallet in records {
+            if insert_wallet(&tx, wallet)? {
+                self.inserted += 1;
+            }
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    fn flag_multi_exchange(&mut self, multi_exchange: &HashMap<String, Vec<String>>) -> Result<(), ScraperError> {
+        let tx = self.conn.transaction()?;
+        {
+            let mut update =
+                tx.prepare("UPDATE wallets SET exchange_names = ?1, multi_exchange = 1 WHERE wallet_address = ?2")?;
+            for (address, names) in multi_exchange {
+                update.execute((names.join(";"), address))?;
+            }
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    fn finish(self: Box<Self>) -> Result<(), ScraperError> {
+        Ok(())
+    }
+}
+
+/// Writes wallets to a single file in one go
+pub(crate) fn write_wallets(format: OutputFormat, filename: &str, wallets: &[WalletRecord]) -> Result<(), ScraperError> {
+    let mut sink = open_sink(format, filename, false)?;
+    sink.write(wallets)?;
+    sink.finish()
+}
+
+/// Rewrites the finished `filename` one record at a time, giving the addresses in
+/// `multi_exchange` their full list of exchanges. Only JSON, NDJSON and CSV carry the
+/// flags and can be read back; other formats are left as they are.
+pub(crate) fn flag_multi_exchange_output(
+    format: OutputFormat,
+    filename: &str,
+    multi_exchange: &HashMap<String, Vec<String>>,
+) -> Result<(), ScraperError> {
+    if !matches!(format, OutputFormat::Json | OutputFormat::Ndjson | OutputFormat::Csv) {
+        return Ok(());
+    }
+    let reader = BufReader::new(File::open(filename)?);
+    let mut sink = open_sink(format, filename, false)?;
+    let mut rewrite = |mut wallet: WalletRecord| {
+        if let Some(names) = multi_exchange.get(&wallet.wallet_address) {
+            wallet.exchange_names = names.clone();
+            wallet.multi_exchange = true;
+        }
+        sink.write(std::slice::from_ref(&wallet))
+    };
+    match format {
+        OutputFormat::Json => serde_json::Deserializer::from_reader(reader).deserialize_seq(EachRecord(&mut rewrite))?,
+        OutputFormat::Ndjson => {
+            for line in reader.lines() {
+                let line = line?;
+                if !line.trim().is_empty() {
+                    rewrite(serde_json::from_str(&line)?)?;
+                }
+            }
+        }
+        _ => {
+            for row in csv::Reader::from_reader(reader).deserialize::<CsvRow>() {
+                rewrite(row?.into())?;
+            }
+        }
+    }
+    sink.finish()
+}
+
+/// Hands each wallet of a JSON array to a callback without collecting the array
+struct EachRecord<F>(F);
+
+impl<'de, F: FnMut(WalletRecord) -> Result<(), ScraperError>> Visitor<'de> for EachRecord<F> {
+    type Value = ();
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("an array of wallet records")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(mut self, mut seq: A) -> Result<(), A::Error> {
+        while let Some(wallet) = seq.next_element()? {
+            (self.0)(wallet).map_err(de::Error::custom)?;
+        }
+        Ok(())
+    }
+}
+
+/// The output files for one exchange, or for every exchange when not splitting
+struct OutputGroup {
+    sinks: Vec<(String, Box<dyn WalletSink>)>,
+    /// The format of each file, for the multi-exchange pass after finishing
+    formats: HashMap<String, OutputFormat>,
+    written: usize,
+}
+
+impl OutputGroup {
+    fn open(formats: &[OutputFormat], exchange: Option<&str>, paths: &OutputPaths, append_csv: bool) -> Self {
+        let mut group = Self { sinks: Vec::new(), formats: HashMap::new(), written: 0 };
+        for (format, filename) in output_files(formats, exchange, paths) {
+            match open_sink(format, &filename, append_csv) {
+                Ok(sink) => {
+                    group.formats.insert(filename.clone(), format);
+                    group.sinks.push((filename, sink));
+                }
+                Err(e) => error!("Failed to create {}: {}", filename, e),
+            }
+        }
+        group
+    }
+
+    fn write(&mut self, wallet: &WalletRecord) {
+        self.sinks.retain_mut(|(name, sink)| match sink.write(std::slice::from_ref(wallet)) {
+            Ok(()) => true,
+            Err(e) => {
+                error!("Failed to write {}: {}", name, e);
+                false
+            }
+        });
+
+        self.written += 1;
+        if self.written.is_multiple_of(FLUSH_EVERY) {
+            self.sinks.retain_mut(|(name, sink)| match sink.flush() {
+                Ok(()) => true,
+                Err(e) => {
+                    error!("Failed to flush {}: {}", name, e);
+                    false
+                }
+            });
+        }
+    }
+}
+
+/// Streams new wallets to every configured output as they arrive.
+///
+/// Files are only created once the first wallet for them is written, so a run that
+/// finds nothing new leaves the previous output in place, and are written through a
+/// temporary file that replaces them on `finish`. A sink that fails is logged and
+/// dropped without affecting the others.
+///
+/// A wallet carries the exchanges that matched its address by the time it is written;
+/// `finish` corrects addresses that another exchange matched later, rewriting the JSON,
+/// NDJSON and CSV files and updating the spreadsheet and database in place. Standard
+/// output can't be taken back, the address list has no flags to correct, and custom
+/// sinks are only corrected if they implement `WalletSink::flag_multi_exchange`.
+pub(crate) struct WalletWriter {
+    /// The file formats; SQLite is in `shared`
+    formats: Vec<OutputFormat>,
+    split_by_exchange: bool,
+    paths: OutputPaths,
+    /// Append to existing CSV files instead of replacing them
+    append_csv: bool,
+    groups: HashMap<String, OutputGroup>,
+    /// The SQLite database and any caller's sinks, which get every wallet whether or not
+    /// the files are split by exchange
+    shared: OutputGroup,
+}
+
+impl WalletWriter {
+    pub(crate) fn new(
+        mut formats: Vec<OutputFormat>,
+        split_by_exchange: bool,
+        paths: OutputPaths,
+        append_csv: bool,
+        sinks: Vec<Box<dyn WalletSink>>,
+    ) -> Self {
+        let mut shared = OutputGroup { sinks: Vec::new(), formats: HashMap::new(), written: 0 };
+        if formats.contains(&OutputFormat::Sqlite) {
+            shared = OutputGroup::open(&[OutputFormat::Sqlite], None, &paths, false);
+            formats.retain(|format| *format != OutputFormat::Sqlite);
+        }
+        for (i, sink) in sinks.into_iter().enumerate() {
+            shared.sinks.push((format!("sink {}", i + 1), sink));
+        }
+
+        Self {
+            formats,
+            split_by_exchange,
+            paths,
+            append_csv,
+            groups: HashMap::new(),
+            shared,
+        }
+    }
+
+    pub(crate) fn write(&mut self, wallet: &WalletRecord) {
+        let (formats, paths, append_csv) = (&self.formats, &self.paths, self.append_csv);
+        let exchange = self.split_by_exchange.then_some(wallet.exchange_name.as_str());
+        self.groups
+            .entry(exchange.unwrap_or_default().to_string())
+            .or_insert_with(|| OutputGroup::open(formats, exchange, paths, append_csv))
+            .write(wallet);
+        self.shared.write(wallet);
+    }
+
+    /// Closes every output, flags the addresses in `multi_exchange` with all of their
+    /// exchanges, and returns the files that were written completely
+    pub(crate) fn finish(self, multi_exchange: &HashMap<String, Vec<String>>) -> Vec<String> {
+        let mut written = Vec::new();
+        for group in self.groups.into_values() {
+            for (filename, mut sink) in group.sinks {
+                if !multi_exchange.is_empty() {
+                    if let Err(e) = sink.flag_multi_exchange(multi_exchange) {
+                        error!("Failed to flag multi-exchange addresses in {}: {}", filename, e);
+                    }
+                }
+                match sink.finish() {
+                    Ok(()) if filename == STDOUT => info!("Wrote {} wallets to stdout", group.written),
+                    Ok(()) => {
+                        info!("Saved {} wallets to {}", group.written, filename);
+                        if !multi_exchange.is_empty() {
+                            if let Err(e) = flag_multi_exchange_output(group.formats[&filename], &filename, multi_exchange) {
+                                error!("Failed to flag multi-exchange addresses in {}: {}", filename, e);
+                            }
+                        }
+                        written.push(filename);
+                    }
+                    Err(e) => error!("Failed to save {}: {}", filename, e),
+                }
+            }
+        }
+        for (name, mut sink) in self.shared.sinks {
+            if !multi_exchange.is_empty() {
+                if let Err(e) = sink.flag_multi_exchange(multi_exchange) {
+                    error!("Failed to flag multi-exchange addresses in {}: {}", name, e);
+                }
+            }
+            if let Err(e) = sink.finish() {
+                error!("Failed to finish {}: {}", name, e);
+            }
+        }
+        written
+    }
+}
+
+/// Opens the database and creates the `wallets` table if needed, adding the multi-exchange
+/// columns to a table from an older version
+pub(crate) fn open_sqlite(path: &Path) -> Result<Connection, ScraperError> {
+    let conn = Connection::open(path)?;
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS wallets (
+            exchange_name TEXT NOT NULL,
+            wallet_address TEXT NOT NULL UNIQUE,
+            source_urls TEXT NOT NULL,
+            exchange_names TEXT NOT NULL DEFAULT '',
+            multi_exchange INTEGER NOT NULL DEFAULT 0
+        )",
+        [],
+    )?;
+    let columns = conn
+        .prepare("SELECT name FROM pragma_table_info('wallets')")?
+        .query_map([], |row| row.get::<_, String>(0))?
+        .collect::<Result<Vec<_>, _>>()?;
+    let added = [("exchange_names", "TEXT NOT NULL DEFAULT ''"), ("multi_exchange", "INTEGER NOT NULL DEFAULT 0")];
+    for (column, definition) in added {
+        if !columns.iter().any(|name| name == column) {
+            conn.execute(&format!("ALTER TABLE wallets ADD COLUMN {} {}", column, definition), [])?;
+        }
+    }
+    Ok(conn)
+}
+
+/// Inserts a wallet unless its address is already stored, returning whether it was new
+pub(crate) fn insert_wallet(conn: &Connection, wallet: &WalletRecord) -> Result<bool, ScraperError> {
+    let inserted = conn
+        .prepare_cached(
+            "INSERT OR IGNORE INTO wallets (exchange_name, wallet_address, source_urls, exchange_names, multi_exchange)
+             VALUES (?1, ?2, ?3, ?4, ?5)",
+        )?
+        .execute((
+            &wallet.exchange_name,
+            &wallet.wallet_address,
+            wallet.joined_source_urls(),
+            wallet.joined_exchange_names(),
+            wallet.multi_exchange,
+        ))?;
+    Ok(inserted > 0)
+}
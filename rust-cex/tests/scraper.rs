@@ -0,0 +1,856 @@
+use cex_wallet_scraper::{
+    run_with_observer, Args, CEXScraper, ExchangeConfig, HttpOptions, PageCache, PageCursors, WalletObserver, WalletRecord,
+    WalletSelectors,
+};
+use clap::Parser;
+use reqwest::Url;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use wiremock::matchers::{method, path, query_param};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+const WALLET: &str = "0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed";
+const OTHER_WALLET: &str = "0xfB6916095ca1df60bB79Ce92cE3Ea74c37c5d359";
+const NO_RESULTS: &str = "<html><body>No matching accounts found</body></html>";
+
+fn scraper() -> CEXScraper {
+    let http = HttpOptions {
+        timeout: Duration::from_secs(10),
+        request_delay: Duration::ZERO,
+        ..HttpOptions::default()
+    };
+    CEXScraper::new(&http, None, None)
+        .unwrap()
+        .with_max_backoff(Duration::from_secs(1))
+        .with_max_pages(3)
+        .with_query_delay(Duration::ZERO)
+}
+
+fn exchange(server: &MockServer) -> ExchangeConfig {
+    ExchangeConfig {
+        name: "Binance".to_string(),
+        etherscan_url: format!("{}/accounts", server.uri()),
+        search_queries: vec!["binance".to_string()],
+        api_label: "binance".to_string(),
+        selectors: WalletSelectors::default(),
+    }
+}
+
+fn listing(addresses: &[&str]) -> String {
+    let links: String = addresses
+        .iter()
+        .map(|address| format!(r#"<a href="/address/{}">{}</a>"#, address.to_lowercase(), address))
+        .collect();
+    format!("<html><body><table>{}</table></body></html>", links)
+}
+
+fn html(body: impl Into<String>) -> ResponseTemplate {
+    ResponseTemplate::new(200).set_body_raw(body.into(), "text/html; charset=utf-8")
+}
+
+/// Serves `body` for page 1 and an empty result for the other pages
+async fn mount_listing(server: &MockServer, body: String) {
+    Mock::given(method("GET"))
+        .and(path("/accounts"))
+        .and(query_param("p", "1"))
+        .respond_with(html(body))
+        .mount(server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/accounts"))
+        .respond_with(html(NO_RESULTS))
+        .mount(server)
+        .await;
+}
+
+#[tokio::test]
+async fn successful_page_yields_checksummed_wallets() {
+    let server = MockServer::start().await;
+    mount_listing(&server, listing(&[WALLET])).await;
+
+    let wallets = scraper().scrape_exchange_wallets(&exchange(&server)).await.unwrap();
+
+    assert_eq!(wallets.len(), 1);
+    assert_eq!(wallets[0].exchange_name, "Binance");
+    assert_eq!(wallets[0].wallet_address, WALLET);
+    assert_eq!(wallets[0].source_urls, vec![format!("{}/accounts?q=binance&p=1", server.uri())]);
+}
+
+#[tokio::test]
+async fn rate_limited_page_is_retried() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/accounts"))
+        .and(query_param("p", "1"))
+        .respond_with(ResponseTemplate::new(429))
+        .up_to_n_times(1)
+        .expect(1)
+        .mount(&server)
+        .await;
+    mount_listing(&server, listing(&[WALLET])).await;
+
+    let wallets = scraper().scrape_exchange_wallets(&exchange(&server)).await.unwrap();
+
+    assert_eq!(wallets.len(), 1);
+    assert_eq!(wallets[0].wallet_address, WALLET);
+}
+
+#[tokio::test]
+async fn no_matching_accounts_is_empty() {
+    let server = MockServer::start().await;
+    mount_listing(&server, NO_RESULTS.to_string()).await;
+
+    let wallets = scraper().scrape_exchange_wallets(&exchange(&server)).await.unwrap();
+
+    assert!(wallets.is_empty());
+}
+
+#[tokio::test]
+async fn server_error_page_is_retried() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/accounts"))
+        .and(query_param("p", "1"))
+        .respond_with(ResponseTemplate::new(503))
+        .up_to_n_times(1)
+        .expect(1)
+        .mount(&server)
+        .await;
+    mount_listing(&server, listing(&[WALLET])).await;
+
+    let wallets = scraper().scrape_exchange_wallets(&exchange(&server)).await.unwrap();
+
+    assert_eq!(wallets.len(), 1);
+}
+
+#[tokio::test]
+async fn missing_page_is_skipped_without_retrying() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/accounts"))
+        .and(query_param("p", "2"))
+        .respond_with(ResponseTemplate::new(404))
+        .expect(1)
+        .mount(&server)
+        .await;
+    mount_listing(&server, listing(&[WALLET])).await;
+
+    let wallets = scraper().scrape_exchange_wallets(&exchange(&server)).await.unwrap();
+
+    assert_eq!(wallets.len(), 1);
+}
+
+#[tokio::test]
+async fn every_page_failing_is_an_error() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/accounts"))
+        .respond_with(ResponseTemplate::new(404))
+        .expect(3)
+        .mount(&server)
+        .await;
+
+    let result = scraper().scrape_exchange_wallets(&exchange(&server)).await;
+
+    assert!(result.is_err());
+}
+
+#[tokio::test]
+async fn fail_fast_stops_at_a_forbidden_page() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/accounts"))
+        .and(query_param("p", "1"))
+        .respond_with(ResponseTemplate::new(403))
+        .expect(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("GET")).and(path("/accounts")).respond_with(html(listing(&[WALLET]))).expect(0).mount(&server).await;
+
+    let error = scraper().with_fail_fast(true).scrape_exchange_wallets(&exchange(&server)).await.unwrap_err();
+
+    assert!(error.is_fatal());
+}
+
+#[tokio::test]
+async fn fail_fast_still_skips_a_missing_page() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/accounts"))
+        .and(query_param("p", "2"))
+        .respond_with(ResponseTemplate::new(404))
+        .expect(1)
+        .mount(&server)
+        .await;
+    mount_listing(&server, listing(&[WALLET])).await;
+
+    let wallets = scraper().with_fail_fast(true).scrape_exchange_wallets(&exchange(&server)).await.unwrap();
+
+    assert_eq!(wallets.len(), 1);
+}
+
+#[tokio::test]
+async fn base_url_redirects_configured_exchanges() {
+    let server = MockServer::start().await;
+    mount_listing(&server, listing(&[WALLET])).await;
+    let config = ExchangeConfig {
+        etherscan_url: "https://etherscan.io/accounts".to_string(),
+        ..exchange(&server)
+    };
+
+    let wallets = scraper()
+        .with_base_url(Some(Url::parse(&server.uri()).unwrap()))
+        .scrape_exchange_wallets(&config)
+        .await
+        .unwrap();
+
+    assert_eq!(wallets.len(), 1);
+}
+
+#[tokio::test]
+async fn cached_pages_are_not_refetched() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/accounts"))
+        .respond_with(html(listing(&[WALLET])))
+        .expect(2)
+        .mount(&server)
+        .await;
+    let cache_dir = std::env::temp_dir().join(format!("cex-page-cache-{}", std::process::id()));
+    let http = HttpOptions {
+        request_delay: Duration::ZERO,
+        cache: Some(PageCache::new(cache_dir.clone(), Duration::from_secs(60))),
+        ..HttpOptions::default()
+    };
+
+    for _ in 0..2 {
+        let wallets = CEXScraper::new(&http, None, None)
+            .unwrap()
+            .scrape_exchange_wallets(&exchange(&server))
+            .await
+            .unwrap();
+        assert_eq!(wallets.len(), 1);
+    }
+
+    std::fs::remove_dir_all(cache_dir).unwrap();
+}
+
+#[tokio::test]
+async fn memory_cache_serves_repeated_pages() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/accounts"))
+        .and(query_param("p", "1"))
+        .respond_with(html(listing(&[WALLET])))
+        .expect(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("GET")).and(path("/accounts")).respond_with(html(NO_RESULTS)).mount(&server).await;
+    let mut scraper = scraper().with_mem_cache(8);
+
+    let first = scraper.scrape_exchange_wallets(&exchange(&server)).await.unwrap();
+    let second = scraper.scrape_exchange_wallets(&exchange(&server)).await.unwrap();
+
+    assert_eq!(first.len(), 1);
+    assert_eq!(second.len(), 1);
+}
+
+#[tokio::test]
+async fn burn_addresses_are_dropped() {
+    let server = MockServer::start().await;
+    let burn = "0x000000000000000000000000000000000000dEaD";
+    mount_listing(&server, listing(&[WALLET, burn, "0x0000000000000000000000000000000000000000"])).await;
+
+    let wallets = scraper().scrape_exchange_wallets(&exchange(&server)).await.unwrap();
+    assert_eq!(wallets.len(), 1);
+    assert_eq!(wallets[0].wallet_address, WALLET);
+
+    let wallets = scraper()
+        .with_burn_addresses(&[WALLET.to_lowercase()])
+        .scrape_exchange_wallets(&exchange(&server))
+        .await
+        .unwrap();
+    assert_eq!(wallets.len(), 2);
+    assert!(wallets.iter().any(|wallet| wallet.wallet_address == burn));
+}
+
+#[tokio::test]
+async fn observer_sees_each_written_wallet_once() {
+    let server = MockServer::start().await;
+    mount_listing(&server, listing(&[WALLET, OTHER_WALLET, WALLET])).await;
+    let dir = std::env::temp_dir().join(format!("cex-observer-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    // Both exchanges find both wallets, and an earlier run already wrote one of them
+    std::fs::write(dir.join("cex_state.json"), format!(r#"{{"seen_wallets": ["{}"]}}"#, WALLET)).unwrap();
+    let config = dir.join("exchanges.toml");
+    let exchange = |key: &str, name: &str| {
+        format!(
+            r#"[exchanges.{key}]
+name = "{name}"
+etherscan_url = "{uri}/accounts"
+search_queries = ["{key}"]
+api_label = "{key}"
+"#,
+            uri = server.uri()
+        )
+    };
+    std::fs::write(&config, exchange("binance", "Binance") + &exchange("okx", "OKX")).unwrap();
+    let args = Args::parse_from([
+        "cex-wallet-scraper",
+        "--config",
+        &config.to_string_lossy(),
+        "--out-dir",
+        &dir.to_string_lossy(),
+        "--query-delay",
+        "0",
+        "--end-page",
+        "2",
+        "--quiet",
+    ]);
+    let http = HttpOptions { request_delay: Duration::ZERO, ..HttpOptions::default() };
+
+    let observed = Arc::new(Mutex::new(Vec::new()));
+    let sink = observed.clone();
+    let observer: WalletObserver = Arc::new(move |wallet| sink.lock().unwrap().push(wallet.wallet_address.clone()));
+    run_with_observer(args, http, Some(observer)).await.unwrap();
+
+    assert_eq!(*observed.lock().unwrap(), vec![OTHER_WALLET.to_string()]);
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[tokio::test]
+async fn observer_sees_new_wallets_of_a_dry_run() {
+    let server = MockServer::start().await;
+    mount_listing(&server, listing(&[WALLET, OTHER_WALLET])).await;
+    let dir = std::env::temp_dir().join(format!("cex-dry-run-observer-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let config = dir.join("exchanges.toml");
+    std::fs::write(
+        &config,
+        format!(
+            r#"[exchanges.binance]
+name = "Binance"
+etherscan_url = "{}/accounts"
+search_queries = ["binance"]
+api_label = "binance"
+"#,
+            server.uri()
+        ),
+    )
+    .unwrap();
+    let args = Args::parse_from([
+        "cex-wallet-scraper",
+        "--config",
+        &config.to_string_lossy(),
+        "--out-dir",
+        &dir.to_string_lossy(),
+        "--query-delay",
+        "0",
+        "--end-page",
+        "2",
+        "--dry-run",
+        "--quiet",
+    ]);
+    let http = HttpOptions { request_delay: Duration::ZERO, ..HttpOptions::default() };
+
+    let observed = Arc::new(Mutex::new(Vec::new()));
+    let sink = observed.clone();
+    let observer: WalletObserver = Arc::new(move |wallet| sink.lock().unwrap().push(wallet.wallet_address.clone()));
+    run_with_observer(args, http, Some(observer)).await.unwrap();
+
+    assert_eq!(*observed.lock().unwrap(), vec![WALLET.to_string(), OTHER_WALLET.to_string()]);
+    let mut files: Vec<_> = std::fs::read_dir(&dir).unwrap().map(|entry| entry.unwrap().file_name()).collect();
+    files.sort();
+    assert_eq!(files, ["exchanges.toml"]);
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[tokio::test]
+async fn exchanges_are_written_in_name_order() {
+    let server = MockServer::start().await;
+    // Alpha finishes last, but still comes first and keeps the address both exchanges found
+    Mock::given(method("GET"))
+        .and(path("/alpha"))
+        .and(query_param("p", "1"))
+        .respond_with(html(listing(&[WALLET])).set_delay(Duration::from_millis(300)))
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/beta"))
+        .and(query_param("p", "1"))
+        .respond_with(html(listing(&[OTHER_WALLET, WALLET])))
+        .mount(&server)
+        .await;
+    Mock::given(method("GET")).respond_with(html(NO_RESULTS)).mount(&server).await;
+    let dir = std::env::temp_dir().join(format!("cex-order-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let config = dir.join("exchanges.toml");
+    let exchange = |key: &str, name: &str| {
+        format!(
+            r#"[exchanges.{key}]
+name = "{name}"
+etherscan_url = "{uri}/{key}"
+search_queries = ["{key}"]
+api_label = "{key}"
+"#,
+            uri = server.uri()
+        )
+    };
+    std::fs::write(&config, exchange("beta", "Beta") + &exchange("alpha", "Alpha")).unwrap();
+    let args = Args::parse_from([
+        "cex-wallet-scraper",
+        "--config",
+        &config.to_string_lossy(),
+        "--out-dir",
+        &dir.to_string_lossy(),
+        "--format",
+        "json",
+        "--query-delay",
+        "0",
+        "--end-page",
+        "2",
+        "--quiet",
+    ]);
+    let http = HttpOptions { request_delay: Duration::ZERO, ..HttpOptions::default() };
+    run_with_observer(args, http, None).await.unwrap();
+
+    let output = std::fs::read_to_string(dir.join("cex_wallets.json")).unwrap();
+    let wallets: Vec<WalletRecord> = serde_json::from_str(&output).unwrap();
+    let written: Vec<_> = wallets
+        .iter()
+        .map(|wallet| (wallet.exchange_name.as_str(), wallet.wallet_address.as_str()))
+        .collect();
+    assert_eq!(written, vec![("Alpha", WALLET), ("Beta", OTHER_WALLET)]);
+    assert!(wallets[0].multi_exchange);
+    assert_eq!(wallets[0].exchange_names, vec!["Alpha", "Beta"]);
+    assert!(!wallets[1].multi_exchange);
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[tokio::test]
+async fn queries_resume_after_their_cursor() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/accounts"))
+        .and(query_param("p", "1"))
+        .respond_with(html(listing(&[WALLET])))
+        .expect(0)
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/accounts"))
+        .and(query_param("p", "2"))
+        .respond_with(html(listing(&[WALLET])))
+        .expect(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/accounts"))
+        .respond_with(html(listing(&[OTHER_WALLET])))
+        .expect(1)
+        .mount(&server)
+        .await;
+    let mut cursors = PageCursors::new();
+    cursors.entry("Binance".to_string()).or_default().insert("binance".to_string(), 1);
+
+    let mut scraper = scraper().with_page_cursors(cursors, None);
+    let wallets = scraper.scrape_exchange_wallets(&exchange(&server)).await.unwrap();
+
+    assert_eq!(wallets.len(), 2);
+    assert!(scraper.page_cursors().is_empty());
+}
+
+#[tokio::test]
+async fn failed_page_is_fetched_again_next_run() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/accounts"))
+        .and(query_param("p", "1"))
+        .respond_with(html(listing(&[WALLET])))
+        .expect(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/accounts"))
+        .and(query_param("p", "2"))
+        .respond_with(ResponseTemplate::new(404))
+        .up_to_n_times(1)
+        .expect(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/accounts"))
+        .and(query_param("p", "2"))
+        .respond_with(html(listing(&[OTHER_WALLET])))
+        .expect(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/accounts"))
+        .respond_with(html(NO_RESULTS))
+        .mount(&server)
+        .await;
+
+    let mut first_run = scraper();
+    let wallets = first_run.scrape_exchange_wallets(&exchange(&server)).await.unwrap();
+    assert_eq!(wallets.len(), 1);
+    let cursors = first_run.page_cursors();
+    assert_eq!(cursors["Binance"]["binance"], 1);
+
+    let mut next_run = scraper().with_page_cursors(cursors, None);
+    let wallets = next_run.scrape_exchange_wallets(&exchange(&server)).await.unwrap();
+
+    assert_eq!(wallets.len(), 1);
+    assert_eq!(wallets[0].wallet_address, OTHER_WALLET);
+    assert!(next_run.page_cursors().is_empty());
+}
+
+#[tokio::test]
+async fn explicit_page_range_overrides_the_cursor() {
+    let server = MockServer::start().await;
+    for page in 4..=6 {
+        let address = format!("0x{:040x}", page + 0x1000);
+        Mock::given(method("GET"))
+            .and(path("/accounts"))
+            .and(query_param("p", page.to_string()))
+            .respond_with(html(listing(&[&address])))
+            .expect(1)
+            .mount(&server)
+            .await;
+    }
+    Mock::given(method("GET")).and(path("/accounts")).respond_with(html(NO_RESULTS)).expect(0).mount(&server).await;
+    let mut cursors = PageCursors::new();
+    cursors.entry("Binance".to_string()).or_default().insert("binance".to_string(), 1);
+
+    let wallets = scraper()
+        .with_page_cursors(cursors, Some(4))
+        .with_max_pages(6)
+        .scrape_exchange_wallets(&exchange(&server))
+        .await
+        .unwrap();
+
+    assert_eq!(wallets.len(), 3);
+}
+
+#[tokio::test]
+async fn start_page_past_the_end_page_is_rejected() {
+    let dir = std::env::temp_dir().join(format!("cex-page-range-{}", std::process::id()));
+    let args = Args::parse_from([
+        "cex-wallet-scraper",
+        "--out-dir",
+        &dir.to_string_lossy(),
+        "--start-page",
+        "5",
+        "--end-page",
+        "4",
+        "--quiet",
+    ]);
+
+    let error = run_with_observer(args, HttpOptions::default(), None).await.unwrap_err();
+    assert!(error.to_string().contains("--start-page 5 is past --end-page 4"), "{}", error);
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[tokio::test]
+async fn query_stops_when_a_page_repeats() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/accounts"))
+        .and(query_param("p", "1"))
+        .respond_with(html(listing(&[WALLET])))
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/accounts"))
+        .and(query_param("p", "2"))
+        .respond_with(html(listing(&[OTHER_WALLET])))
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/accounts"))
+        .respond_with(html(listing(&[OTHER_WALLET])))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let wallets = scraper().with_max_pages(5).scrape_exchange_wallets(&exchange(&server)).await.unwrap();
+
+    assert_eq!(wallets.len(), 2);
+}
+
+#[tokio::test]
+async fn query_stops_at_the_footer_page_count() {
+    let server = MockServer::start().await;
+    let body = listing(&[WALLET]).replace("</table>", "</table><div>Showing 1 to 1 of 1 entries</div>");
+    Mock::given(method("GET"))
+        .and(path("/accounts"))
+        .and(query_param("p", "1"))
+        .respond_with(html(body))
+        .expect(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("GET")).and(path("/accounts")).respond_with(html(NO_RESULTS)).expect(0).mount(&server).await;
+
+    let wallets = scraper().scrape_exchange_wallets(&exchange(&server)).await.unwrap();
+
+    assert_eq!(wallets.len(), 1);
+}
+
+#[tokio::test]
+async fn query_stops_at_max_pages() {
+    let server = MockServer::start().await;
+    for page in 1..=2 {
+        let address = format!("0x{:040x}", page + 0x1000);
+        Mock::given(method("GET"))
+            .and(path("/accounts"))
+            .and(query_param("p", page.to_string()))
+            .respond_with(html(listing(&[&address])))
+            .expect(1)
+            .mount(&server)
+            .await;
+    }
+
+    let wallets = scraper().with_max_pages(2).scrape_exchange_wallets(&exchange(&server)).await.unwrap();
+
+    assert_eq!(wallets.len(), 2);
+}
+
+#[tokio::test]
+async fn exchange_stops_at_its_wallet_limit() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/accounts"))
+        .and(query_param("p", "1"))
+        .respond_with(html(listing(&[WALLET, OTHER_WALLET])))
+        .expect(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("GET")).and(path("/accounts")).respond_with(html(NO_RESULTS)).expect(0).mount(&server).await;
+
+    let wallets = scraper().with_limit_per_exchange(1).scrape_exchange_wallets(&exchange(&server)).await.unwrap();
+
+    assert_eq!(wallets.len(), 1);
+}
+
+#[tokio::test]
+async fn non_html_page_is_not_parsed() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/accounts"))
+        .and(query_param("p", "1"))
+        .respond_with(ResponseTemplate::new(200).set_body_raw(listing(&[WALLET]), "application/json"))
+        .expect(1)
+        .mount(&server)
+        .await;
+    mount_listing(&server, listing(&[OTHER_WALLET])).await;
+
+    let wallets = scraper().scrape_exchange_wallets(&exchange(&server)).await.unwrap();
+
+    assert!(wallets.is_empty());
+}
+
+#[tokio::test]
+async fn api_mode_uses_the_v2_endpoint() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/v2/api"))
+        .and(query_param("chainid", "56"))
+        .and(query_param("label", "binance"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+            "status": "1",
+            "message": "OK",
+            "result": [{"address": WALLET.to_lowercase()}],
+        })))
+        .expect(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/v2/api"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+            "status": "0",
+            "message": "No records found",
+            "result": [],
+        })))
+        .mount(&server)
+        .await;
+    let api_url = format!("{}/v2/api", server.uri());
+    let api_scraper = || {
+        let http = HttpOptions { request_delay: Duration::ZERO, ..HttpOptions::default() };
+        CEXScraper::new(&http, None, Some("key".to_string())).unwrap()
+    };
+
+    let wallets = api_scraper().with_api(&api_url, 56).scrape_exchange_wallets(&exchange(&server)).await.unwrap();
+    assert_eq!(wallets.len(), 1);
+    assert_eq!(wallets[0].wallet_address, WALLET);
+    assert!(wallets[0].source_urls[0].contains("chainid=56"));
+
+    let wallets = api_scraper().with_api(&api_url, 1).scrape_exchange_wallets(&exchange(&server)).await.unwrap();
+    assert!(wallets.is_empty());
+}
+
+#[tokio::test]
+async fn api_server_errors_are_retried() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/v2/api"))
+        .respond_with(ResponseTemplate::new(503))
+        .up_to_n_times(1)
+        .expect(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/v2/api"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+            "status": "1",
+            "message": "OK",
+            "result": [{"address": WALLET.to_lowercase()}],
+        })))
+        .expect(1)
+        .mount(&server)
+        .await;
+    let http = HttpOptions { request_delay: Duration::ZERO, ..HttpOptions::default() };
+    let mut scraper = CEXScraper::new(&http, None, Some("key".to_string()))
+        .unwrap()
+        .with_max_backoff(Duration::from_secs(1))
+        .with_api(&format!("{}/v2/api", server.uri()), 1);
+
+    let wallets = scraper.scrape_exchange_wallets(&exchange(&server)).await.unwrap();
+
+    assert_eq!(wallets.len(), 1);
+}
+
+#[tokio::test]
+async fn wallets_below_the_tx_count_are_dropped() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(query_param("action", "eth_getTransactionCount"))
+        .and(query_param("address", WALLET))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({"jsonrpc": "2.0", "id": 1, "result": "0x5"})))
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(query_param("action", "eth_getTransactionCount"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({"jsonrpc": "2.0", "id": 1, "result": "0x0"})))
+        .mount(&server)
+        .await;
+    let wallet = |address: &str| WalletRecord {
+        exchange_name: "Binance".to_string(),
+        wallet_address: address.to_string(),
+        source_urls: Vec::new(),
+        balance_wei: None,
+        exchange_names: Vec::new(),
+        multi_exchange: false,
+        label: None,
+        funded_by: None,
+    };
+    let wallets = vec![wallet(WALLET), wallet(OTHER_WALLET)];
+    let http = HttpOptions { request_delay: Duration::ZERO, ..HttpOptions::default() };
+
+    let kept = CEXScraper::new(&http, None, Some("key".to_string()))
+        .unwrap()
+        .with_api(&server.uri(), 1)
+        .filter_by_tx_count(wallets.clone(), 1)
+        .await;
+    assert_eq!(kept.len(), 1);
+    assert_eq!(kept[0].wallet_address, WALLET);
+
+    let kept = CEXScraper::new(&http, None, None).unwrap().filter_by_tx_count(wallets, 1).await;
+    assert_eq!(kept.len(), 2);
+}
+
+#[tokio::test]
+async fn only_wallets_tagged_with_their_exchange_are_verified() {
+    let server = MockServer::start().await;
+    let tag = |address: &str, nametag: &str| {
+        ResponseTemplate::new(200).set_body_json(serde_json::json!({
+            "status": "1",
+            "message": "OK",
+            "result": [{"address": address.to_lowercase(), "nametag": nametag}],
+        }))
+    };
+    Mock::given(method("GET"))
+        .and(query_param("action", "getaddresstag"))
+        .and(query_param("address", WALLET))
+        .respond_with(tag(WALLET, "Binance 14"))
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(query_param("action", "getaddresstag"))
+        .respond_with(tag(OTHER_WALLET, "Fake_Phishing123"))
+        .mount(&server)
+        .await;
+    let wallet = |address: &str| WalletRecord {
+        exchange_name: "Binance".to_string(),
+        wallet_address: address.to_string(),
+        source_urls: Vec::new(),
+        balance_wei: None,
+        exchange_names: Vec::new(),
+        multi_exchange: false,
+        label: None,
+        funded_by: None,
+    };
+    let http = HttpOptions { request_delay: Duration::ZERO, ..HttpOptions::default() };
+
+    let (verified, unverified) = CEXScraper::new(&http, None, Some("key".to_string()))
+        .unwrap()
+        .with_api(&server.uri(), 1)
+        .verify_labels(vec![wallet(WALLET), wallet(OTHER_WALLET)], "Binance")
+        .await;
+
+    assert_eq!(verified.len(), 1);
+    assert_eq!(verified[0].label.as_deref(), Some("Binance 14"));
+    assert_eq!(unverified.len(), 1);
+    assert_eq!(unverified[0].wallet_address, OTHER_WALLET);
+    assert_eq!(unverified[0].label.as_deref(), Some("Fake_Phishing123"));
+}
+
+#[tokio::test]
+async fn funders_are_looked_up_once() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(query_param("action", "txlist"))
+        .and(query_param("address", WALLET))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+            "status": "1",
+            "message": "OK",
+            "result": [
+                {"from": WALLET.to_lowercase(), "to": ""},
+                {"from": OTHER_WALLET.to_lowercase(), "to": WALLET.to_lowercase()},
+            ],
+        })))
+        .expect(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(query_param("action", "txlist"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+            "status": "0",
+            "message": "No transactions found",
+            "result": [],
+        })))
+        .mount(&server)
+        .await;
+    let wallet = |address: &str| WalletRecord {
+        exchange_name: "Binance".to_string(),
+        wallet_address: address.to_string(),
+        source_urls: Vec::new(),
+        balance_wei: None,
+        exchange_names: Vec::new(),
+        multi_exchange: false,
+        label: None,
+        funded_by: None,
+    };
+    let http = HttpOptions { request_delay: Duration::ZERO, ..HttpOptions::default() };
+    let mut scraper = CEXScraper::new(&http, None, Some("key".to_string())).unwrap().with_api(&server.uri(), 1);
+
+    let mut wallets = vec![wallet(WALLET), wallet(OTHER_WALLET)];
+    scraper.fetch_funders(&mut wallets).await;
+    assert_eq!(wallets[0].funded_by.as_deref(), Some(OTHER_WALLET));
+    assert_eq!(wallets[1].funded_by, None);
+
+    // Served from the cache, which the state file carries into the next run
+    let mut again = vec![wallet(WALLET)];
+    scraper.fetch_funders(&mut again).await;
+    assert_eq!(again[0].funded_by.as_deref(), Some(OTHER_WALLET));
+    assert_eq!(scraper.funders().get(WALLET).map(String::as_str), Some(OTHER_WALLET));
+}
@@ -0,0 +1,69 @@
+use regex::Regex;
+use std::sync::LazyLock;
+use tiny_keccak::{Hasher, Keccak};
+
+/// A `0x` address anywhere in a string, compiled once instead of on every page
+pub static ADDRESS_REGEX: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"0x[a-fA-F0-9]{40}").expect("address pattern is valid"));
+
+/// Whether an address is `0x` followed by 40 hex digits, with a valid checksum when it is mixed case
+pub fn is_valid_ethereum_address(address: &str, chain_id: Option<u64>) -> bool {
+    if address.len() != 42 || !address.starts_with("0x") {
+        return false;
+    }
+
+    let hex_chars: Vec<char> = address[2..].chars().collect();
+    if !hex_chars.iter().all(|c| c.is_ascii_hexdigit()) {
+        return false;
+    }
+
+    // Only mixed case carries a checksum; all-lowercase and all-uppercase are plain hex
+    let has_upper = address[2..].chars().any(|c| c.is_ascii_uppercase());
+    let has_lower = address[2..].chars().any(|c| c.is_ascii_lowercase());
+    if has_upper && has_lower {
+        return verify_checksum(address, chain_id);
+    }
+
+    true
+}
+
+/// Checks the mixed-case checksum of an address.
+///
+/// With no chain id this is plain EIP-55. With a chain id the hash input is
+/// prefixed with it as specified by EIP-1191, which some L2s and testnets use.
+pub fn verify_checksum(address: &str, chain_id: Option<u64>) -> bool {
+    to_checksum_address(address, chain_id).as_deref() == Some(address)
+}
+
+/// Keccak-256 of the lowercase hex address, prefixed with the chain id for EIP-1191
+fn checksum_hash(address: &str, chain_id: Option<u64>) -> [u8; 32] {
+    let address_lower = address.to_lowercase();
+    let mut hasher = Keccak::v256();
+    match chain_id {
+        Some(chain_id) => hasher.update(format!("{}0x{}", chain_id, &address_lower[2..]).as_bytes()),
+        None => hasher.update(&address_lower.as_bytes()[2..]),
+    }
+    let mut address_hash = [0u8; 32];
+    hasher.finalize(&mut address_hash);
+    address_hash
+}
+
+/// Returns the checksummed form of a well-formed address, or `None` if it is not
+/// `0x` followed by 40 hex digits
+pub fn to_checksum_address(address: &str, chain_id: Option<u64>) -> Option<String> {
+    if address.len() != 42 || !address.starts_with("0x") || !address[2..].chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+
+    let address_hash = checksum_hash(address, chain_id);
+    let checksummed = address[2..]
+        .chars()
+        .enumerate()
+        .map(|(i, c)| {
+            let byte = address_hash[i / 2];
+            let nibble = if i % 2 == 0 { byte >> 4 } else { byte & 0x0f };
+            if nibble > 7 { c.to_ascii_uppercase() } else { c.to_ascii_lowercase() }
+        })
+        .collect::<String>();
+
+    Some(format!("0x{}", checksummed))
+}
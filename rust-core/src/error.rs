@@ -0,0 +1,15 @@
+use thiserror::Error;
+
+/// Failures from building the shared HTTP client and its settings, or from object storage; each scraper
+/// converts them into its own error type
+#[derive(Debug, Error)]
+pub enum CoreError {
+    #[error("HTTP request failed: {0}")]
+    Http(#[from] reqwest::Error),
+
+    #[error("invalid configuration: {0}")]
+    Config(String),
+
+    #[error("object storage error: {0}")]
+    Storage(String),
+}
@@ -0,0 +1,115 @@
+use reqwest::{Client, RequestBuilder};
+use serde::Deserialize;
+
+/// Etherscan's unified V2 endpoint; the `chainid` parameter picks the chain and one key covers them all
+pub const ETHERSCAN_V2_API_URL: &str = "https://api.etherscan.io/v2/api";
+
+/// The `{status, message, result}` envelope every Etherscan API response comes in
+#[derive(Debug, Deserialize)]
+pub struct ApiEnvelope {
+    pub status: String,
+    pub message: String,
+    pub result: serde_json::Value,
+}
+
+/// What an envelope means to the caller
+#[derive(Debug, PartialEq)]
+pub enum ApiReply {
+    /// `status == "1"`, with the `result` payload
+    Records(serde_json::Value),
+    /// `status == "0"` because nothing matched, which is an empty result rather than a failure
+    NoRecords,
+    /// Throttled; the API says so in the envelope rather than with a 429
+    RateLimited,
+    /// Any other failure, with the message and detail from the envelope
+    Error(String),
+}
+
+impl ApiEnvelope {
+    pub fn reply(self) -> ApiReply {
+        let detail = self.result.as_str().unwrap_or_default();
+        let nothing_found = |text: &str| text.starts_with("No records found") || text.starts_with("No transactions found");
+        if self.status == "1" {
+            ApiReply::Records(self.result)
+        } else if nothing_found(&self.message) || nothing_found(detail) {
+            ApiReply::NoRecords
+        } else if detail.contains("rate limit") {
+            ApiReply::RateLimited
+        } else {
+            ApiReply::Error(format!("{} {}", self.message, detail).trim_end().to_string())
+        }
+    }
+}
+
+impl ApiReply {
+    /// Reads either the usual envelope or the JSON-RPC response of the `proxy` module,
+    /// which only uses the envelope to report errors such as rate limiting
+    pub fn from_body(body: serde_json::Value) -> Self {
+        if body.get("jsonrpc").is_some() {
+            return match (body.get("result"), body.get("error")) {
+                (Some(result), None) => ApiReply::Records(result.clone()),
+                (_, error) => ApiReply::Error(
+                    error
+                        .and_then(|error| error.get("message"))
+                        .and_then(|message| message.as_str())
+                        .unwrap_or("JSON-RPC error")
+                        .to_string(),
+                ),
+            };
+        }
+        match serde_json::from_value::<ApiEnvelope>(body) {
+            Ok(envelope) => envelope.reply(),
+            Err(e) => ApiReply::Error(format!("unexpected API response: {}", e)),
+        }
+    }
+}
+
+/// A GET against the V2 endpoint at `api_url` for `chain_id`, with the key and `params` as query parameters
+pub fn v2_request(client: &Client, api_url: &str, chain_id: u64, api_key: &str, params: &[(&str, &str)]) -> RequestBuilder {
+    client
+        .get(api_url)
+        .query(&[("chainid", chain_id.to_string())])
+        .query(params)
+        .query(&[("apikey", api_key)])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn envelope(json: &str) -> ApiEnvelope {
+        serde_json::from_str(json).unwrap()
+    }
+
+    #[test]
+    fn classifies_envelopes() {
+        let reply = envelope(r#"{"status": "1", "message": "OK", "result": [1]}"#).reply();
+        assert_eq!(reply, ApiReply::Records(serde_json::json!([1])));
+        assert_eq!(
+            envelope(r#"{"status": "0", "message": "No records found", "result": []}"#).reply(),
+            ApiReply::NoRecords
+        );
+        assert_eq!(
+            envelope(r#"{"status": "0", "message": "No transactions found", "result": []}"#).reply(),
+            ApiReply::NoRecords
+        );
+        assert_eq!(
+            envelope(r#"{"status": "0", "message": "NOTOK", "result": "Max rate limit reached"}"#).reply(),
+            ApiReply::RateLimited
+        );
+        assert_eq!(
+            envelope(r#"{"status": "0", "message": "NOTOK", "result": "Invalid API Key"}"#).reply(),
+            ApiReply::Error("NOTOK Invalid API Key".to_string())
+        );
+    }
+
+    #[test]
+    fn reads_proxy_responses() {
+        let reply = ApiReply::from_body(serde_json::json!({"jsonrpc": "2.0", "id": 1, "result": "0x1b"}));
+        assert_eq!(reply, ApiReply::Records(serde_json::json!("0x1b")));
+        let reply = ApiReply::from_body(serde_json::json!({"jsonrpc": "2.0", "id": 1, "error": {"code": -32602, "message": "invalid address"}}));
+        assert_eq!(reply, ApiReply::Error("invalid address".to_string()));
+        let reply = ApiReply::from_body(serde_json::json!({"status": "0", "message": "NOTOK", "result": "Max rate limit reached"}));
+        assert_eq!(reply, ApiReply::RateLimited);
+    }
+}
@@ -0,0 +1,115 @@
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+/// The temporary file a write to `path` goes through, `path` with `.tmp` appended
+fn tmp_path(path: &Path) -> PathBuf {
+    let mut tmp_path = path.as_os_str().to_owned();
+    tmp_path.push(".tmp");
+    PathBuf::from(tmp_path)
+}
+
+/// Writes `path` through a temporary file beside it that is renamed into place once `write`
+/// succeeds, so readers see either the old or the new complete file, never a truncated one
+pub fn write_atomic<E: From<io::Error>>(
+    path: &Path,
+    write: impl FnOnce(&mut BufWriter<File>) -> Result<(), E>,
+) -> Result<(), E> {
+    let tmp_path = tmp_path(path);
+
+    let result = (|| {
+        let mut writer = BufWriter::new(File::create(&tmp_path)?);
+        write(&mut writer)?;
+        writer.into_inner().map_err(|e| e.into_error())?.sync_all()?;
+        std::fs::rename(&tmp_path, path)?;
+        Ok(())
+    })();
+    if result.is_err() {
+        let _ = std::fs::remove_file(&tmp_path);
+    }
+    result
+}
+
+/// A file written in pieces through a temporary file beside `path`, which `commit` renames
+/// into place. Dropping it uncommitted removes the temporary file and leaves `path` as it was.
+pub struct AtomicFile {
+    file: File,
+    path: PathBuf,
+    tmp_path: PathBuf,
+    committed: bool,
+}
+
+impl AtomicFile {
+    pub fn create(path: &Path) -> io::Result<Self> {
+        let tmp_path = tmp_path(path);
+        let file = File::create(&tmp_path)?;
+        Ok(Self { file, path: path.to_path_buf(), tmp_path, committed: false })
+    }
+
+    /// Syncs the temporary file and renames it over the target
+    pub fn commit(mut self) -> io::Result<()> {
+        self.file.sync_all()?;
+        std::fs::rename(&self.tmp_path, &self.path)?;
+        self.committed = true;
+        Ok(())
+    }
+}
+
+impl Write for AtomicFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.file.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.file.flush()
+    }
+}
+
+impl Drop for AtomicFile {
+    fn drop(&mut self) {
+        if !self.committed {
+            let _ = std::fs::remove_file(&self.tmp_path);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn failed_write_leaves_the_old_file() {
+        let path = std::env::temp_dir().join(format!("scathat-atomic-{}.json", std::process::id()));
+        write_atomic(&path, |writer| writer.write_all(b"{\"complete\": true}")).unwrap();
+
+        let result = write_atomic(&path, |writer| {
+            writer.write_all(b"{\"compl")?;
+            Err(io::Error::other("killed mid-write"))
+        });
+
+        assert!(result.is_err());
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "{\"complete\": true}");
+        assert!(!path.with_extension("json.tmp").exists());
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn atomic_file_replaces_the_target_only_on_commit() {
+        let path = std::env::temp_dir().join(format!("scathat-atomic-file-{}.json", std::process::id()));
+        std::fs::write(&path, "old").unwrap();
+
+        let mut file = AtomicFile::create(&path).unwrap();
+        file.write_all(b"[").unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "old");
+        drop(file);
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "old");
+        assert!(!path.with_extension("json.tmp").exists());
+
+        let mut file = AtomicFile::create(&path).unwrap();
+        file.write_all(b"[]").unwrap();
+        file.commit().unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "[]");
+        assert!(!path.with_extension("json.tmp").exists());
+        std::fs::remove_file(path).unwrap();
+    }
+}
@@ -0,0 +1,255 @@
+use crate::{with_rng, CoreError};
+use rand::seq::SliceRandom;
+use reqwest::header::{CONTENT_TYPE, USER_AGENT};
+use reqwest::{Certificate, Client, Proxy, RequestBuilder, Response, StatusCode, Url};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tiny_keccak::{Hasher, Keccak};
+
+pub const DEFAULT_USER_AGENT: &str = "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36";
+
+/// Rate limiting and the server errors a retry usually gets past
+pub const DEFAULT_RETRY_STATUSES: [u16; 5] = [429, 500, 502, 503, 504];
+
+/// Current desktop browsers, used when rotation is enabled without a user agent file
+const BUILTIN_USER_AGENTS: [&str; 6] = [
+    "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/124.0.0.0 Safari/537.36",
+    "Mozilla/5.0 (Macintosh; Intel Mac OS X 10_15_7) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/124.0.0.0 Safari/537.36",
+    "Mozilla/5.0 (X11; Linux x86_64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/124.0.0.0 Safari/537.36",
+    "Mozilla/5.0 (Windows NT 10.0; Win64; x64; rv:125.0) Gecko/20100101 Firefox/125.0",
+    "Mozilla/5.0 (Macintosh; Intel Mac OS X 14_4_1) AppleWebKit/605.1.15 (KHTML, like Gecko) Version/17.4.1 Safari/605.1.15",
+    "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/124.0.0.0 Safari/537.36 Edg/124.0.0.0",
+];
+
+/// Proxy variables reqwest reads on its own when no explicit proxy is given
+const PROXY_ENV_VARS: [&str; 6] = ["HTTPS_PROXY", "https_proxy", "HTTP_PROXY", "http_proxy", "ALL_PROXY", "all_proxy"];
+
+/// Markers of Cloudflare interstitials served with a 200 instead of the real page
+const CHALLENGE_MARKERS: [&str; 6] = [
+    "<title>Just a moment...</title>",
+    "cf-browser-verification",
+    "/cdn-cgi/challenge-platform/",
+    "cf_chl_opt",
+    "Attention Required! | Cloudflare",
+    "Checking your browser before accessing",
+];
+
+/// HTTP settings shared with the other scathat scrapers
+#[derive(Debug, Clone)]
+pub struct HttpOptions {
+    /// Whole-request timeout; zero disables it
+    pub timeout: Duration,
+    pub request_delay: Duration,
+    /// Proxy for every request (`http://`, `https://`, `socks5://` or `socks5h://`).
+    /// When unset, the standard `HTTP_PROXY`/`HTTPS_PROXY`/`ALL_PROXY` variables apply.
+    pub proxy: Option<String>,
+    /// User agents to rotate through, one picked at random per request.
+    /// When empty, every request uses the client's single default user agent.
+    pub user_agents: Vec<String>,
+    /// Serve pages from disk when they were fetched recently; `None` always hits the network
+    pub cache: Option<PageCache>,
+    /// Refuse to parse responses whose `Content-Type` is not HTML
+    pub check_content_type: bool,
+    /// Error statuses worth retrying; any other error status fails at once.
+    /// Network errors are retried regardless.
+    pub retry_statuses: Vec<u16>,
+    /// PEM file of extra root certificates to trust, e.g. an internal CA
+    pub ca_cert: Option<PathBuf>,
+    /// Skip TLS certificate verification entirely; only for mirrors you control
+    pub accept_invalid_certs: bool,
+}
+
+impl Default for HttpOptions {
+    fn default() -> Self {
+        Self {
+            timeout: Duration::from_secs(30),
+            request_delay: Duration::from_millis(1000),
+            proxy: None,
+            user_agents: Vec::new(),
+            cache: None,
+            check_content_type: true,
+            retry_statuses: DEFAULT_RETRY_STATUSES.to_vec(),
+            ca_cert: None,
+            accept_invalid_certs: false,
+        }
+    }
+}
+
+impl HttpOptions {
+    pub fn is_retryable(&self, status: StatusCode) -> bool {
+        self.retry_statuses.contains(&status.as_u16())
+    }
+}
+
+/// On-disk cache of fetched pages, one file per URL named by the URL's Keccak-256 hash
+#[derive(Debug, Clone)]
+pub struct PageCache {
+    dir: PathBuf,
+    ttl: Duration,
+}
+
+impl PageCache {
+    pub fn new(dir: PathBuf, ttl: Duration) -> Self {
+        Self { dir, ttl }
+    }
+
+    fn path(&self, url: &str) -> PathBuf {
+        let mut hasher = Keccak::v256();
+        let mut hash = [0u8; 32];
+        hasher.update(url.as_bytes());
+        hasher.finalize(&mut hash);
+        let name: String = hash.iter().map(|byte| format!("{:02x}", byte)).collect();
+        self.dir.join(format!("{}.html", name))
+    }
+
+    /// Returns the cached body for `url` if it was stored within the TTL
+    pub fn get(&self, url: &str) -> Option<String> {
+        let path = self.path(url);
+        let modified = fs::metadata(&path).ok()?.modified().ok()?;
+        if modified.elapsed().unwrap_or_default() > self.ttl {
+            return None;
+        }
+
+        let body = fs::read_to_string(&path).ok()?;
+        log::debug!("Serving {} from cache {}", url, path.display());
+        Some(body)
+    }
+
+    /// Stores a fetched body, logging rather than failing when the cache can't be written
+    pub fn put(&self, url: &str, body: &str) {
+        if let Err(e) = fs::create_dir_all(&self.dir).and_then(|()| fs::write(self.path(url), body)) {
+            log::warn!("Failed to cache {} in {}: {}", url, self.dir.display(), e);
+        }
+    }
+}
+
+/// Builds the HTTP client, rejecting malformed proxy settings instead of silently going direct
+pub fn build_client(http: &HttpOptions) -> Result<Client, CoreError> {
+    let mut builder = Client::builder().user_agent(DEFAULT_USER_AGENT);
+    if !http.timeout.is_zero() {
+        builder = builder.timeout(http.timeout);
+    }
+
+    match &http.proxy {
+        Some(proxy) => {
+            validate_proxy_url(proxy)?;
+            let proxy = Proxy::all(proxy).map_err(|e| CoreError::Config(format!("invalid proxy {}: {}", proxy, e)))?;
+            builder = builder.proxy(proxy);
+        }
+        None => {
+            for var in PROXY_ENV_VARS {
+                if let Some(value) = std::env::var(var).ok().filter(|value| !value.is_empty()) {
+                    validate_proxy_url(&value).map_err(|e| CoreError::Config(format!("{} from {}", e, var)))?;
+                }
+            }
+        }
+    }
+
+    if let Some(path) = &http.ca_cert {
+        let pem = fs::read(path)
+            .map_err(|e| CoreError::Config(format!("cannot read CA certificate {}: {}", path.display(), e)))?;
+        let certificates = Certificate::from_pem_bundle(&pem)
+            .map_err(|e| CoreError::Config(format!("invalid CA certificate {}: {}", path.display(), e)))?;
+        if certificates.is_empty() {
+            return Err(CoreError::Config(format!("CA certificate file {} holds no certificates", path.display())));
+        }
+        for certificate in certificates {
+            builder = builder.add_root_certificate(certificate);
+        }
+    }
+    if http.accept_invalid_certs {
+        log::warn!("TLS certificate verification is DISABLED: any server, or anyone in between, can impersonate the sites scraped");
+        builder = builder.danger_accept_invalid_certs(true);
+    }
+
+    Ok(builder.build()?)
+}
+
+/// Resolves the user agent pool: one agent per line from `file`, the built-in list
+/// when only `rotate` is set, or empty to keep the single default
+pub fn user_agent_pool(file: Option<&Path>, rotate: bool) -> Result<Vec<String>, CoreError> {
+    let Some(file) = file else {
+        return Ok(if rotate {
+            BUILTIN_USER_AGENTS.iter().map(|agent| agent.to_string()).collect()
+        } else {
+            Vec::new()
+        });
+    };
+
+    let contents = std::fs::read_to_string(file)
+        .map_err(|e| CoreError::Config(format!("cannot read user agent file {}: {}", file.display(), e)))?;
+    let agents: Vec<String> = contents
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(str::to_string)
+        .collect();
+
+    if agents.is_empty() {
+        return Err(CoreError::Config(format!("user agent file {} is empty", file.display())));
+    }
+
+    Ok(agents)
+}
+
+/// Overrides the client's user agent with a random pick from the pool, if there is one
+pub fn with_user_agent(request: RequestBuilder, user_agents: &[String]) -> RequestBuilder {
+    match with_rng(|rng| user_agents.choose(rng)) {
+        Some(agent) => request.header(USER_AGENT, agent),
+        None => request,
+    }
+}
+
+/// Whether Cloudflare flagged the response as a challenge, whatever its status
+pub fn has_challenge_header(response: &Response) -> bool {
+    response.headers().get("cf-mitigated").is_some_and(|value| value == "challenge")
+}
+
+/// Whether a page body is a Cloudflare challenge or captcha rather than real content
+pub fn is_challenge_body(body: &str) -> bool {
+    CHALLENGE_MARKERS.iter().any(|marker| body.contains(marker))
+}
+
+/// The response's content type when it is not HTML, so JSON, PDFs and error blobs are
+/// rejected before parsing. A response without a `Content-Type` header is assumed to be HTML.
+pub fn non_html_content_type(response: &Response) -> Option<String> {
+    let content_type = response.headers().get(CONTENT_TYPE)?.to_str().unwrap_or_default();
+    let mime = content_type.split(';').next().unwrap_or_default().trim();
+    if mime.eq_ignore_ascii_case("text/html") || mime.eq_ignore_ascii_case("application/xhtml+xml") {
+        None
+    } else {
+        Some(content_type.to_string())
+    }
+}
+
+fn validate_proxy_url(proxy: &str) -> Result<(), CoreError> {
+    let url = Url::parse(proxy).map_err(|e| CoreError::Config(format!("invalid proxy URL {}: {}", proxy, e)))?;
+
+    if !matches!(url.scheme(), "http" | "https" | "socks5" | "socks5h") {
+        return Err(CoreError::Config(format!(
+            "unsupported proxy scheme {} in {}, expected http, https, socks5 or socks5h",
+            url.scheme(),
+            proxy
+        )));
+    }
+
+    if url.host_str().is_none() {
+        return Err(CoreError::Config(format!("proxy URL {} has no host", proxy)));
+    }
+
+    Ok(())
+}
+
+/// Reads a `Retry-After` header given in seconds
+pub fn retry_after(response: &Response) -> Option<Duration> {
+    response
+        .headers()
+        .get(reqwest::header::RETRY_AFTER)?
+        .to_str()
+        .ok()?
+        .trim()
+        .parse()
+        .ok()
+        .map(Duration::from_secs)
+}
@@ -0,0 +1,25 @@
+//! HTTP, Etherscan API, rate limiting, retries, logging, randomness, object storage, file and address helpers shared by the scathat scrapers
+
+mod address;
+mod error;
+pub mod etherscan;
+mod fs;
+pub mod http;
+mod logging;
+mod outcome;
+mod random;
+mod rate_limit;
+mod retry;
+mod storage;
+
+pub use address::{is_valid_ethereum_address, to_checksum_address, verify_checksum, ADDRESS_REGEX};
+pub use error::CoreError;
+pub use etherscan::{ApiEnvelope, ApiReply, ETHERSCAN_V2_API_URL};
+pub use fs::{write_atomic, AtomicFile};
+pub use http::{build_client, user_agent_pool, HttpOptions, PageCache, DEFAULT_RETRY_STATUSES};
+pub use logging::{init_logging, LogFormat};
+pub use outcome::{RunOutcome, EXIT_CODES_HELP};
+pub use random::{seed_rng, with_rng};
+pub use rate_limit::{jittered, RateLimiter};
+pub use retry::{with_retry, RetryError, RetryPolicy};
+pub use storage::S3Store;
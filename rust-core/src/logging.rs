@@ -0,0 +1,29 @@
+use clap::ValueEnum;
+use std::io::Write;
+
+/// Log line format for the binaries
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LogFormat {
+    /// env_logger's human-readable lines
+    #[default]
+    Text,
+    /// One JSON object per line with `timestamp`, `level`, `target` and `message`
+    Json,
+}
+
+/// Installs the global logger, honouring `RUST_LOG` over `default_filter`
+pub fn init_logging(default_filter: &str, format: LogFormat) {
+    let mut builder = env_logger::Builder::from_env(env_logger::Env::default().default_filter_or(default_filter));
+    if format == LogFormat::Json {
+        builder.format(|buf, record| {
+            let line = serde_json::json!({
+                "timestamp": chrono::Utc::now().to_rfc3339(),
+                "level": record.level().as_str(),
+                "target": record.target(),
+                "message": record.args().to_string(),
+            });
+            writeln!(buf, "{}", line)
+        });
+    }
+    builder.init();
+}
@@ -0,0 +1,62 @@
+use std::process::ExitCode;
+
+/// Exit codes listed in the binaries' `--help`
+pub const EXIT_CODES_HELP: &str = "Exit codes:
+  0  success; new results were found, or a monitoring loop was interrupted
+  1  error, such as an unreadable config or state file, or a fatal scrape error with --fail-fast
+  2  invalid command line
+  3  the scrape ran but found nothing new
+  4  nothing new was found and some pages failed or were rate limited";
+
+/// How a scrape run ended, reported to the caller as the process exit code
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RunOutcome {
+    /// New results were found
+    Success,
+    /// Every page loaded but none held anything new
+    NoResults,
+    /// Nothing new was found, and at least one source could not be scraped, so
+    /// "nothing new" can't be trusted
+    Failed,
+}
+
+impl RunOutcome {
+    /// The outcome of a run that found `new_results` with `failures` sources left unscraped
+    pub fn from_counts(new_results: usize, failures: usize) -> Self {
+        if new_results > 0 {
+            RunOutcome::Success
+        } else if failures > 0 {
+            RunOutcome::Failed
+        } else {
+            RunOutcome::NoResults
+        }
+    }
+
+    /// Exit status for this outcome; 1 is left to errors and 2 to clap's usage errors
+    pub fn code(self) -> u8 {
+        match self {
+            RunOutcome::Success => 0,
+            RunOutcome::NoResults => 3,
+            RunOutcome::Failed => 4,
+        }
+    }
+}
+
+impl From<RunOutcome> for ExitCode {
+    fn from(outcome: RunOutcome) -> Self {
+        ExitCode::from(outcome.code())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn new_results_win_over_failures() {
+        assert_eq!(RunOutcome::from_counts(2, 1), RunOutcome::Success);
+        assert_eq!(RunOutcome::from_counts(0, 0), RunOutcome::NoResults);
+        assert_eq!(RunOutcome::from_counts(0, 3), RunOutcome::Failed);
+        assert_eq!(RunOutcome::Failed.code(), 4);
+    }
+}
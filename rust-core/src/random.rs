@@ -0,0 +1,35 @@
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use std::sync::{Mutex, OnceLock};
+
+/// The one generator behind every random choice the scrapers make: backoff jitter, user
+/// agent rotation and the wallet scraper's stagger offsets
+static RNG: OnceLock<Mutex<StdRng>> = OnceLock::new();
+
+fn rng() -> &'static Mutex<StdRng> {
+    RNG.get_or_init(|| Mutex::new(StdRng::from_entropy()))
+}
+
+/// Reseeds the shared generator from `seed`, or from a fresh random seed without one, and
+/// returns the seed so a run can be repeated. Concurrent tasks draw in whatever order they
+/// are scheduled, so a seed reproduces a run exactly only when its requests are sequential.
+pub fn seed_rng(seed: Option<u64>) -> u64 {
+    let seed = match seed {
+        Some(seed) => {
+            log::info!("Using random seed {}", seed);
+            seed
+        }
+        None => {
+            let seed = rand::random();
+            log::debug!("Using random seed {}; pass it as --seed to repeat this run", seed);
+            seed
+        }
+    };
+    *rng().lock().expect("rng lock poisoned") = StdRng::seed_from_u64(seed);
+    seed
+}
+
+/// Runs `f` with the shared generator
+pub fn with_rng<T>(f: impl FnOnce(&mut StdRng) -> T) -> T {
+    f(&mut rng().lock().expect("rng lock poisoned"))
+}
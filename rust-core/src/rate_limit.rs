@@ -0,0 +1,100 @@
+use crate::with_rng;
+use rand::Rng;
+use std::time::{Duration, Instant};
+use tokio::time::sleep;
+
+/// Token bucket: up to `capacity` requests go out back to back, then one per refill interval
+#[derive(Clone)]
+pub struct RateLimiter {
+    capacity: f64,
+    tokens: f64,
+    refill_per_sec: f64,
+    last_refill: Instant,
+}
+
+impl RateLimiter {
+    /// At most one request every `min_delay`, without bursts
+    pub fn new(min_delay: Duration) -> Self {
+        let refill_per_sec = if min_delay.is_zero() { f64::INFINITY } else { 1.0 / min_delay.as_secs_f64() };
+        Self::token_bucket(1, refill_per_sec)
+    }
+
+    /// Bursts of up to `capacity` requests, refilled at `refill_per_sec` tokens per second.
+    /// A rate that isn't positive, or NaN, never limits, like a zero delay in `new`.
+    pub fn token_bucket(capacity: u32, refill_per_sec: f64) -> Self {
+        let capacity = f64::from(capacity.max(1));
+        Self {
+            capacity,
+            tokens: capacity,
+            refill_per_sec: if refill_per_sec > 0.0 { refill_per_sec } else { f64::INFINITY },
+            last_refill: Instant::now(),
+        }
+    }
+
+    pub async fn wait(&mut self) {
+        self.refill();
+        if self.tokens < 1.0 {
+            sleep(Duration::from_secs_f64((1.0 - self.tokens) / self.refill_per_sec)).await;
+            self.refill();
+        }
+        self.tokens = (self.tokens - 1.0).max(0.0);
+    }
+
+    fn refill(&mut self) {
+        let now = Instant::now();
+        self.tokens = if self.refill_per_sec.is_infinite() {
+            self.capacity
+        } else {
+            let refilled = now.duration_since(self.last_refill).as_secs_f64() * self.refill_per_sec;
+            (self.tokens + refilled).min(self.capacity)
+        };
+        self.last_refill = now;
+    }
+}
+
+/// Picks a random duration in `[delay / 2, delay]` so concurrent retries don't
+/// all fire at the same instant after a shared 429
+pub fn jittered(delay: Duration) -> Duration {
+    let half = delay / 2;
+    half + (delay - half).mul_f64(with_rng(|rng| rng.gen::<f64>()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn token_bucket_allows_bursts_then_refills() {
+        let mut limiter = RateLimiter::token_bucket(3, 20.0);
+        let started = Instant::now();
+        for _ in 0..3 {
+            limiter.wait().await;
+        }
+        assert!(started.elapsed() < Duration::from_millis(40));
+
+        limiter.wait().await;
+        assert!(started.elapsed() >= Duration::from_millis(45));
+    }
+
+    #[tokio::test]
+    async fn fixed_delay_limiter_spaces_every_request() {
+        let mut limiter = RateLimiter::new(Duration::from_millis(30));
+        let started = Instant::now();
+        for _ in 0..3 {
+            limiter.wait().await;
+        }
+        assert!(started.elapsed() >= Duration::from_millis(58));
+    }
+
+    #[tokio::test]
+    async fn non_positive_rates_do_not_limit() {
+        for rate in [0.0, -1.0, f64::NAN] {
+            let mut limiter = RateLimiter::token_bucket(1, rate);
+            let started = Instant::now();
+            for _ in 0..3 {
+                limiter.wait().await;
+            }
+            assert!(started.elapsed() < Duration::from_millis(40), "{}", rate);
+        }
+    }
+}
@@ -0,0 +1,129 @@
+use crate::jittered;
+use std::fmt::Display;
+use std::future::Future;
+use std::time::Duration;
+use tokio::time::sleep;
+
+/// How often and how patiently `with_retry` repeats a failing operation
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RetryPolicy {
+    /// Attempts in total, including the first; 0 behaves like 1
+    pub max_attempts: u32,
+    /// Wait before the first retry, doubled after each further one
+    pub base_delay: Duration,
+    /// Ceiling for the doubled wait
+    pub max_delay: Duration,
+    /// Spread each wait over `[delay / 2, delay]` so concurrent retries don't fire together
+    pub jitter: bool,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            base_delay: Duration::from_secs(1),
+            max_delay: Duration::from_secs(60),
+            jitter: true,
+        }
+    }
+}
+
+/// A failed attempt, classified by whether trying again could help
+#[derive(Debug)]
+pub enum RetryError<E> {
+    /// Worth another attempt; from `with_retry` it means every attempt failed
+    Retryable(E),
+    /// Would only fail the same way, so `with_retry` returns it at once
+    Fatal(E),
+}
+
+impl<E> RetryError<E> {
+    pub fn into_inner(self) -> E {
+        match self {
+            RetryError::Retryable(e) | RetryError::Fatal(e) => e,
+        }
+    }
+}
+
+/// Runs `op`, passing the 1-based attempt number, until it succeeds, fails fatally or runs
+/// out of attempts, sleeping with exponential backoff between attempts. `what` names the
+/// operation in the retry warnings.
+pub async fn with_retry<T, E, F, Fut>(policy: &RetryPolicy, what: &str, mut op: F) -> Result<T, RetryError<E>>
+where
+    E: Display,
+    F: FnMut(u32) -> Fut,
+    Fut: Future<Output = Result<T, RetryError<E>>>,
+{
+    let mut delay = policy.base_delay;
+    let mut attempt = 1;
+
+    loop {
+        match op(attempt).await {
+            Err(RetryError::Retryable(e)) if attempt < policy.max_attempts => {
+                let wait = if policy.jitter { jittered(delay) } else { delay };
+                log::warn!("{} failed: {}. Retrying in {:?}", what, e, wait);
+                sleep(wait).await;
+                delay = (delay * 2).min(policy.max_delay);
+                attempt += 1;
+            }
+            result => return result,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Instant;
+
+    fn policy(max_attempts: u32) -> RetryPolicy {
+        RetryPolicy {
+            max_attempts,
+            base_delay: Duration::from_millis(10),
+            max_delay: Duration::from_millis(15),
+            jitter: false,
+        }
+    }
+
+    #[tokio::test]
+    async fn retries_until_the_operation_succeeds() {
+        let mut calls = 0;
+        let started = Instant::now();
+        let result = with_retry(&policy(5), "counting", |attempt| {
+            calls += 1;
+            async move { if attempt < 3 { Err(RetryError::Retryable("not yet")) } else { Ok(attempt) } }
+        })
+        .await;
+
+        assert_eq!(result.unwrap(), 3);
+        assert_eq!(calls, 3);
+        // 10ms, then 20ms capped to 15ms
+        assert!(started.elapsed() >= Duration::from_millis(25));
+    }
+
+    #[tokio::test]
+    async fn gives_up_after_max_attempts() {
+        let mut calls = 0;
+        let result: Result<(), _> = with_retry(&policy(3), "counting", |_| {
+            calls += 1;
+            async { Err(RetryError::Retryable("still failing")) }
+        })
+        .await;
+
+        assert!(matches!(result, Err(RetryError::Retryable("still failing"))));
+        assert_eq!(calls, 3);
+    }
+
+    #[tokio::test]
+    async fn fatal_errors_are_not_retried() {
+        let mut calls = 0;
+        let result: Result<(), _> = with_retry(&policy(3), "counting", |_| {
+            calls += 1;
+            async { Err(RetryError::Fatal("not found")) }
+        })
+        .await;
+
+        assert_eq!(result.unwrap_err().into_inner(), "not found");
+        assert_eq!(calls, 1);
+    }
+}
@@ -0,0 +1,78 @@
+use crate::{with_retry, CoreError, RetryError, RetryPolicy};
+use s3::creds::Credentials;
+use s3::{Bucket, Region};
+use std::time::Duration;
+
+/// Backoff for uploads: three attempts, one and then two seconds apart
+const UPLOAD_RETRY: RetryPolicy = RetryPolicy {
+    max_attempts: 3,
+    base_delay: Duration::from_secs(1),
+    max_delay: Duration::from_secs(2),
+    jitter: false,
+};
+
+/// An S3-compatible bucket and key prefix; each scraper decides what it uploads there
+pub struct S3Store {
+    bucket: Box<Bucket>,
+    prefix: String,
+}
+
+impl S3Store {
+    /// Credentials come from the usual AWS environment variables or profile.
+    /// A custom `endpoint` targets S3-compatible stores such as MinIO and uses path-style URLs.
+    pub fn new(bucket: &str, region: &str, endpoint: Option<&str>, prefix: &str) -> Result<Self, CoreError> {
+        let region = match endpoint {
+            Some(endpoint) => Region::Custom {
+                region: region.to_string(),
+                endpoint: endpoint.to_string(),
+            },
+            None => region
+                .parse()
+                .map_err(|e| CoreError::Storage(format!("invalid S3 region {}: {}", region, e)))?,
+        };
+
+        let credentials =
+            Credentials::default().map_err(|e| CoreError::Storage(format!("missing S3 credentials: {}", e)))?;
+
+        let mut bucket = Bucket::new(bucket, region, credentials)
+            .map_err(|e| CoreError::Storage(format!("invalid S3 bucket {}: {}", bucket, e)))?;
+        if endpoint.is_some() {
+            bucket = bucket.with_path_style();
+        }
+
+        Ok(Self {
+            bucket,
+            prefix: prefix.trim_matches('/').to_string(),
+        })
+    }
+
+    /// The key for `name` under the prefix
+    pub fn key(&self, name: &str) -> String {
+        format!("{}/{}", self.prefix, name)
+    }
+
+    /// Uploads `content` under `key`, retrying network failures, 429s and server errors with
+    /// backoff, and returns its `s3://` URL
+    pub async fn upload(&self, key: &str, content: &[u8], content_type: &str) -> Result<String, CoreError> {
+        let bucket = &self.bucket;
+        with_retry(&UPLOAD_RETRY, &format!("S3 upload of {}", key), move |_| async move {
+            match bucket.put_object_with_content_type(key, content, content_type).await {
+                Ok(response) if (200..300).contains(&response.status_code()) => Ok(()),
+                Ok(response) => {
+                    let status = response.status_code();
+                    let error = CoreError::Storage(format!("upload failed with status {}", status));
+                    // Statuses such as 403 would only come back the same
+                    if status == 429 || status >= 500 {
+                        Err(RetryError::Retryable(error))
+                    } else {
+                        Err(RetryError::Fatal(error))
+                    }
+                }
+                Err(e) => Err(RetryError::Retryable(CoreError::Storage(e.to_string()))),
+            }
+        })
+        .await
+        .map_err(RetryError::into_inner)?;
+        Ok(format!("s3://{}/{}", self.bucket.name(), key))
+    }
+}
@@ -0,0 +1,151 @@
+use anyhow::Result;
+use scathat_core::{
+    init_logging, seed_rng, user_agent_pool, HttpOptions, LogFormat, PageCache, DEFAULT_RETRY_STATUSES, EXIT_CODES_HELP,
+};
+use clap::{Args, Parser, Subcommand};
+use std::path::PathBuf;
+use std::process::ExitCode;
+use std::time::Duration;
+
+#[derive(Parser, Debug)]
+#[command(name = "scathat", about = "Scathat wallet and contract scrapers", after_help = EXIT_CODES_HELP)]
+struct Cli {
+    #[command(flatten)]
+    common: CommonArgs,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+/// Options shared by every scraper subcommand
+#[derive(Args, Debug)]
+struct CommonArgs {
+    /// Request timeout in seconds; 0 disables the timeout
+    #[arg(long, global = true, default_value_t = 30)]
+    timeout: u64,
+
+    /// Minimum delay between requests in milliseconds
+    #[arg(long, global = true, default_value_t = 1000)]
+    request_delay_ms: u64,
+
+    /// Proxy URL (http, https, socks5 or socks5h); defaults to HTTP_PROXY/HTTPS_PROXY
+    #[arg(long, global = true)]
+    proxy: Option<String>,
+
+    /// Also trust the root certificates in this PEM file, e.g. for a mirror with an internal CA
+    #[arg(long, global = true)]
+    ca_cert: Option<PathBuf>,
+
+    /// Accept any TLS certificate, even expired, self-signed or for another host. Dangerous:
+    /// only for explorer mirrors you control
+    #[arg(long, global = true)]
+    danger_accept_invalid_certs: bool,
+
+    /// Rotate through the user agents in this file, one per line
+    #[arg(long, global = true)]
+    user_agents_file: Option<PathBuf>,
+
+    /// Rotate through a built-in list of browser user agents
+    #[arg(long, global = true)]
+    rotate_user_agents: bool,
+
+    /// Cache fetched pages in this directory and reuse them instead of refetching
+    #[arg(long, global = true)]
+    cache_dir: Option<PathBuf>,
+
+    /// Seconds a cached page stays valid
+    #[arg(long, global = true, default_value_t = 3600)]
+    cache_ttl: u64,
+
+    /// Parse responses as HTML whatever their Content-Type header says
+    #[arg(long, global = true)]
+    no_content_type_check: bool,
+
+    /// HTTP error statuses to retry, comma separated; other error statuses fail at once.
+    /// Network errors are always retried.
+    #[arg(long, global = true, value_delimiter = ',', default_values_t = DEFAULT_RETRY_STATUSES, value_parser = clap::value_parser!(u16).range(400..600))]
+    retry_status: Vec<u16>,
+
+    /// Log filter such as `info` or `debug`; RUST_LOG takes precedence
+    #[arg(long, global = true, default_value = "info")]
+    log_level: String,
+
+    /// Seed for every random choice: backoff jitter, user agent rotation and the wallet
+    /// scraper's stagger offsets. Runs with the same seed choose alike; without one the seed is random
+    #[arg(long, global = true)]
+    seed: Option<u64>,
+
+    /// Log line format
+    #[arg(long, global = true, value_enum, default_value_t = LogFormat::Text)]
+    log_format: LogFormat,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Scrape centralized exchange wallet addresses from etherscan
+    Wallets(cex_wallet_scraper::Args),
+    /// Monitor basescan for newly verified contracts
+    Contracts(basescan_scraper::Args),
+    /// Check whether an address is well-formed and correctly checksummed
+    Validate {
+        address: String,
+
+        /// Check the EIP-1191 checksum for this chain id instead of EIP-55
+        #[arg(long)]
+        chain_id: Option<u64>,
+    },
+    /// Serve wallet lookups over HTTP from the SQLite or JSON output
+    Serve(cex_wallet_scraper::ServeArgs),
+    /// Merge wallet files from earlier runs into one deduplicated file
+    Merge(cex_wallet_scraper::MergeArgs),
+    /// Print wallets per exchange, duplicates and other aggregates of a wallet file
+    Stats(cex_wallet_scraper::StatsArgs),
+    /// Print the contracts in the contract monitor's output that were scraped after an instant
+    Query(basescan_scraper::QueryArgs),
+}
+
+/// HTTP settings for the scraper subcommands, which share one client configuration
+fn http_options(common: CommonArgs) -> Result<HttpOptions> {
+    Ok(HttpOptions {
+        timeout: Duration::from_secs(common.timeout),
+        request_delay: Duration::from_millis(common.request_delay_ms),
+        proxy: common.proxy,
+        user_agents: user_agent_pool(common.user_agents_file.as_deref(), common.rotate_user_agents)?,
+        cache: common.cache_dir.map(|dir| PageCache::new(dir, Duration::from_secs(common.cache_ttl))),
+        check_content_type: !common.no_content_type_check,
+        retry_statuses: common.retry_status,
+        ca_cert: common.ca_cert,
+        accept_invalid_certs: common.danger_accept_invalid_certs,
+    })
+}
+
+#[tokio::main]
+async fn main() -> Result<ExitCode> {
+    let cli = Cli::parse();
+    init_logging(&cli.common.log_level, cli.common.log_format);
+    seed_rng(cli.common.seed);
+    
+    match cli.command {
+        Command::Wallets(args) => Ok(cex_wallet_scraper::run(args, http_options(cli.common)?).await?.into()),
+        Command::Contracts(args) => Ok(basescan_scraper::run(args, http_options(cli.common)?).await?.into()),
+        Command::Validate { address, chain_id } => {
+            Ok(if cex_wallet_scraper::validate_address(&address, chain_id) { ExitCode::SUCCESS } else { ExitCode::FAILURE })
+        }
+        Command::Serve(args) => {
+            cex_wallet_scraper::serve(args).await?;
+            Ok(ExitCode::SUCCESS)
+        }
+        Command::Merge(args) => {
+            cex_wallet_scraper::merge_files(&args)?;
+            Ok(ExitCode::SUCCESS)
+        }
+        Command::Stats(args) => {
+            cex_wallet_scraper::print_file_stats(&args, &mut std::io::stdout().lock())?;
+            Ok(ExitCode::SUCCESS)
+        }
+        Command::Query(args) => {
+            basescan_scraper::query_since(&args, &mut std::io::stdout().lock())?;
+            Ok(ExitCode::SUCCESS)
+        }
+    }
+}
@@ -0,0 +1,42 @@
+use crate::ScraperError;
+use growable_bloom_filter::GrowableBloom;
+use scathat_core::write_atomic;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::Path;
+
+/// Contracts a new filter is sized for before it grows
+const ESTIMATED_CONTRACTS: usize = 10_000;
+
+/// Processed contract addresses kept as a growable bloom filter instead of an exact set.
+///
+/// The filter takes a few bits per address rather than the full string, but a false
+/// positive makes a new contract look already processed, so it is silently skipped.
+pub struct BloomDedup {
+    filter: GrowableBloom,
+}
+
+impl BloomDedup {
+    /// Reads the filter saved at `path`, or starts an empty one with the given false-positive rate.
+    /// A saved filter keeps the rate it was created with.
+    pub fn load(path: &Path, false_positive_rate: f64) -> Result<Self, ScraperError> {
+        let filter = if path.exists() {
+            bincode::deserialize_from(BufReader::new(File::open(path)?))?
+        } else {
+            GrowableBloom::new(false_positive_rate, ESTIMATED_CONTRACTS)
+        };
+        Ok(Self { filter })
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), ScraperError> {
+        write_atomic(path, |writer| Ok(bincode::serialize_into(writer, &self.filter)?))
+    }
+
+    pub fn contains(&self, address: &str) -> bool {
+        self.filter.contains(address)
+    }
+
+    pub fn insert(&mut self, address: &str) {
+        self.filter.insert(address);
+    }
+}
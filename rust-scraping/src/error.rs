@@ -0,0 +1,72 @@
+use scathat_core::CoreError;
+use std::time::Duration;
+use thiserror::Error;
+
+/// Failures surfaced by the scraper library, split so callers can tell
+/// transient conditions (rate limiting, network) from permanent ones
+#[derive(Debug, Error)]
+pub enum ScraperError {
+    #[error("HTTP request failed: {0}")]
+    Http(#[from] reqwest::Error),
+
+    #[error("rate limited{}", .retry_after.map(|d| format!(", retry after {:?}", d)).unwrap_or_default())]
+    RateLimited { retry_after: Option<Duration> },
+
+    #[error("failed to parse page: {0}")]
+    Parse(String),
+
+    #[error("blocked by a challenge page at {0}")]
+    Challenge(String),
+
+    #[error("expected an HTML page at {url} but got {content_type}")]
+    UnexpectedContentType { url: String, content_type: String },
+
+    #[error("invalid address: {0}")]
+    InvalidAddress(String),
+
+    #[error("etherscan API error: {0}")]
+    Api(String),
+
+    #[error("invalid configuration: {0}")]
+    Config(String),
+
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("JSON error: {0}")]
+    Json(#[from] serde_json::Error),
+
+    #[error("SQLite error: {0}")]
+    Sqlite(#[from] rusqlite::Error),
+
+    #[error("bloom filter encoding error: {0}")]
+    Bloom(#[from] bincode::Error),
+
+    #[cfg(feature = "postgres")]
+    #[error("Postgres error: {0}")]
+    Postgres(#[from] sqlx::Error),
+
+    #[error("object storage error: {0}")]
+    Storage(String),
+}
+
+impl ScraperError {
+    /// Whether retrying the same request later may succeed
+    pub fn is_transient(&self) -> bool {
+        match self {
+            ScraperError::RateLimited { .. } | ScraperError::Challenge(_) => true,
+            ScraperError::Http(e) => e.is_timeout() || e.is_connect() || e.status().is_some_and(|s| s.is_server_error()),
+            _ => false,
+        }
+    }
+}
+
+impl From<CoreError> for ScraperError {
+    fn from(e: CoreError) -> Self {
+        match e {
+            CoreError::Http(e) => ScraperError::Http(e),
+            CoreError::Config(message) => ScraperError::Config(message),
+            CoreError::Storage(message) => ScraperError::Storage(message),
+        }
+    }
+}
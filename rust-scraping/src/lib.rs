@@ -0,0 +1,2205 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, NaiveDate, NaiveDateTime, Utc};
+use clap::{Parser, ValueEnum};
+use cron::Schedule;
+use futures::stream::{self, StreamExt};
+use regex::Regex;
+use reqwest::Client;
+use scraper::{Html, Selector};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashSet, VecDeque};
+use std::fs::{File, OpenOptions};
+use std::io::{BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
+use tiny_keccak::{Hasher, Keccak};
+use tokio::time::sleep;
+
+mod bloom;
+mod error;
+#[cfg(feature = "postgres")]
+mod postgres;
+mod query;
+mod storage;
+mod validators;
+
+pub use bloom::BloomDedup;
+pub use error::ScraperError;
+use scathat_core::etherscan::{self, ApiEnvelope, ApiReply};
+use scathat_core::{http, is_valid_ethereum_address, with_retry, write_atomic, RetryError, RetryPolicy, ADDRESS_REGEX, ETHERSCAN_V2_API_URL};
+pub use scathat_core::{
+    build_client, init_logging, seed_rng, user_agent_pool, HttpOptions, LogFormat, PageCache, RunOutcome, DEFAULT_RETRY_STATUSES,
+    EXIT_CODES_HELP,
+};
+#[cfg(feature = "postgres")]
+pub use postgres::PostgresSink;
+pub use query::{query_since, QueryArgs};
+pub use storage::S3Uploader;
+pub use validators::PageValidators;
+use validators::Validator;
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct VerifiedContract {
+    /// Name of the chain whose explorer listed the contract
+    #[serde(default)]
+    pub chain: String,
+    pub contract_address: String,
+    pub contract_name: String,
+    pub compiler_version: String,
+    pub contract_creator: String,
+    pub source_code: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub raw_source_code: Option<String>,
+    /// Keccak-256 of `source_code`, set once the source has been fetched. Taken before any
+    /// `--max-source-bytes` cut, so it still identifies the full source.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source_hash: Option<String>,
+    /// Length in bytes of the full fetched source, recorded with `--max-source-bytes`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source_length: Option<usize>,
+    /// Verified ABI as a JSON string, set with `--fetch-abi`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub abi: Option<String>,
+    /// When the contract was verified, from the listing's age or date column, falling back to `scraped_at`
+    pub timestamp: String,
+    /// When this row was scraped
+    #[serde(default)]
+    pub scraped_at: String,
+}
+
+/// Called with each new contract once it is written and marked processed, e.g. to feed a queue or dashboard
+pub type ContractObserver = Arc<dyn Fn(&VerifiedContract) + Send + Sync>;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ScraperState {
+    pub processed_contracts: HashSet<String>,
+    /// Newest contract timestamp recorded so far; listing rows older than this are not polled again
+    #[serde(default)]
+    pub watermark: Option<DateTime<Utc>>,
+    /// Source hashes of every contract written so far, used by `--dedup-source`
+    #[serde(default)]
+    pub source_hashes: HashSet<String>,
+    /// Size in bytes of the line-per-contract output when this state was saved. Anything past
+    /// it on startup was appended by a run that stopped before saving its state.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub output_len: Option<u64>,
+    /// Records in the `--json-array` output when this state was saved, used like `output_len`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub output_records: Option<usize>,
+}
+
+/// Payload shape for new-contract notifications
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WebhookFormat {
+    /// `{"chain", "count", "contracts": [{"address", "name"}]}`
+    Json,
+    /// A Discord message in the `content` field
+    Discord,
+    /// A Slack message in the `text` field
+    Slack,
+}
+
+#[derive(Parser, Debug)]
+#[command(about = "Monitor basescan for newly verified contracts")]
+pub struct Args {
+    /// Number of recent intervals kept for the rolling new-contract count
+    #[arg(long, default_value_t = 12)]
+    pub alert_window: usize,
+
+    /// Alert when an interval yields more than this many times the rolling average
+    #[arg(long)]
+    pub alert_spike_factor: Option<f64>,
+
+    /// Alert after this many consecutive intervals with zero new contracts
+    #[arg(long)]
+    pub alert_zero_intervals: Option<usize>,
+
+    /// Webhook URL that receives a JSON payload for each alert
+    #[arg(long)]
+    pub alert_webhook: Option<String>,
+
+    /// Webhook URL notified with the new contracts whenever some are found
+    #[arg(long)]
+    pub webhook: Option<String>,
+
+    /// Payload shape for --webhook
+    #[arg(long, value_enum, default_value_t = WebhookFormat::Json, requires = "webhook")]
+    pub webhook_format: WebhookFormat,
+
+    /// Etherscan API key; when set, source code of known chains is fetched from the
+    /// Etherscan V2 API instead of scraping each contract's code page
+    #[arg(long, env = "ETHERSCAN_API_KEY", hide_env_values = true)]
+    pub api_key: Option<String>,
+
+    /// Listing page of newly verified contracts to poll
+    #[arg(long, default_value = BASE_URL)]
+    pub url: String,
+
+    /// Poll this chain's explorer instead of `--url`; repeatable, polled in turn each cycle.
+    /// Each chain keeps its own `scraper_state_<chain>.json` and `verified_contracts_<chain>.json`.
+    #[arg(long = "chain", value_name = "NAME", value_parser = parse_chain_name, conflicts_with = "url")]
+    pub chains: Vec<String>,
+
+    /// Keep the state and output files in this directory, created if missing, instead of the working directory
+    #[arg(long)]
+    pub out_dir: Option<PathBuf>,
+
+    /// Seconds to wait between polling cycles
+    #[arg(long, default_value_t = 300)]
+    pub interval: u64,
+
+    /// Start each cycle at the times of this cron expression, with a seconds field, such as
+    /// "0 0 * * * *" for hourly on the hour, instead of waiting `--interval` after each one
+    #[arg(long, value_parser = parse_schedule, conflicts_with = "interval")]
+    pub cron: Option<Schedule>,
+
+    /// Exit after one cycle, with an exit code telling whether anything new was found
+    #[arg(long)]
+    pub once: bool,
+
+    /// Maximum number of listing pages walked per cycle
+    #[arg(long, default_value_t = 10)]
+    pub max_pages: u32,
+
+    /// Number of listing pages to fetch on the first cycle to backfill older contracts
+    #[arg(long, default_value_t = 1)]
+    pub backfill_pages: u32,
+
+    /// Maximum number of backfill pages fetched concurrently
+    #[arg(long, default_value_t = 4)]
+    pub backfill_concurrency: usize,
+
+    /// CSS selector for the verified contracts table
+    #[arg(long, default_value = "table.table")]
+    pub table_selector: String,
+
+    /// CSS selector for the rows of the contracts table
+    #[arg(long, default_value = "tbody tr")]
+    pub row_selector: String,
+
+    /// CSS selector for the cells of a contracts table row
+    #[arg(long, default_value = "td")]
+    pub cell_selector: String,
+
+    /// Only record contracts whose compiler version starts with this prefix (e.g. `v0.8`)
+    /// or matches this regex; repeatable. Skipped contracts are not marked processed.
+    #[arg(long = "compiler", value_name = "PATTERN")]
+    pub compilers: Vec<String>,
+
+    /// Only record contracts whose name matches this regex, e.g. `Token` or `^Uniswap`; combines
+    /// with --compiler. Skipped contracts are not marked processed.
+    #[arg(long, value_name = "REGEX")]
+    pub name_filter: Option<Regex>,
+
+    /// Skip writing contracts whose source is identical to one already written, e.g. factory clones
+    #[arg(long)]
+    pub dedup_source: bool,
+
+    /// Remember processed contracts in a bloom filter saved next to the state file instead of
+    /// listing every address in the state. Much smaller on disk, but a new contract is mistaken
+    /// for a processed one, and skipped, with probability `--bloom-fp-rate`.
+    #[arg(long)]
+    pub bloom_dedup: bool,
+
+    /// False-positive rate of a new `--bloom-dedup` filter; an existing filter keeps its own
+    #[arg(long, default_value_t = 0.0001, value_parser = parse_probability, requires = "bloom_dedup")]
+    pub bloom_fp_rate: f64,
+
+    /// Strip `//` and `/* */` comments from fetched source code before storing it
+    #[arg(long)]
+    pub strip_source_comments: bool,
+
+    /// With --strip-source-comments, also store the unmodified source in `raw_source_code`
+    #[arg(long, requires = "strip_source_comments")]
+    pub keep_raw_source: bool,
+
+    /// Cut stored source code to this many bytes, marked with "...[truncated]", and record the
+    /// full length in `source_length`; 0 stores only the length
+    #[arg(long)]
+    pub max_source_bytes: Option<usize>,
+
+    /// Also store each contract's ABI, read from its code page or, with --api-key, from the
+    /// `getabi` API call, which costs one more request per contract
+    #[arg(long)]
+    pub fetch_abi: bool,
+
+    /// Keep the output file a single JSON array instead of appending one object per line
+    #[arg(long)]
+    pub json_array: bool,
+
+    /// Also upsert new contracts into this SQLite database
+    #[arg(long)]
+    pub sqlite_out: Option<PathBuf>,
+
+    /// Also upsert new contracts into the `verified_contracts` table of this Postgres database
+    #[cfg(feature = "postgres")]
+    #[arg(long, env = "POSTGRES_URL")]
+    pub postgres_url: Option<String>,
+
+    /// Upload each batch of new contracts to this S3 bucket after writing it locally
+    #[arg(long, env = "S3_BUCKET")]
+    pub s3_bucket: Option<String>,
+
+    /// Region of the S3 bucket
+    #[arg(long, env = "AWS_REGION", default_value = "us-east-1")]
+    pub s3_region: String,
+
+    /// Endpoint of an S3-compatible store such as MinIO
+    #[arg(long, env = "S3_ENDPOINT")]
+    pub s3_endpoint: Option<String>,
+
+    /// Key prefix for uploaded objects; each batch is stored as `<prefix>/<timestamp>.ndjson`
+    #[arg(long, default_value = "verified-contracts")]
+    pub s3_prefix: String,
+}
+
+/// Rolling per-interval new-contract counts used to detect surges and stalls
+struct RateMonitor {
+    counts: VecDeque<usize>,
+    window: usize,
+    spike_factor: Option<f64>,
+    zero_intervals: Option<usize>,
+    consecutive_zero: usize,
+}
+
+impl RateMonitor {
+    fn new(window: usize, spike_factor: Option<f64>, zero_intervals: Option<usize>) -> Self {
+        Self {
+            counts: VecDeque::with_capacity(window),
+            window: window.max(1),
+            spike_factor,
+            zero_intervals,
+            consecutive_zero: 0,
+        }
+    }
+
+    fn rolling_average(&self) -> f64 {
+        if self.counts.is_empty() {
+            0.0
+        } else {
+            self.counts.iter().sum::<usize>() as f64 / self.counts.len() as f64
+        }
+    }
+
+    /// Records the count for one interval and returns an alert message if a threshold was crossed
+    fn record(&mut self, count: usize) -> Option<String> {
+        let average = self.rolling_average();
+        let mut alert = None;
+
+        if let Some(factor) = self.spike_factor {
+            // Require a full window so the first few intervals don't count as a surge
+            if self.counts.len() == self.window && count as f64 > factor * average.max(1.0) {
+                alert = Some(format!(
+                    "New contract surge: {} this interval vs rolling average {:.1}",
+                    count, average
+                ));
+            }
+        }
+
+        if count == 0 {
+            self.consecutive_zero += 1;
+        } else {
+            self.consecutive_zero = 0;
+        }
+
+        if let Some(limit) = self.zero_intervals {
+            // Only fire once when the threshold is crossed, not on every quiet interval after
+            if self.consecutive_zero == limit {
+                alert = Some(format!(
+                    "No new contracts for {} consecutive intervals, the scraper may be broken",
+                    self.consecutive_zero
+                ));
+            }
+        }
+
+        if self.counts.len() == self.window {
+            self.counts.pop_front();
+        }
+        self.counts.push_back(count);
+
+        alert
+    }
+}
+
+const BASE_URL: &str = "https://sepolia.basescan.org/contractsVerified";
+/// Known chains with their explorer and the chain id the Etherscan V2 API knows them by
+const CHAINS: [(&str, &str, u64); 6] = [
+    ("base-sepolia", "https://sepolia.basescan.org", 84532),
+    ("base", "https://basescan.org", 8453),
+    ("optimism", "https://optimistic.etherscan.io", 10),
+    ("arbitrum", "https://arbiscan.io", 42161),
+    ("ethereum", "https://etherscan.io", 1),
+    ("polygon", "https://polygonscan.com", 137),
+];
+const STATE_FILE: &str = "scraper_state.json";
+const OUTPUT_FILE: &str = "verified_contracts.json";
+/// Backoff for page fetches and for refetching pages that don't parse
+const RETRY_POLICY: RetryPolicy = RetryPolicy {
+    max_attempts: 3,
+    base_delay: Duration::from_secs(1),
+    max_delay: Duration::from_secs(60),
+    jitter: true,
+};
+const SOURCE_PLACEHOLDER: &str = "Source code would be fetched from individual contract page";
+const TRUNCATION_MARKER: &str = "...[truncated]";
+/// Tolerance for rounded relative ages when comparing against the watermark
+const WATERMARK_SLACK: chrono::Duration = chrono::Duration::minutes(10);
+
+/// Fetches a page with retries, taking a fresh user agent for each attempt, or serves it from the page cache
+pub async fn fetch_page(client: &Client, url: &str, http: &HttpOptions) -> Result<String, ScraperError> {
+    // Without validators nothing asks the server for a 304
+    fetch_page_if_changed(client, url, http, None).await.map(Option::unwrap_or_default)
+}
+
+/// Like `fetch_page`, but asks for the page only if it changed since `validators` last recorded
+/// it, returning `None` when the server answers 304 Not Modified
+pub async fn fetch_page_if_changed(
+    client: &Client,
+    url: &str,
+    http: &HttpOptions,
+    validators: Option<&PageValidators>,
+) -> Result<Option<String>, ScraperError> {
+    if let Some(body) = http.cache.as_ref().and_then(|cache| cache.get(url)) {
+        return Ok(Some(body));
+    }
+    
+    let body = with_retry(&RETRY_POLICY, &format!("Fetching {}", url), move |_| async move {
+        fetch_page_once(client, url, http, validators).await.map_err(|e| {
+            if is_retryable(&e, http) { RetryError::Retryable(e) } else { RetryError::Fatal(e) }
+        })
+    })
+    .await
+    .map_err(|e| {
+        log::warn!("Giving up on {}", url);
+        e.into_inner()
+    })?;
+    
+    if let (Some(cache), Some(body)) = (&http.cache, &body) {
+        cache.put(url, body);
+    }
+    Ok(body)
+}
+
+/// Fetches a listing page and parses its contracts table, refetching with backoff when
+/// the body doesn't parse, e.g. because it was cut off. A valid page without rows is not retried.
+pub async fn fetch_contracts(
+    client: &Client,
+    url: &str,
+    http: &HttpOptions,
+    selectors: &ContractSelectors,
+) -> Result<Vec<VerifiedContract>, ScraperError> {
+    fetch_contracts_if_changed(client, url, http, selectors, None).await.map(Option::unwrap_or_default)
+}
+
+/// Like `fetch_contracts`, but returns `None` without parsing anything when the server
+/// says the page is unchanged since `validators` last recorded it
+pub async fn fetch_contracts_if_changed(
+    client: &Client,
+    url: &str,
+    http: &HttpOptions,
+    selectors: &ContractSelectors,
+    validators: Option<&PageValidators>,
+) -> Result<Option<Vec<VerifiedContract>>, ScraperError> {
+    // Retries skip the cache, which may hold the very body that failed to parse
+    let uncached = HttpOptions { cache: None, ..http.clone() };
+    let uncached = &uncached;
+    with_retry(&RETRY_POLICY, &format!("Parsing {}", url), move |attempt| async move {
+        let options = if attempt == 1 { http } else { uncached };
+        let Some(html) = fetch_page_if_changed(client, url, options, validators).await.map_err(RetryError::Fatal)? else {
+            return Ok(None);
+        };
+        let contracts = parse_contracts_table(&html, selectors).map_err(|e| {
+            // The validators describe the body that failed, so refetch it in full
+            if let Some(validators) = validators {
+                validators.forget(url);
+            }
+            RetryError::Retryable(e)
+        })?;
+        if let Some(cache) = http.cache.as_ref().filter(|_| attempt > 1) {
+            cache.put(url, &html);
+        }
+        Ok(Some(contracts))
+    })
+    .await
+    .map_err(RetryError::into_inner)
+}
+
+/// Whether another attempt at the same URL could succeed. A non-HTML body or an error
+/// status outside `retry_statuses` would only come back the same; network errors might not.
+fn is_retryable(error: &ScraperError, http: &HttpOptions) -> bool {
+    match error {
+        ScraperError::UnexpectedContentType { .. } => false,
+        ScraperError::RateLimited { .. } => http.is_retryable(reqwest::StatusCode::TOO_MANY_REQUESTS),
+        ScraperError::Http(e) => e.status().is_none_or(|status| http.is_retryable(status)),
+        _ => true,
+    }
+}
+
+/// One attempt at a page; `None` when `validators` made it conditional and the server answered 304
+async fn fetch_page_once(
+    client: &Client,
+    url: &str,
+    http: &HttpOptions,
+    validators: Option<&PageValidators>,
+) -> Result<Option<String>, ScraperError> {
+    let mut request = http::with_user_agent(client.get(url), &http.user_agents);
+    if let Some(validators) = validators {
+        request = validators.apply(url, request);
+    }
+    let mut response = request.send().await?;
+    
+    if validators.is_some() && response.status() == reqwest::StatusCode::NOT_MODIFIED {
+        return Ok(None);
+    }
+    if response.status() == reqwest::StatusCode::TOO_MANY_REQUESTS {
+        return Err(ScraperError::RateLimited { retry_after: http::retry_after(&response) });
+    }
+    
+    let challenged = http::has_challenge_header(&response);
+    if !challenged {
+        response = response.error_for_status()?;
+        if let Some(content_type) = http::non_html_content_type(&response).filter(|_| http.check_content_type) {
+            log::warn!("Expected HTML from {} but got {}", url, content_type);
+            return Err(ScraperError::UnexpectedContentType { url: url.to_string(), content_type });
+        }
+    }
+    let validator = Validator::from_response(&response);
+    let body = response.text().await?;
+    
+    // A block would otherwise parse as an empty listing
+    if challenged || http::is_challenge_body(&body) {
+        log::warn!("Blocked by a challenge page at {}", url);
+        return Err(ScraperError::Challenge(url.to_string()));
+    }
+    
+    if let Some(validators) = validators {
+        validators.record(url, validator);
+    }
+    Ok(Some(body))
+}
+
+/// CSS selectors for the verified contracts listing, overridable when the explorer changes its markup
+#[derive(Debug, Clone)]
+pub struct ContractSelectors {
+    pub table: Selector,
+    pub row: Selector,
+    pub cell: Selector,
+    /// Header cells, used to find columns by name
+    pub header: Selector,
+    /// Link to the contract page inside the address cell
+    pub link: Selector,
+    /// Link to the creator's address page inside the creator cell
+    pub creator_link: Selector,
+    /// Source code blocks on a contract's code tab
+    pub source: Selector,
+    /// ABI block on a contract's code tab
+    pub abi: Selector,
+}
+
+impl ContractSelectors {
+    pub fn new(table: &str, row: &str, cell: &str) -> Result<Self, ScraperError> {
+        Ok(Self {
+            table: parse_selector("table", table)?,
+            row: parse_selector("row", row)?,
+            cell: parse_selector("cell", cell)?,
+            header: parse_selector("header", "thead th")?,
+            link: parse_selector("link", "a")?,
+            creator_link: parse_selector("creator link", "a[href*='/address/']")?,
+            source: parse_selector("source", "pre.js-sourcecopyarea")?,
+            abi: parse_selector("abi", "pre#js-copytextarea2")?,
+        })
+    }
+}
+
+/// Compiler version patterns from `--compiler`; a contract passes if it matches any of them
+#[derive(Debug, Clone, Default)]
+pub struct CompilerFilter {
+    patterns: Vec<CompilerPattern>,
+}
+
+#[derive(Debug, Clone)]
+enum CompilerPattern {
+    Prefix(String),
+    Regex(Regex),
+}
+
+impl CompilerFilter {
+    /// Treats patterns made only of version characters as prefixes and anything else as a regex
+    pub fn new(patterns: &[String]) -> Result<Self, ScraperError> {
+        let patterns = patterns
+            .iter()
+            .map(|pattern| {
+                if pattern.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '+' | '-')) {
+                    Ok(CompilerPattern::Prefix(pattern.clone()))
+                } else {
+                    Regex::new(pattern)
+                        .map(CompilerPattern::Regex)
+                        .map_err(|e| ScraperError::Config(format!("invalid compiler pattern {:?}: {}", pattern, e)))
+                }
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Self { patterns })
+    }
+    
+    pub fn is_active(&self) -> bool {
+        !self.patterns.is_empty()
+    }
+    
+    pub fn matches(&self, compiler_version: &str) -> bool {
+        !self.is_active()
+            || self.patterns.iter().any(|pattern| match pattern {
+                CompilerPattern::Prefix(prefix) => compiler_version.starts_with(prefix.as_str()),
+                CompilerPattern::Regex(regex) => regex.is_match(compiler_version),
+            })
+    }
+}
+
+fn parse_selector(name: &str, css: &str) -> Result<Selector, ScraperError> {
+    Selector::parse(css).map_err(|e| ScraperError::Config(format!("invalid {} selector {:?}: {}", name, css, e)))
+}
+
+/// Column positions in the contracts table
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Columns {
+    address: usize,
+    name: usize,
+    compiler: usize,
+    creator: Option<usize>,
+    age: Option<usize>,
+}
+
+impl Default for Columns {
+    /// The layout the scraper was written against, used when the table has no usable header
+    fn default() -> Self {
+        Self { address: 0, name: 1, compiler: 2, creator: Some(3), age: None }
+    }
+}
+
+impl Columns {
+    /// Maps columns by the words in their header text, falling back to the default positions for any that are missing
+    fn from_headers(headers: &[String]) -> Self {
+        let find = |keys: &[&str]| {
+            headers
+                .iter()
+                .position(|header| header.to_lowercase().split_whitespace().any(|word| keys.contains(&word)))
+        };
+        
+        let default = Self::default();
+        if headers.is_empty() {
+            return default;
+        }
+        Self {
+            address: find(&["address"]).unwrap_or(default.address),
+            name: find(&["name"]).unwrap_or(default.name),
+            compiler: find(&["compiler"]).or_else(|| find(&["version"])).unwrap_or(default.compiler),
+            creator: find(&["creator"]),
+            age: find(&["age", "verified", "date", "time"]),
+        }
+    }
+}
+
+pub fn parse_contracts_table(html: &str, selectors: &ContractSelectors) -> Result<Vec<VerifiedContract>, ScraperError> {
+    let document = Html::parse_document(html);
+    let now = Utc::now();
+    
+    let mut contracts = Vec::new();
+    
+    // Every real listing has the table, even when it has no rows
+    let Some(table) = document.select(&selectors.table).next() else {
+        return Err(ScraperError::Parse("contracts table not found".to_string()));
+    };
+    
+    let headers: Vec<String> = table
+        .select(&selectors.header)
+        .map(|header| header.text().collect::<String>().trim().to_string())
+        .collect();
+    let columns = Columns::from_headers(&headers);
+    
+    for (index, row) in table.select(&selectors.row).enumerate() {
+        let cells: Vec<_> = row.select(&selectors.cell).collect();
+        let text = |column: usize| cells.get(column).map(|cell| cell.text().collect::<String>().trim().to_string());
+        
+        // Skip just this row when a required column is missing, instead of misreading it
+        let (Some(address_cell), Some(name_cell), Some(compiler_cell)) =
+            (text(columns.address), text(columns.name), text(columns.compiler))
+        else {
+            log::warn!(
+                "Skipping row {}: {} cells, expected address, name and compiler in columns {}, {} and {}",
+                index + 1,
+                cells.len(),
+                columns.address + 1,
+                columns.name + 1,
+                columns.compiler + 1
+            );
+            continue;
+        };
+        // The cell text is often a truncated or named label, the link holds the full address
+        let creator_cell = columns
+            .creator
+            .and_then(|column| {
+                let linked = cells
+                    .get(column)?
+                    .select(&selectors.creator_link)
+                    .filter_map(|link| link.value().attr("href"))
+                    .find_map(|href| ADDRESS_REGEX.find(href))
+                    .map(|address| address.as_str().to_string())
+                    .filter(|address| is_valid_ethereum_address(address, None));
+                linked.or_else(|| text(column))
+            })
+            .unwrap_or_default();
+        
+        // Extract contract address from the link if available
+        let contract_address = if let Some(link) = cells[columns.address].select(&selectors.link).next() {
+            link.value().attr("href")
+                .and_then(|href| href.split('/').nth(2))
+                .unwrap_or(&address_cell)
+                .to_string()
+        } else {
+            address_cell
+        };
+        // The link's `#code` fragment stays, since stored dedup keys include it
+        let address = contract_address.split(['#', '?']).next().unwrap_or_default();
+        if !is_valid_ethereum_address(address, None) {
+            log::warn!("Skipping row {}: {:?} in column {} is not an address", index + 1, contract_address, columns.address + 1);
+            continue;
+        }
+        
+        // Without a recognised header, take the first cell that parses as a time
+        let verified_at = match columns.age {
+            Some(column) => text(column).and_then(|age| parse_verified_time(&age, now)),
+            None => cells
+                .iter()
+                .skip(1)
+                .find_map(|cell| parse_verified_time(cell.text().collect::<String>().trim(), now)),
+        }
+        .unwrap_or(now);
+        
+        contracts.push(VerifiedContract {
+            chain: String::new(),
+            contract_address,
+            contract_name: name_cell,
+            compiler_version: compiler_cell,
+            contract_creator: creator_cell,
+            source_code: SOURCE_PLACEHOLDER.to_string(),
+            source_hash: None,
+            source_length: None,
+            raw_source_code: None,
+            abi: None,
+            timestamp: verified_at.to_rfc3339(),
+            scraped_at: now.to_rfc3339(),
+        });
+    }
+    
+    Ok(contracts)
+}
+
+/// Parses a listing's verification time, either a relative age such as
+/// `3 mins ago` or `1 hr 5 mins ago`, or an absolute date
+pub fn parse_verified_time(text: &str, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+    if let Some(age) = text.strip_suffix("ago") {
+        let tokens: Vec<&str> = age.split_whitespace().collect();
+        if tokens.is_empty() || !tokens.len().is_multiple_of(2) {
+            return None;
+        }
+        
+        let mut total = chrono::Duration::zero();
+        for pair in tokens.chunks(2) {
+            let amount: i64 = pair[0].parse().ok()?;
+            let unit = match pair[1].trim_end_matches('s') {
+                "sec" | "second" => chrono::Duration::seconds(1),
+                "min" | "minute" => chrono::Duration::minutes(1),
+                "hr" | "hour" => chrono::Duration::hours(1),
+                "day" => chrono::Duration::days(1),
+                "week" | "wk" => chrono::Duration::weeks(1),
+                _ => return None,
+            };
+            total += unit * amount as i32;
+        }
+        return Some(now - total);
+    }
+    
+    if let Ok(time) = DateTime::parse_from_rfc3339(text) {
+        return Some(time.with_timezone(&Utc));
+    }
+    for format in ["%Y-%m-%d %H:%M:%S", "%Y-%m-%d %H:%M", "%m/%d/%Y %H:%M:%S"] {
+        if let Ok(time) = NaiveDateTime::parse_from_str(text, format) {
+            return Some(time.and_utc());
+        }
+    }
+    for format in ["%Y-%m-%d", "%m/%d/%Y"] {
+        if let Ok(date) = NaiveDate::parse_from_str(text, format) {
+            return date.and_hms_opt(0, 0, 0).map(|time| time.and_utc());
+        }
+    }
+    
+    None
+}
+
+/// Extracts the verified Solidity source from a contract's code tab.
+///
+/// Multi-file contracts render one code block per file, which are joined in page order.
+pub fn parse_source_code(html: &str, selectors: &ContractSelectors) -> Option<String> {
+    let document = Html::parse_document(html);
+    
+    let files: Vec<String> = document
+        .select(&selectors.source)
+        .map(|block| block.text().collect::<String>())
+        .filter(|source| !source.trim().is_empty())
+        .collect();
+    
+    if files.is_empty() {
+        None
+    } else {
+        Some(files.join("\n\n"))
+    }
+}
+
+/// Source code of one contract from the Etherscan V2 `getsourcecode` action, `None` when it isn't verified.
+///
+/// Multi-file sources arrive as standard JSON input, whose files are joined like the code page's blocks.
+pub async fn fetch_source_code_api(
+    client: &Client,
+    api_url: &str,
+    chain_id: u64,
+    api_key: &str,
+    address: &str,
+) -> Result<Option<String>, ScraperError> {
+    let params = [("module", "contract"), ("action", "getsourcecode"), ("address", address)];
+    let envelope: ApiEnvelope = etherscan::v2_request(client, api_url, chain_id, api_key, &params)
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await?;
+    
+    let result = match envelope.reply() {
+        ApiReply::Records(result) => result,
+        ApiReply::NoRecords => return Ok(None),
+        ApiReply::RateLimited => return Err(ScraperError::RateLimited { retry_after: None }),
+        ApiReply::Error(message) => return Err(ScraperError::Api(message)),
+    };
+    let source = result
+        .get(0)
+        .and_then(|entry| entry.get("SourceCode"))
+        .and_then(|source| source.as_str())
+        .ok_or_else(|| ScraperError::Parse("unexpected getsourcecode result".to_string()))?;
+    if source.trim().is_empty() {
+        return Ok(None);
+    }
+    
+    // Standard JSON input is wrapped in an extra pair of braces
+    let standard_json = source
+        .strip_prefix("{{")
+        .and_then(|inner| inner.strip_suffix("}}"))
+        .and_then(|inner| serde_json::from_str::<serde_json::Value>(&format!("{{{}}}", inner)).ok());
+    let files = standard_json.as_ref().and_then(|input| input.get("sources")).and_then(|sources| sources.as_object());
+    Ok(Some(match files {
+        Some(files) => files
+            .values()
+            .filter_map(|file| file.get("content")?.as_str())
+            .collect::<Vec<_>>()
+            .join("\n\n"),
+        None => source.to_string(),
+    }))
+}
+
+/// ABI of one contract from the Etherscan V2 `getabi` action, `None` when it isn't verified
+pub async fn fetch_abi_api(
+    client: &Client,
+    api_url: &str,
+    chain_id: u64,
+    api_key: &str,
+    address: &str,
+) -> Result<Option<String>, ScraperError> {
+    let params = [("module", "contract"), ("action", "getabi"), ("address", address)];
+    let envelope: ApiEnvelope = etherscan::v2_request(client, api_url, chain_id, api_key, &params)
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await?;
+    
+    match envelope.reply() {
+        ApiReply::Records(abi) => Ok(abi.as_str().and_then(valid_abi)),
+        ApiReply::NoRecords => Ok(None),
+        ApiReply::RateLimited => Err(ScraperError::RateLimited { retry_after: None }),
+        ApiReply::Error(message) if message.contains("not verified") => Ok(None),
+        ApiReply::Error(message) => Err(ScraperError::Api(message)),
+    }
+}
+
+/// The ABI block of a contract's code page, if it holds a JSON array
+pub fn parse_abi(html: &str, selectors: &ContractSelectors) -> Option<String> {
+    let document = Html::parse_document(html);
+    let abi = document.select(&selectors.abi).next()?.text().collect::<String>();
+    valid_abi(&abi)
+}
+
+fn valid_abi(abi: &str) -> Option<String> {
+    let abi = abi.trim();
+    serde_json::from_str::<serde_json::Value>(abi)
+        .is_ok_and(|value| value.is_array())
+        .then(|| abi.to_string())
+}
+
+/// Cuts `source` to at most `max_bytes` bytes on a character boundary and appends
+/// `TRUNCATION_MARKER`; a limit of 0 empties it
+fn truncate_source(source: &mut String, max_bytes: usize) {
+    if max_bytes == 0 {
+        source.clear();
+        return;
+    }
+    if source.len() <= max_bytes {
+        return;
+    }
+    let mut end = max_bytes;
+    while !source.is_char_boundary(end) {
+        end -= 1;
+    }
+    source.truncate(end);
+    source.push_str(TRUNCATION_MARKER);
+}
+
+/// Removes Solidity line and block comments while leaving string literals untouched.
+///
+/// Lines emptied by the removal are collapsed so license headers don't leave large gaps.
+pub fn strip_comments(source: &str) -> String {
+    let mut stripped = String::with_capacity(source.len());
+    let mut chars = source.chars().peekable();
+    
+    while let Some(c) = chars.next() {
+        match c {
+            '"' | '\'' => {
+                stripped.push(c);
+                while let Some(inner) = chars.next() {
+                    stripped.push(inner);
+                    if inner == '\\' {
+                        if let Some(escaped) = chars.next() {
+                            stripped.push(escaped);
+                        }
+                    } else if inner == c || inner == '\n' {
+                        break;
+                    }
+                }
+            }
+            '/' if chars.peek() == Some(&'/') => {
+                while chars.peek().is_some_and(|&next| next != '\n') {
+                    chars.next();
+                }
+            }
+            '/' if chars.peek() == Some(&'*') => {
+                chars.next();
+                let mut previous = '\0';
+                for inner in chars.by_ref() {
+                    if previous == '*' && inner == '/' {
+                        break;
+                    }
+                    previous = inner;
+                }
+            }
+            _ => stripped.push(c),
+        }
+    }
+    
+    let mut result = String::with_capacity(stripped.len());
+    let mut previous_blank = true;
+    for line in stripped.lines() {
+        let line = line.trim_end();
+        if line.is_empty() {
+            if previous_blank {
+                continue;
+            }
+            previous_blank = true;
+        } else {
+            previous_blank = false;
+        }
+        result.push_str(line);
+        result.push('\n');
+    }
+    
+    result.trim_end().to_string()
+}
+
+/// Hex Keccak-256 of a contract's source code
+fn source_hash(source: &str) -> String {
+    let mut hasher = Keccak::v256();
+    let mut hash = [0u8; 32];
+    hasher.update(source.as_bytes());
+    hasher.finalize(&mut hash);
+    format!("0x{}", hash.iter().map(|byte| format!("{:02x}", byte)).collect::<String>())
+}
+
+fn page_url(base_url: &str, page: u32) -> String {
+    format!("{}?p={}", base_url, page)
+}
+
+/// Verification time of a contract, or `None` when the listing gave none and it fell back to the scrape time
+fn contract_time(contract: &VerifiedContract) -> Option<DateTime<Utc>> {
+    if contract.timestamp == contract.scraped_at {
+        return None;
+    }
+    DateTime::parse_from_rfc3339(&contract.timestamp)
+        .ok()
+        .map(|time| time.with_timezone(&Utc))
+}
+
+fn is_before_watermark(contract: &VerifiedContract, watermark: Option<DateTime<Utc>>) -> bool {
+    match (contract_time(contract), watermark) {
+        // Relative ages are rounded, so allow some slack; processed contracts are still skipped by address
+        (Some(time), Some(watermark)) => time < watermark - WATERMARK_SLACK,
+        _ => false,
+    }
+}
+
+fn parse_chain_name(name: &str) -> Result<String, String> {
+    if CHAINS.iter().any(|(chain, _, _)| *chain == name) {
+        Ok(name.to_string())
+    } else {
+        let names: Vec<&str> = CHAINS.iter().map(|(chain, _, _)| *chain).collect();
+        Err(format!("unknown chain, expected one of {}", names.join(", ")))
+    }
+}
+
+fn parse_schedule(expression: &str) -> Result<Schedule, String> {
+    Schedule::from_str(expression).map_err(|e| format!("invalid cron expression: {}", e))
+}
+
+fn parse_probability(value: &str) -> Result<f64, String> {
+    match value.parse::<f64>() {
+        Ok(rate) if rate > 0.0 && rate < 1.0 => Ok(rate),
+        _ => Err("expected a probability between 0 and 1, exclusive".to_string()),
+    }
+}
+
+/// An explorer to poll and the files its contracts are tracked in
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Chain {
+    pub name: String,
+    pub listing_url: String,
+    /// Origin of the explorer, used to build contract code page URLs
+    pub explorer_url: String,
+    /// Etherscan V2 chain id, unknown for explorers outside `CHAINS`
+    pub chain_id: Option<u64>,
+    pub state_file: PathBuf,
+    pub output_file: PathBuf,
+}
+
+impl Chain {
+    /// Resolves `--chain` names, or the single `--url` target with the unprefixed files when none are given
+    pub fn from_args(args: &Args) -> Vec<Chain> {
+        let in_out_dir = |file: &str| args.out_dir.as_deref().unwrap_or(Path::new("")).join(file);
+        if args.chains.is_empty() {
+            let explorer_url = reqwest::Url::parse(&args.url)
+                .map(|url| url.origin().ascii_serialization())
+                .unwrap_or_else(|_| args.url.clone());
+            let known = CHAINS.iter().find(|(_, url, _)| *url == explorer_url);
+            let name = known
+                .map(|(chain, _, _)| chain.to_string())
+                .unwrap_or_else(|| explorer_url.split("://").last().unwrap_or_default().to_string());
+            return vec![Chain {
+                name,
+                listing_url: args.url.clone(),
+                chain_id: known.map(|(_, _, chain_id)| *chain_id),
+                explorer_url,
+                state_file: in_out_dir(STATE_FILE),
+                output_file: in_out_dir(OUTPUT_FILE),
+            }];
+        }
+        
+        let mut chains: Vec<Chain> = Vec::new();
+        for name in &args.chains {
+            if chains.iter().any(|chain| &chain.name == name) {
+                continue;
+            }
+            if let Some((_, explorer_url, chain_id)) = CHAINS.iter().find(|(chain, _, _)| chain == name) {
+                chains.push(Chain {
+                    name: name.clone(),
+                    listing_url: format!("{}/contractsVerified", explorer_url),
+                    explorer_url: explorer_url.to_string(),
+                    chain_id: Some(*chain_id),
+                    state_file: in_out_dir(&format!("scraper_state_{}.json", name)),
+                    output_file: in_out_dir(&format!("verified_contracts_{}.json", name)),
+                });
+            }
+        }
+        chains
+    }
+}
+
+/// One chain's state within the monitor
+struct ChainMonitor {
+    chain: Chain,
+    state: ScraperState,
+    /// Replaces `state.processed_contracts` with `--bloom-dedup`
+    bloom: Option<BloomDedup>,
+    backfill_pending: bool,
+    /// Listing pages that failed in the current cycle
+    failed_pages: usize,
+}
+
+impl ChainMonitor {
+    fn bloom_file(&self) -> PathBuf {
+        self.chain.state_file.with_extension("bloom")
+    }
+    
+    fn is_processed(&self, address: &str) -> bool {
+        match &self.bloom {
+            Some(bloom) => bloom.contains(address),
+            None => self.state.processed_contracts.contains(address),
+        }
+    }
+    
+    fn mark_processed<'a>(&mut self, addresses: impl IntoIterator<Item = &'a String>) {
+        match &mut self.bloom {
+            Some(bloom) => addresses.into_iter().for_each(|address| bloom.insert(address)),
+            None => self.state.processed_contracts.extend(addresses.into_iter().cloned()),
+        }
+    }
+    
+    /// Saves the state before the bloom filter, so the filter never marks contracts that the
+    /// startup truncation would cut from the output. A crash between the two leaves contracts
+    /// written but unmarked, which at worst writes them again.
+    fn save(&self) -> Result<(), ScraperError> {
+        save_state(&self.state, &self.chain.state_file)?;
+        if let Some(bloom) = &self.bloom {
+            bloom.save(&self.bloom_file())?;
+        }
+        Ok(())
+    }
+}
+
+/// Settings and connections shared by every chain in a run of the monitor
+struct ContractScraper {
+    client: Client,
+    args: Args,
+    http: HttpOptions,
+    uploader: Option<S3Uploader>,
+    #[cfg(feature = "postgres")]
+    postgres: Option<PostgresSink>,
+    selectors: ContractSelectors,
+    compiler_filter: CompilerFilter,
+    observer: Option<ContractObserver>,
+    /// Validators of the polled listing pages, so an unchanged page costs a 304
+    validators: PageValidators,
+}
+
+impl ContractScraper {
+    /// Fetches the first `backfill_pages` listing pages concurrently, skipping pages that fail
+    async fn backfill(&self, monitor: &mut ChainMonitor) -> Vec<VerifiedContract> {
+        let pages = self.args.backfill_pages;
+        let concurrency = self.args.backfill_concurrency;
+        log::info!("Backfilling {} {} pages with concurrency {}", pages, monitor.chain.name, concurrency);
+        
+        let (client, http, base_url, selectors) = (&self.client, &self.http, &monitor.chain.listing_url, &self.selectors);
+        let results: Vec<_> = stream::iter(1..=pages)
+            .map(|page| async move {
+                let url = page_url(base_url, page);
+                (page, fetch_contracts(client, &url, http, selectors).await)
+            })
+            .buffer_unordered(concurrency.max(1))
+            .collect()
+            .await;
+        
+        let mut all_contracts = Vec::new();
+        for (page, contracts) in results {
+            match contracts {
+                Ok(contracts) => {
+                    log::info!("Backfill page {} yielded {} contracts", page, contracts.len());
+                    all_contracts.extend(contracts);
+                }
+                Err(e) => {
+                    log::error!("Backfill page {} failed: {}", page, e);
+                    monitor.failed_pages += 1;
+                }
+            }
+        }
+        
+        all_contracts
+    }
+    
+    /// Fills in each contract's `source_code`, and `abi` with `--fetch-abi`, from the Etherscan V2
+    /// API when there is a key and the chain id is known, or else from its code page, keeping
+    /// the placeholder on failure
+    async fn fetch_source_code(&self, chain: &Chain, contracts: &mut [VerifiedContract]) {
+        let api = self.args.api_key.as_deref().zip(chain.chain_id);
+        for (i, contract) in contracts.iter_mut().enumerate() {
+            if i > 0 {
+                sleep(self.http.request_delay).await;
+            }
+            
+            let address = contract.contract_address.trim_end_matches("#code");
+            let (source, abi) = match api {
+                Some((api_key, chain_id)) => {
+                    let source = fetch_source_code_api(&self.client, ETHERSCAN_V2_API_URL, chain_id, api_key, address).await;
+                    let abi = if self.args.fetch_abi {
+                        sleep(self.http.request_delay).await;
+                        fetch_abi_api(&self.client, ETHERSCAN_V2_API_URL, chain_id, api_key, address).await
+                    } else {
+                        Ok(None)
+                    };
+                    (source, abi)
+                }
+                None => {
+                    let url = format!("{}/address/{}#code", chain.explorer_url, address);
+                    match fetch_page(&self.client, &url, &self.http).await {
+                        Ok(html) => (
+                            Ok(parse_source_code(&html, &self.selectors)),
+                            Ok(parse_abi(&html, &self.selectors).filter(|_| self.args.fetch_abi)),
+                        ),
+                        Err(e) => (Err(e), Ok(None)),
+                    }
+                }
+            };
+            
+            if self.args.fetch_abi {
+                match abi {
+                    Ok(Some(abi)) => contract.abi = Some(abi),
+                    Ok(None) => log::warn!("No ABI found for {}", address),
+                    Err(e) => log::warn!("Failed to fetch the ABI of {}: {}", address, e),
+                }
+            }
+            
+            match source {
+                Ok(Some(source)) if self.args.strip_source_comments => {
+                    contract.source_code = strip_comments(&source);
+                    contract.source_hash = Some(source_hash(&contract.source_code));
+                    if self.args.keep_raw_source {
+                        contract.raw_source_code = Some(source);
+                    }
+                }
+                Ok(Some(source)) => {
+                    contract.source_hash = Some(source_hash(&source));
+                    contract.source_code = source;
+                }
+                Ok(None) => log::warn!("No source code found for {}", address),
+                Err(e) => log::warn!("Failed to fetch source code for {}: {}", address, e),
+            }
+            
+            if let Some(max_bytes) = self.args.max_source_bytes.filter(|_| contract.source_hash.is_some()) {
+                contract.source_length = Some(contract.source_code.len());
+                truncate_source(&mut contract.source_code, max_bytes);
+                if max_bytes == 0 {
+                    contract.raw_source_code = None;
+                } else if let Some(raw) = &mut contract.raw_source_code {
+                    truncate_source(raw, max_bytes);
+                }
+            }
+        }
+    }
+    
+    /// Walks listing pages in order until one is empty, holds nothing new, or `max_pages` is reached
+    async fn scrape_pages(&self, monitor: &mut ChainMonitor) -> Result<usize, ScraperError> {
+        let mut total_new = 0;
+        
+        for page in 1..=self.args.max_pages.max(1) {
+            let url = page_url(&monitor.chain.listing_url, page);
+            log::info!("Fetching verified contracts from: {}", url);
+            
+            let contracts = match fetch_contracts_if_changed(&self.client, &url, &self.http, &self.selectors, Some(&self.validators)).await {
+                Ok(Some(contracts)) => contracts,
+                Ok(None) => {
+                    log::info!("Page {} is unchanged since the last cycle, stopping", page);
+                    break;
+                }
+                Err(e @ ScraperError::Parse(_)) => {
+                    log::error!("Failed to parse contracts table: {}", e);
+                    break;
+                }
+                Err(e) => {
+                    log::error!("Failed to fetch page: {}", e);
+                    monitor.failed_pages += 1;
+                    break;
+                }
+            };
+            
+            if contracts.is_empty() {
+                log::info!("Page {} has no contracts, stopping", page);
+                break;
+            }
+            
+            // The listing is newest first, so once a row predates the watermark every
+            // later row and page has already been polled
+            let row_count = contracts.len();
+            let watermark = monitor.state.watermark;
+            let contracts: Vec<_> = contracts
+                .into_iter()
+                .take_while(|contract| !is_before_watermark(contract, watermark))
+                .collect();
+            let reached_watermark = contracts.len() < row_count;
+            let filtered_out = contracts
+                .iter()
+                .filter(|contract| {
+                    !monitor.is_processed(&contract.contract_address) && !self.is_wanted(contract)
+                })
+                .count();
+            
+            let new_count = self.record_new_contracts(monitor, contracts).await?;
+            total_new += new_count;
+            
+            if reached_watermark {
+                log::info!("Page {} reached the last seen contract timestamp, stopping", page);
+                break;
+            }
+            
+            // Filtered contracts stay unprocessed, so they don't mean the page was seen before
+            if new_count == 0 && filtered_out == 0 {
+                log::info!("Page {} holds only processed contracts, stopping", page);
+                break;
+            }
+        }
+        
+        Ok(total_new)
+    }
+    
+    /// Whether a contract passes both the compiler and the name filter
+    fn is_wanted(&self, contract: &VerifiedContract) -> bool {
+        self.compiler_filter.matches(&contract.compiler_version)
+            && self.args.name_filter.as_ref().is_none_or(|filter| filter.is_match(&contract.contract_name))
+    }
+    
+    /// Appends contracts not seen before that pass the compiler and name filters to the output and
+    /// persists the state, returning how many were new
+    async fn record_new_contracts(&self, monitor: &mut ChainMonitor, contracts: Vec<VerifiedContract>) -> Result<usize, ScraperError> {
+        // The batch set also drops duplicates within the batch, e.g. a contract that
+        // shifted onto the next page between two backfill requests
+        let mut batch = HashSet::new();
+        let mut new_contracts: Vec<_> = contracts
+            .into_iter()
+            .filter(|contract| {
+                !monitor.is_processed(&contract.contract_address)
+                    && self.is_wanted(contract)
+                    && batch.insert(contract.contract_address.clone())
+            })
+            .map(|contract| VerifiedContract { chain: monitor.chain.name.clone(), ..contract })
+            .collect();
+        
+        if new_contracts.is_empty() {
+            log::info!("No new contracts found");
+            return Ok(0);
+        }
+        
+        log::info!("Found {} new {} contracts", new_contracts.len(), monitor.chain.name);
+        
+        for contract in &new_contracts {
+            log::info!("New contract: {} - {}", contract.contract_address, contract.contract_name);
+        }
+        
+        self.fetch_source_code(&monitor.chain, &mut new_contracts).await;
+        let new_count = new_contracts.len();
+        
+        // Duplicates are still marked processed below so their source isn't fetched again
+        let (unique_contracts, duplicates): (Vec<_>, Vec<_>) = if self.args.dedup_source {
+            let mut batch_hashes = HashSet::new();
+            new_contracts.into_iter().partition(|contract| match &contract.source_hash {
+                Some(hash) => !monitor.state.source_hashes.contains(hash) && batch_hashes.insert(hash.clone()),
+                None => true,
+            })
+        } else {
+            (new_contracts, Vec::new())
+        };
+        if !duplicates.is_empty() {
+            log::info!("Skipping {} contracts with source identical to one already written", duplicates.len());
+        }
+        let new_contracts = unique_contracts;
+        
+        // No awaits from here until the state is saved, so an interrupt never
+        // marks contracts as processed without them reaching the output
+        if self.args.json_array {
+            monitor.state.output_records = Some(append_to_json_array(&monitor.chain.output_file, &new_contracts)?);
+            monitor.state.output_len = None;
+        } else {
+            append_to_output(&monitor.chain.output_file, &new_contracts)?;
+            monitor.state.output_len = Some(std::fs::metadata(&monitor.chain.output_file)?.len());
+            monitor.state.output_records = None;
+        }
+        if let Some(path) = &self.args.sqlite_out {
+            save_to_sqlite(path, &new_contracts)?;
+        }
+        
+        monitor.mark_processed(new_contracts.iter().chain(&duplicates).map(|contract| &contract.contract_address));
+        monitor.state.source_hashes.extend(new_contracts.iter().filter_map(|contract| contract.source_hash.clone()));
+        // Skipped contracts may be older than the newest match, so a filtered run leaves
+        // the watermark alone for a later run with a different filter to reach them
+        if !self.compiler_filter.is_active() && self.args.name_filter.is_none() {
+            if let Some(newest) = new_contracts.iter().filter_map(contract_time).max() {
+                monitor.state.watermark = monitor.state.watermark.max(Some(newest));
+            }
+        }
+        monitor.save()?;
+        
+        if let Some(observer) = &self.observer {
+            for contract in &new_contracts {
+                observer(contract);
+            }
+        }
+        if let Some(uploader) = &self.uploader {
+            uploader.upload_batch(&new_contracts).await;
+        }
+        
+        // Like the S3 upload this is best effort, the local output already holds the batch
+        #[cfg(feature = "postgres")]
+        if let Some(postgres) = self.postgres.as_ref().filter(|_| !new_contracts.is_empty()) {
+            if let Err(e) = postgres.upsert(&new_contracts).await {
+                log::error!("Failed to upsert {} contracts into Postgres: {}", new_contracts.len(), e);
+            }
+        }
+        
+        if let Some(webhook) = self.args.webhook.as_ref().filter(|_| !new_contracts.is_empty()) {
+            let payload = webhook_payload(self.args.webhook_format, &monitor.chain.name, &new_contracts);
+            if let Err(e) = post_webhook(&self.client, webhook, &payload).await {
+                log::error!("Failed to notify webhook about {} new contracts: {}", new_contracts.len(), e);
+            }
+        }
+        
+        Ok(new_count)
+    }
+}
+
+pub fn load_state(path: &Path) -> Result<ScraperState, ScraperError> {
+    if path.exists() {
+        let file = File::open(path)?;
+        let reader = BufReader::new(file);
+        Ok(serde_json::from_reader(reader)?)
+    } else {
+        Ok(ScraperState {
+            processed_contracts: HashSet::new(),
+            watermark: None,
+            source_hashes: HashSet::new(),
+            output_len: None,
+            output_records: None,
+        })
+    }
+}
+
+pub fn save_state(state: &ScraperState, path: &Path) -> Result<(), ScraperError> {
+    write_atomic(path, |writer| Ok(serde_json::to_writer_pretty(writer, state)?))
+}
+
+pub fn append_to_output(path: &Path, contracts: &[VerifiedContract]) -> Result<(), ScraperError> {
+    let file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)?;
+    
+    let mut writer = BufWriter::new(file);
+    
+    for contract in contracts {
+        serde_json::to_writer(&mut writer, contract)?;
+        writer.write_all(b"\n")?;
+    }
+    
+    Ok(())
+}
+
+/// Cuts the output back to the `committed_len` bytes the state last recorded, dropping
+/// contracts, or a partial line, appended after the state was saved. Those contracts are
+/// not marked processed, so the next scrape writes them again. Returns the bytes dropped.
+pub fn truncate_uncommitted(path: &Path, committed_len: Option<u64>) -> Result<u64, ScraperError> {
+    let Some(committed_len) = committed_len else {
+        return Ok(0);
+    };
+    let file = match OpenOptions::new().write(true).open(path) {
+        Ok(file) => file,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(0),
+        Err(e) => return Err(e.into()),
+    };
+    let len = file.metadata()?.len();
+    if len <= committed_len {
+        return Ok(0);
+    }
+    file.set_len(committed_len)?;
+    file.sync_all()?;
+    Ok(len - committed_len)
+}
+
+/// Adds contracts to the JSON array in `path`, converting an NDJSON file written
+/// without `--json-array`. The array is rewritten to a temporary file that then
+/// replaces the output, so the file stays valid if the process is killed mid-write.
+/// Returns how many records the array now holds.
+pub fn append_to_json_array(path: &Path, contracts: &[VerifiedContract]) -> Result<usize, ScraperError> {
+    let mut records = read_json_records(path)?;
+    for contract in contracts {
+        records.push(serde_json::to_value(contract)?);
+    }
+    
+    write_json_array(path, &records)?;
+    Ok(records.len())
+}
+
+/// Cuts the `--json-array` output back to the `committed_records` the state last recorded,
+/// like `truncate_uncommitted` does for line-per-contract output. Returns the records dropped.
+pub fn truncate_uncommitted_records(path: &Path, committed_records: Option<usize>) -> Result<usize, ScraperError> {
+    let Some(committed_records) = committed_records else {
+        return Ok(0);
+    };
+    let mut records = read_json_records(path)?;
+    if records.len() <= committed_records {
+        return Ok(0);
+    }
+    let dropped = records.len() - committed_records;
+    records.truncate(committed_records);
+    write_json_array(path, &records)?;
+    Ok(dropped)
+}
+
+/// Reads the records of a JSON array or NDJSON output, or none if it doesn't exist yet
+fn read_json_records(path: &Path) -> Result<Vec<serde_json::Value>, ScraperError> {
+    match std::fs::read_to_string(path) {
+        Ok(text) if text.trim_start().starts_with('[') => Ok(serde_json::from_str(&text)?),
+        Ok(text) => Ok(text
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(serde_json::from_str)
+            .collect::<Result<_, _>>()?),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Vec::new()),
+        Err(e) => Err(e.into()),
+    }
+}
+
+fn write_json_array(path: &Path, records: &[serde_json::Value]) -> Result<(), ScraperError> {
+    write_atomic(path, |writer| {
+        serde_json::to_writer_pretty(&mut *writer, records)?;
+        Ok(writer.write_all(b"\n")?)
+    })
+}
+
+/// Upserts contracts into a `contracts` table keyed on the chain and address, returning how many were new
+pub fn save_to_sqlite(path: &Path, contracts: &[VerifiedContract]) -> Result<usize, ScraperError> {
+    let mut conn = rusqlite::Connection::open(path)?;
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS contracts (
+            chain TEXT NOT NULL,
+            contract_address TEXT NOT NULL,
+            contract_name TEXT NOT NULL,
+            compiler_version TEXT NOT NULL,
+            contract_creator TEXT NOT NULL,
+            source_code TEXT NOT NULL,
+            raw_source_code TEXT,
+            source_hash TEXT,
+            timestamp TEXT NOT NULL,
+            scraped_at TEXT NOT NULL,
+            PRIMARY KEY (chain, contract_address)
+        )",
+        [],
+    )?;
+    
+    let tx = conn.transaction()?;
+    let mut inserted = 0;
+    {
+        let mut insert = tx.prepare(
+            "INSERT OR IGNORE INTO contracts
+                (chain, contract_address, contract_name, compiler_version, contract_creator, source_code, raw_source_code, source_hash, timestamp, scraped_at)
+                VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+        )?;
+        for contract in contracts {
+            inserted += insert.execute((
+                &contract.chain,
+                &contract.contract_address,
+                &contract.contract_name,
+                &contract.compiler_version,
+                &contract.contract_creator,
+                &contract.source_code,
+                &contract.raw_source_code,
+                &contract.source_hash,
+                &contract.timestamp,
+                &contract.scraped_at,
+            ))?;
+        }
+    }
+    tx.commit()?;
+    
+    log::info!("Saved {} of {} contracts to {}", inserted, contracts.len(), path.display());
+    Ok(inserted)
+}
+
+async fn send_alert(client: &Client, webhook: &str, message: &str, count: usize, average: f64) -> Result<(), ScraperError> {
+    let payload = serde_json::json!({
+        "message": message,
+        "new_contracts": count,
+        "rolling_average": average,
+        "timestamp": chrono::Utc::now().to_rfc3339(),
+    });
+
+    let response = client
+        .post(webhook)
+        .json(&payload)
+        .send()
+        .await?;
+
+    response.error_for_status()?;
+    Ok(())
+}
+
+/// Contracts listed by name in a chat message before the rest are summarised
+const WEBHOOK_MESSAGE_CONTRACTS: usize = 10;
+
+fn webhook_payload(format: WebhookFormat, chain: &str, contracts: &[VerifiedContract]) -> serde_json::Value {
+    if format == WebhookFormat::Json {
+        let contracts: Vec<_> = contracts
+            .iter()
+            .map(|contract| serde_json::json!({ "address": contract.contract_address, "name": contract.contract_name }))
+            .collect();
+        return serde_json::json!({ "chain": chain, "count": contracts.len(), "contracts": contracts });
+    }
+    
+    // Kept short to stay within chat message limits
+    let mut message = format!("{} new verified contracts on {}", contracts.len(), chain);
+    for contract in contracts.iter().take(WEBHOOK_MESSAGE_CONTRACTS) {
+        message.push_str(&format!("\n- {} `{}`", contract.contract_name, contract.contract_address.trim_end_matches("#code")));
+    }
+    if contracts.len() > WEBHOOK_MESSAGE_CONTRACTS {
+        message.push_str(&format!("\n…and {} more", contracts.len() - WEBHOOK_MESSAGE_CONTRACTS));
+    }
+    
+    match format {
+        WebhookFormat::Discord => serde_json::json!({ "content": message }),
+        _ => serde_json::json!({ "text": message }),
+    }
+}
+
+async fn post_webhook(client: &Client, webhook: &str, payload: &serde_json::Value) -> Result<(), ScraperError> {
+    client.post(webhook).json(payload).send().await?.error_for_status()?;
+    Ok(())
+}
+
+/// Time from `now` until the next cycle: the next `--cron` time, or `--interval` seconds.
+/// `None` when the schedule has no times left.
+fn next_cycle_wait(args: &Args, now: DateTime<Utc>) -> Option<Duration> {
+    match &args.cron {
+        Some(schedule) => schedule.after(&now).next().map(|next| (next - now).to_std().unwrap_or_default()),
+        None => Some(Duration::from_secs(args.interval)),
+    }
+}
+
+/// Runs the monitoring loop until interrupted with Ctrl-C, or for one cycle with `--once`
+pub async fn run(args: Args, http: HttpOptions) -> Result<RunOutcome> {
+    run_with_observer(args, http, None).await
+}
+
+/// Like `run`, calling `observer` with every new contract as it is recorded
+pub async fn run_with_observer(args: Args, http: HttpOptions, observer: Option<ContractObserver>) -> Result<RunOutcome> {
+    let client = build_client(&http).context("Failed to create HTTP client")?;
+    if let Some(dir) = &args.out_dir {
+        std::fs::create_dir_all(dir).with_context(|| format!("Failed to create output directory {}", dir.display()))?;
+    }
+    
+    let mut chains = Vec::new();
+    for chain in Chain::from_args(&args) {
+        let state = load_state(&chain.state_file)
+            .with_context(|| format!("Failed to load state file {}", chain.state_file.display()))?;
+        if args.json_array {
+            let dropped = truncate_uncommitted_records(&chain.output_file, state.output_records)
+                .with_context(|| format!("Failed to recover output file {}", chain.output_file.display()))?;
+            if dropped > 0 {
+                log::warn!("Dropped {} records of {} written after the last saved state", dropped, chain.output_file.display());
+            }
+        } else {
+            let dropped = truncate_uncommitted(&chain.output_file, state.output_len)
+                .with_context(|| format!("Failed to recover output file {}", chain.output_file.display()))?;
+            if dropped > 0 {
+                log::warn!("Dropped {} bytes of {} written after the last saved state", dropped, chain.output_file.display());
+            }
+        }
+        let mut monitor = ChainMonitor { chain, state, bloom: None, backfill_pending: args.backfill_pages > 1, failed_pages: 0 };
+        if args.bloom_dedup {
+            let path = monitor.bloom_file();
+            let bloom = BloomDedup::load(&path, args.bloom_fp_rate)
+                .with_context(|| format!("Failed to load bloom filter {}", path.display()))?;
+            monitor.bloom = Some(bloom);
+            // Move addresses from an exact-set state into the filter, shrinking the state file
+            let processed = std::mem::take(&mut monitor.state.processed_contracts);
+            if !processed.is_empty() {
+                log::info!("Moving {} processed {} contracts into {}", processed.len(), monitor.chain.name, path.display());
+                monitor.mark_processed(&processed);
+            }
+        }
+        chains.push(monitor);
+    }
+    
+    // Set up the uploader and database before polling so bad credentials fail fast
+    let uploader = match &args.s3_bucket {
+        Some(bucket) => Some(
+            S3Uploader::new(bucket, &args.s3_region, args.s3_endpoint.as_deref(), &args.s3_prefix)
+                .context("Failed to configure S3 upload")?,
+        ),
+        None => None,
+    };
+    #[cfg(feature = "postgres")]
+    let postgres = match &args.postgres_url {
+        Some(url) => Some(PostgresSink::connect(url).await.context("Failed to connect to Postgres")?),
+        None => None,
+    };
+    
+    let mut monitor = RateMonitor::new(args.alert_window, args.alert_spike_factor, args.alert_zero_intervals);
+    let selectors = ContractSelectors::new(&args.table_selector, &args.row_selector, &args.cell_selector)
+        .context("Invalid contracts table selectors")?;
+    let compiler_filter = CompilerFilter::new(&args.compilers).context("Invalid --compiler pattern")?;
+    
+    let scraper = ContractScraper {
+        client,
+        args,
+        http,
+        uploader,
+        #[cfg(feature = "postgres")]
+        postgres,
+        selectors,
+        compiler_filter,
+        observer,
+        validators: PageValidators::default(),
+    };
+    
+    let shutdown = tokio::signal::ctrl_c();
+    tokio::pin!(shutdown);
+    
+    loop {
+        // Failed fetches count as zero so a broken scraper trips the stall alert
+        let poll = async {
+            let mut new_count = 0;
+            for chain in chains.iter_mut() {
+                chain.failed_pages = 0;
+                new_count += if chain.backfill_pending {
+                    chain.backfill_pending = false;
+                    let contracts = scraper.backfill(chain).await;
+                    scraper.record_new_contracts(chain, contracts).await?
+                } else {
+                    scraper.scrape_pages(chain).await?
+                };
+            }
+            Ok::<_, ScraperError>(new_count)
+        };
+        
+        let new_count = tokio::select! {
+            new_count = poll => new_count?,
+            _ = &mut shutdown => break,
+        };
+        
+        let average = monitor.rolling_average();
+        if let Some(message) = monitor.record(new_count) {
+            log::error!("{}", message);
+            
+            if let Some(webhook) = &scraper.args.alert_webhook {
+                if let Err(e) = send_alert(&scraper.client, webhook, &message, new_count, average).await {
+                    log::error!("Failed to deliver alert: {}", e);
+                }
+            }
+        }
+        
+        if scraper.args.once {
+            let failed_pages: usize = chains.iter().map(|chain| chain.failed_pages).sum();
+            if failed_pages > 0 {
+                log::error!("{} listing page(s) failed to load", failed_pages);
+            }
+            return Ok(RunOutcome::from_counts(new_count, failed_pages));
+        }
+        
+        // Rate limiting - wait before next scrape
+        let Some(wait) = next_cycle_wait(&scraper.args, Utc::now()) else {
+            log::warn!("The cron schedule has no further times, stopping");
+            break;
+        };
+        log::info!("Waiting {} seconds before next scrape...", wait.as_secs());
+        tokio::select! {
+            _ = sleep(wait) => {}
+            _ = &mut shutdown => break,
+        }
+    }
+    
+    log::info!("Interrupted, saving state before exiting");
+    for chain in &chains {
+        chain.save().with_context(|| format!("Failed to save state file {}", chain.chain.state_file.display()))?;
+    }
+    Ok(RunOutcome::Success)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn long_source_is_truncated_on_a_char_boundary() {
+        let mut source = "contract Café {}".to_string();
+        truncate_source(&mut source, 13);
+        assert_eq!(source, "contract Caf...[truncated]");
+        
+        let mut source = "contract A {}".to_string();
+        truncate_source(&mut source, 100);
+        assert_eq!(source, "contract A {}");
+        truncate_source(&mut source, 0);
+        assert_eq!(source, "");
+    }
+
+    #[test]
+    fn strips_line_and_block_comments() {
+        let source = "// SPDX-License-Identifier: MIT\npragma solidity ^0.8.0; // version\n/* block\n   comment */\ncontract A {}";
+        assert_eq!(strip_comments(source), "pragma solidity ^0.8.0;\n\ncontract A {}");
+    }
+
+    #[test]
+    fn keeps_comment_markers_inside_strings() {
+        let source = r#"string a = "http://example.com"; string b = '/* not a comment */';"#;
+        assert_eq!(strip_comments(source), source);
+    }
+
+    #[test]
+    fn handles_escaped_quotes_in_strings() {
+        let source = r#"string a = "say \"hi\" // still string"; // comment"#;
+        assert_eq!(strip_comments(source), r#"string a = "say \"hi\" // still string";"#);
+    }
+
+    #[test]
+    fn keeps_division_operator() {
+        assert_eq!(strip_comments("uint x = a / b;"), "uint x = a / b;");
+    }
+
+    #[test]
+    fn block_comments_do_not_nest() {
+        assert_eq!(strip_comments("a /* /* inner */ b */"), "a  b */");
+    }
+
+    #[test]
+    fn unterminated_block_comment_drops_remainder() {
+        assert_eq!(strip_comments("uint x;\n/* never closed\nuint y;"), "uint x;");
+    }
+
+    #[test]
+    fn collapses_blank_lines_left_by_comments() {
+        let source = "a;\n\n// one\n// two\n\nb;";
+        assert_eq!(strip_comments(source), "a;\n\nb;");
+    }
+
+    #[test]
+    fn parses_relative_ages() {
+        let now = DateTime::parse_from_rfc3339("2024-05-01T12:00:00Z").unwrap().with_timezone(&Utc);
+        assert_eq!(parse_verified_time("3 mins ago", now), Some(now - chrono::Duration::minutes(3)));
+        assert_eq!(parse_verified_time("1 hr 5 mins ago", now), Some(now - chrono::Duration::minutes(65)));
+        assert_eq!(parse_verified_time("2 days 3 hrs ago", now), Some(now - chrono::Duration::hours(51)));
+        assert_eq!(parse_verified_time("45 secs ago", now), Some(now - chrono::Duration::seconds(45)));
+    }
+
+    #[test]
+    fn parses_absolute_dates() {
+        let now = Utc::now();
+        let expected = DateTime::parse_from_rfc3339("2024-05-01T10:30:00Z").unwrap().with_timezone(&Utc);
+        assert_eq!(parse_verified_time("2024-05-01 10:30:00", now), Some(expected));
+        assert_eq!(parse_verified_time("5/1/2024", now), Some(expected - chrono::Duration::minutes(630)));
+    }
+
+    #[test]
+    fn rejects_other_cells() {
+        let now = Utc::now();
+        assert_eq!(parse_verified_time("0x1234", now), None);
+        assert_eq!(parse_verified_time("v0.8.24+commit.e11b9ed9", now), None);
+        assert_eq!(parse_verified_time("12", now), None);
+        assert_eq!(parse_verified_time("a while ago", now), None);
+    }
+
+    #[test]
+    fn compiler_filter_matches_prefixes_and_regexes() {
+        let filter = CompilerFilter::new(&["v0.8".to_string(), r"^v0\.7\.[0-5]\+".to_string()]).unwrap();
+        assert!(filter.matches("v0.8.24+commit.e11b9ed9"));
+        assert!(filter.matches("v0.7.4+commit.3f05b770"));
+        assert!(!filter.matches("v0.7.6+commit.7338295f"));
+        assert!(!filter.matches("v0.6.12+commit.27d51765"));
+        assert!(CompilerFilter::default().matches("v0.4.26+commit.4563c3fc"));
+        assert!(CompilerFilter::new(&["v0.(8".to_string()]).is_err());
+    }
+
+    #[test]
+    fn name_filter_is_a_regex() {
+        let args = Args::try_parse_from(["scraper", "--name-filter", "^Uniswap"]).unwrap();
+        let filter = args.name_filter.unwrap();
+        assert!(filter.is_match("UniswapV3Pool"));
+        assert!(!filter.is_match("PancakeUniswapFork"));
+        assert!(Args::try_parse_from(["scraper", "--name-filter", "Token("]).is_err());
+    }
+
+    #[test]
+    fn source_hash_is_keccak256() {
+        assert_eq!(source_hash(""), "0xc5d2460186f7233c927e7db2dcc703c0e500b653ca82273b7bfad8045d85a470");
+        assert_eq!(source_hash("contract A {}"), source_hash("contract A {}"));
+        assert_ne!(source_hash("contract A {}"), source_hash("contract B {}"));
+    }
+
+    #[test]
+    fn cron_schedule_waits_for_the_next_time() {
+        let now = "2024-05-01T10:20:30Z".parse::<DateTime<Utc>>().unwrap();
+        let args = Args::try_parse_from(["scraper", "--cron", "0 0 * * * *"]).unwrap();
+        assert_eq!(next_cycle_wait(&args, now), Some(Duration::from_secs(39 * 60 + 30)));
+        
+        let args = Args::try_parse_from(["scraper", "--interval", "60"]).unwrap();
+        assert_eq!(next_cycle_wait(&args, now), Some(Duration::from_secs(60)));
+        
+        assert!(Args::try_parse_from(["scraper", "--cron", "hourly please"]).is_err());
+        assert!(Args::try_parse_from(["scraper", "--cron", "0 0 * * * *", "--interval", "60"]).is_err());
+    }
+
+    #[test]
+    fn chains_get_their_own_files() {
+        let args = Args::try_parse_from(["scraper", "--chain", "base", "--chain", "optimism", "--chain", "base"]).unwrap();
+        let chains = Chain::from_args(&args);
+        assert_eq!(chains.len(), 2);
+        assert_eq!(chains[0].listing_url, "https://basescan.org/contractsVerified");
+        assert_eq!(chains[1].state_file, PathBuf::from("scraper_state_optimism.json"));
+        assert_eq!(chains[1].chain_id, Some(10));
+        assert_eq!(chains[1].output_file, PathBuf::from("verified_contracts_optimism.json"));
+        
+        let chains = Chain::from_args(&Args::try_parse_from(["scraper"]).unwrap());
+        assert_eq!(chains[0].name, "base-sepolia");
+        assert_eq!(chains[0].explorer_url, "https://sepolia.basescan.org");
+        assert_eq!(chains[0].state_file, PathBuf::from(STATE_FILE));
+        assert_eq!(chains[0].chain_id, Some(84532));
+        
+        let chains = Chain::from_args(&Args::try_parse_from(["scraper", "--chain", "base", "--out-dir", "runs/base"]).unwrap());
+        assert_eq!(chains[0].state_file, PathBuf::from("runs/base/scraper_state_base.json"));
+        assert_eq!(chains[0].output_file, PathBuf::from("runs/base/verified_contracts_base.json"));
+        
+        assert!(Args::try_parse_from(["scraper", "--chain", "solana"]).is_err());
+    }
+
+    #[test]
+    fn maps_columns_by_header() {
+        let html = r#"<table class="table">
+            <thead><tr><th>Address</th><th>Age</th><th>Contract Name</th><th>Compiler</th></tr></thead>
+            <tbody>
+                <tr><td><a href="/address/0x0000000000000000000000000000000000000abc#code">0xabc</a></td><td>5 mins ago</td><td>Token</td><td>v0.8.24</td></tr>
+                <tr><td><a href="/address/0x0000000000000000000000000000000000000def#code">0xdef</a></td><td>6 mins ago</td></tr>
+            </tbody>
+        </table>"#;
+        let selectors = ContractSelectors::new("table.table", "tbody tr", "td").unwrap();
+        let contracts = parse_contracts_table(html, &selectors).unwrap();
+        
+        assert_eq!(contracts.len(), 1);
+        assert_eq!(contracts[0].contract_address, "0x0000000000000000000000000000000000000abc#code");
+        assert_eq!(contracts[0].contract_name, "Token");
+        assert_eq!(contracts[0].compiler_version, "v0.8.24");
+        assert_eq!(contracts[0].contract_creator, "");
+        assert_ne!(contracts[0].timestamp, contracts[0].scraped_at);
+    }
+
+    #[test]
+    fn rows_without_a_valid_address_are_skipped() {
+        let html = r#"<table class="table"><tbody>
+                <tr><td><a href="/address/0x5aaeb6053f3e94c9b9a09f33669435e7ef1beaed#code">0x5aAeb6...</a></td><td>Token</td><td>v0.8.24</td></tr>
+                <tr><td><a href="/tokens">View</a></td><td>Token</td><td>v0.8.24</td></tr>
+                <tr><td>0x5aAeb6...BeAed</td><td>Token</td><td>v0.8.24</td></tr>
+            </tbody></table>"#;
+        let selectors = ContractSelectors::new("table.table", "tbody tr", "td").unwrap();
+        let contracts = parse_contracts_table(html, &selectors).unwrap();
+        
+        assert_eq!(contracts.len(), 1);
+        assert_eq!(contracts[0].contract_address, "0x5aaeb6053f3e94c9b9a09f33669435e7ef1beaed#code");
+    }
+
+    #[test]
+    fn creator_address_comes_from_its_link() {
+        let creator = "0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed";
+        let html = format!(
+            r#"<table class="table"><tbody>
+                <tr><td><a href="/address/0x0000000000000000000000000000000000000abc#code">0xabc</a></td><td>Token</td><td>v0.8.24</td>
+                    <td><a href="/address/{}">0x5aAeb6...BeAed</a></td></tr>
+                <tr><td><a href="/address/0x0000000000000000000000000000000000000def#code">0xdef</a></td><td>Token</td><td>v0.8.24</td><td>deployer.eth</td></tr>
+            </tbody></table>"#,
+            creator
+        );
+        let selectors = ContractSelectors::new("table.table", "tbody tr", "td").unwrap();
+        let contracts = parse_contracts_table(&html, &selectors).unwrap();
+        
+        assert_eq!(contracts[0].contract_creator, creator);
+        assert_eq!(contracts[1].contract_creator, "deployer.eth");
+    }
+
+    #[test]
+    fn falls_back_to_default_columns_without_a_header() {
+        let columns = Columns::from_headers(&[]);
+        assert_eq!(columns, Columns::default());
+        
+        let headers: Vec<String> = ["Contract Address", "Contract Name", "Version", "Creator", "Verified"]
+            .iter()
+            .map(|header| header.to_string())
+            .collect();
+        let columns = Columns::from_headers(&headers);
+        assert_eq!(columns, Columns { address: 0, name: 1, compiler: 2, creator: Some(3), age: Some(4) });
+    }
+
+    fn sample_contracts(count: usize) -> Vec<VerifiedContract> {
+        (0..count)
+            .map(|i| VerifiedContract {
+                chain: "base".to_string(),
+                contract_address: format!("0x{:040x}#code", i),
+                contract_name: format!("Token{}", i),
+                compiler_version: "v0.8.24".to_string(),
+                contract_creator: String::new(),
+                source_code: String::new(),
+                source_hash: None,
+                source_length: None,
+                raw_source_code: None,
+                abi: None,
+                timestamp: String::new(),
+                scraped_at: String::new(),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn webhook_payloads_fit_each_format() {
+        let contracts = sample_contracts(12);
+        
+        let json = webhook_payload(WebhookFormat::Json, "base", &contracts[..2]);
+        assert_eq!(json["count"], 2);
+        assert_eq!(json["contracts"][1]["name"], "Token1");
+        
+        let discord = webhook_payload(WebhookFormat::Discord, "base", &contracts);
+        let content = discord["content"].as_str().unwrap();
+        assert!(content.starts_with("12 new verified contracts on base"));
+        assert!(content.contains("Token9 `0x0000000000000000000000000000000000000009`"));
+        assert!(!content.contains("Token10"));
+        assert!(content.ends_with("and 2 more"));
+        
+        assert!(webhook_payload(WebhookFormat::Slack, "base", &contracts)["text"].is_string());
+    }
+
+    #[test]
+    fn json_array_output_stays_valid_across_appends() {
+        let path = std::env::temp_dir().join(format!("verified-contracts-{}.json", std::process::id()));
+        let contracts = sample_contracts(3);
+        append_to_output(&path, &contracts[..1]).unwrap();
+        
+        append_to_json_array(&path, &contracts[1..2]).unwrap();
+        append_to_json_array(&path, &contracts[2..]).unwrap();
+        
+        let records: Vec<VerifiedContract> = serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        let names: Vec<_> = records.iter().map(|contract| contract.contract_name.as_str()).collect();
+        assert_eq!(names, ["Token0", "Token1", "Token2"]);
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn crash_before_saving_state_leaves_no_duplicates() {
+        let path = std::env::temp_dir().join(format!("verified-contracts-crash-{}.json", std::process::id()));
+        let contracts = sample_contracts(2);
+        append_to_output(&path, &contracts[..1]).unwrap();
+        let committed_len = Some(std::fs::metadata(&path).unwrap().len());
+        
+        // The second batch and part of a third reach the file, then the process dies
+        append_to_output(&path, &contracts[1..]).unwrap();
+        OpenOptions::new().append(true).open(&path).unwrap().write_all(b"{\"chain\":\"ba").unwrap();
+        
+        assert!(truncate_uncommitted(&path, committed_len).unwrap() > 0);
+        append_to_output(&path, &contracts[1..]).unwrap();
+        
+        let lines: Vec<VerifiedContract> = std::fs::read_to_string(&path)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        let names: Vec<_> = lines.iter().map(|contract| contract.contract_name.as_str()).collect();
+        assert_eq!(names, ["Token0", "Token1"]);
+        assert_eq!(truncate_uncommitted(&path, None).unwrap(), 0);
+        std::fs::remove_file(path).unwrap();
+    }
+    
+    #[test]
+    fn crash_before_saving_state_leaves_no_duplicates_in_a_json_array() {
+        let path = std::env::temp_dir().join(format!("verified-contracts-array-crash-{}.json", std::process::id()));
+        let contracts = sample_contracts(2);
+        let committed_records = Some(append_to_json_array(&path, &contracts[..1]).unwrap());
+        
+        // The second batch replaces the array, then the process dies before saving the state
+        append_to_json_array(&path, &contracts[1..]).unwrap();
+        
+        assert_eq!(truncate_uncommitted_records(&path, committed_records).unwrap(), 1);
+        assert_eq!(append_to_json_array(&path, &contracts[1..]).unwrap(), 2);
+        
+        let records: Vec<VerifiedContract> = serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        let names: Vec<_> = records.iter().map(|contract| contract.contract_name.as_str()).collect();
+        assert_eq!(names, ["Token0", "Token1"]);
+        assert_eq!(truncate_uncommitted_records(&path, None).unwrap(), 0);
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn bloom_dedup_survives_a_restart() {
+        let state_file = std::env::temp_dir().join(format!("scraper-state-bloom-{}.json", std::process::id()));
+        let mut chain = Chain::from_args(&Args::try_parse_from(["scraper"]).unwrap()).remove(0);
+        chain.state_file = state_file.clone();
+        let contracts = sample_contracts(2);
+        let monitor = |bloom| ChainMonitor {
+            chain: chain.clone(),
+            state: load_state(&state_file).unwrap(),
+            bloom,
+            backfill_pending: false,
+            failed_pages: 0,
+        };
+        
+        let mut first = monitor(Some(BloomDedup::load(&state_file.with_extension("bloom"), 0.0001).unwrap()));
+        first.mark_processed([&contracts[0].contract_address]);
+        first.save().unwrap();
+        
+        let second = monitor(Some(BloomDedup::load(&first.bloom_file(), 0.5).unwrap()));
+        assert!(second.is_processed(&contracts[0].contract_address));
+        assert!(!second.is_processed(&contracts[1].contract_address));
+        assert!(second.state.processed_contracts.is_empty());
+        assert!(!monitor(None).is_processed(&contracts[0].contract_address));
+        
+        std::fs::remove_file(&state_file).unwrap();
+        std::fs::remove_file(second.bloom_file()).unwrap();
+        assert!(Args::try_parse_from(["scraper", "--bloom-dedup", "--bloom-fp-rate", "1"]).is_err());
+    }
+
+    #[tokio::test]
+    async fn truncated_listing_is_refetched() {
+        use wiremock::matchers::method;
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+        
+        let server = MockServer::start().await;
+        let html = |body: &str| ResponseTemplate::new(200).set_body_raw(body.to_string(), "text/html");
+        Mock::given(method("GET"))
+            .respond_with(html("<html><body><div class=\"container\"><h1>Verified Contr"))
+            .up_to_n_times(1)
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .respond_with(html(r#"<table class="table"><tbody>
+                <tr><td><a href="/address/0x0000000000000000000000000000000000000abc#code">0xabc</a></td><td>Token</td><td>v0.8.24</td></tr>
+            </tbody></table>"#))
+            .expect(1)
+            .mount(&server)
+            .await;
+        let http = HttpOptions::default();
+        let client = build_client(&http).unwrap();
+        let selectors = ContractSelectors::new("table.table", "tbody tr", "td").unwrap();
+        
+        let contracts = fetch_contracts(&client, &server.uri(), &http, &selectors).await.unwrap();
+        
+        assert_eq!(contracts.len(), 1);
+        assert_eq!(contracts[0].contract_name, "Token");
+    }
+
+    #[tokio::test]
+    async fn unchanged_listing_is_not_reparsed() {
+        use wiremock::matchers::{header, method};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+        
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(header("If-None-Match", "\"v1\""))
+            .respond_with(ResponseTemplate::new(304))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .insert_header("ETag", "\"v1\"")
+                    .set_body_raw(r#"<table class="table"><tbody>
+                        <tr><td><a href="/address/0x0000000000000000000000000000000000000abc#code">0xabc</a></td><td>Token</td><td>v0.8.24</td></tr>
+                    </tbody></table>"#, "text/html"),
+            )
+            .expect(1)
+            .mount(&server)
+            .await;
+        let http = HttpOptions::default();
+        let client = build_client(&http).unwrap();
+        let selectors = ContractSelectors::new("table.table", "tbody tr", "td").unwrap();
+        let validators = PageValidators::default();
+        
+        let first = fetch_contracts_if_changed(&client, &server.uri(), &http, &selectors, Some(&validators)).await.unwrap();
+        let second = fetch_contracts_if_changed(&client, &server.uri(), &http, &selectors, Some(&validators)).await.unwrap();
+        
+        assert_eq!(first.unwrap().len(), 1);
+        assert!(second.is_none());
+    }
+
+    #[tokio::test]
+    async fn only_retryable_statuses_are_retried() {
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+        
+        let server = MockServer::start().await;
+        Mock::given(path("/flaky"))
+            .respond_with(ResponseTemplate::new(503))
+            .up_to_n_times(1)
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/flaky"))
+            .respond_with(ResponseTemplate::new(200).set_body_raw("<html></html>", "text/html"))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(path("/missing")).respond_with(ResponseTemplate::new(404)).expect(1).mount(&server).await;
+        let http = HttpOptions::default();
+        let client = build_client(&http).unwrap();
+        
+        assert!(fetch_page(&client, &format!("{}/flaky", server.uri()), &http).await.is_ok());
+        assert!(fetch_page(&client, &format!("{}/missing", server.uri()), &http).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn source_code_comes_from_the_v2_api() {
+        use wiremock::matchers::{method, query_param};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+        
+        let server = MockServer::start().await;
+        let standard_json = r#"{{"language": "Solidity", "sources": {"A.sol": {"content": "contract A {}"}, "B.sol": {"content": "contract B {}"}}}}"#;
+        Mock::given(method("GET"))
+            .and(query_param("chainid", "8453"))
+            .and(query_param("address", "0xabc"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "status": "1",
+                "message": "OK",
+                "result": [{"SourceCode": standard_json, "ContractName": "A"}],
+            })))
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "status": "0",
+                "message": "No records found",
+                "result": [],
+            })))
+            .mount(&server)
+            .await;
+        let client = build_client(&HttpOptions::default()).unwrap();
+        
+        let source = fetch_source_code_api(&client, &server.uri(), 8453, "key", "0xabc").await.unwrap();
+        assert_eq!(source.as_deref(), Some("contract A {}\n\ncontract B {}"));
+        assert_eq!(fetch_source_code_api(&client, &server.uri(), 1, "key", "0xabc").await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn abi_comes_from_the_code_page_or_getabi() {
+        use wiremock::matchers::{method, query_param};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+        
+        let selectors = ContractSelectors::new("table.table", "tbody tr", "td").unwrap();
+        let abi = r#"[{"type":"function","name":"owner","inputs":[],"outputs":[]}]"#;
+        let html = format!(r#"<pre class="js-sourcecopyarea">contract A {{}}</pre><pre id="js-copytextarea2">{}</pre>"#, abi);
+        assert_eq!(parse_abi(&html, &selectors).as_deref(), Some(abi));
+        assert_eq!(parse_abi(r#"<pre id="js-copytextarea2">Contract source code not verified</pre>"#, &selectors), None);
+        
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(query_param("action", "getabi"))
+            .and(query_param("address", "0xabc"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({"status": "1", "message": "OK", "result": abi})))
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "status": "0",
+                "message": "NOTOK",
+                "result": "Contract source code not verified",
+            })))
+            .mount(&server)
+            .await;
+        let client = build_client(&HttpOptions::default()).unwrap();
+        
+        assert_eq!(fetch_abi_api(&client, &server.uri(), 1, "key", "0xabc").await.unwrap().as_deref(), Some(abi));
+        assert_eq!(fetch_abi_api(&client, &server.uri(), 1, "key", "0xdef").await.unwrap(), None);
+    }
+}
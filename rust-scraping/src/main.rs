@@ -1,177 +1,106 @@
-use anyhow::{Context, Result};
-use reqwest::Client;
-use scraper::{Html, Selector};
-use serde::{Deserialize, Serialize};
-use std::collections::HashSet;
-use std::fs::{File, OpenOptions};
-use std::io::{BufReader, BufWriter, Write};
-use std::path::Path;
+use anyhow::Result;
+use basescan_scraper::{
+    init_logging, query_since, run, seed_rng, user_agent_pool, Args, HttpOptions, LogFormat, PageCache, QueryArgs,
+    DEFAULT_RETRY_STATUSES, EXIT_CODES_HELP,
+};
+use clap::{Parser, Subcommand};
+use std::path::PathBuf;
+use std::process::ExitCode;
 use std::time::Duration;
-use tokio::time::sleep;
-
-#[derive(Debug, Serialize, Deserialize, Clone)]
-struct VerifiedContract {
-    contract_address: String,
-    contract_name: String,
-    compiler_version: String,
-    contract_creator: String,
-    source_code: String,
-    timestamp: String,
-}
 
-#[derive(Debug, Serialize, Deserialize)]
-struct ScraperState {
-    processed_contracts: HashSet<String>,
-}
+#[derive(Parser, Debug)]
+#[command(
+    about = "Monitor basescan for newly verified contracts",
+    args_conflicts_with_subcommands = true,
+    after_help = EXIT_CODES_HELP
+)]
+struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
 
-const BASE_URL: &str = "https://sepolia.basescan.org/contractsVerified";
-const STATE_FILE: &str = "scraper_state.json";
-const OUTPUT_FILE: &str = "verified_contracts.json";
-
-async fn fetch_page(client: &Client, url: &str) -> Result<String> {
-    let response = client
-        .get(url)
-        .header("User-Agent", "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36")
-        .send()
-        .await
-        .context("Failed to send request")?;
-    
-    if !response.status().is_success() {
-        anyhow::bail!("HTTP error: {}", response.status());
-    }
-    
-    response.text().await.context("Failed to read response text")
-}
+    #[command(flatten)]
+    args: Args,
 
-fn parse_contracts_table(html: &str) -> Result<Vec<VerifiedContract>> {
-    let document = Html::parse_document(html);
-    let table_selector = Selector::parse("table.table").unwrap();
-    let row_selector = Selector::parse("tbody tr").unwrap();
-    let cell_selector = Selector::parse("td").unwrap();
-    
-    let mut contracts = Vec::new();
-    
-    if let Some(table) = document.select(&table_selector).next() {
-        for row in table.select(&row_selector) {
-            let cells: Vec<_> = row.select(&cell_selector).collect();
-            
-            if cells.len() >= 7 {
-                let address_cell = cells[0].text().collect::<String>().trim().to_string();
-                let name_cell = cells[1].text().collect::<String>().trim().to_string();
-                let compiler_cell = cells[2].text().collect::<String>().trim().to_string();
-                let creator_cell = cells[3].text().collect::<String>().trim().to_string();
-                
-                // Extract contract address from the link if available
-                let contract_address = if let Some(link) = cells[0].select(&Selector::parse("a").unwrap()).next() {
-                    link.value().attr("href")
-                        .and_then(|href| href.split('/').nth(2))
-                        .unwrap_or(&address_cell)
-                        .to_string()
-                } else {
-                    address_cell
-                };
-                
-                let contract = VerifiedContract {
-                    contract_address: contract_address.clone(),
-                    contract_name: name_cell,
-                    compiler_version: compiler_cell,
-                    contract_creator: creator_cell,
-                    source_code: "Source code would be fetched from individual contract page".to_string(),
-                    timestamp: chrono::Utc::now().to_rfc3339(),
-                };
-                
-                contracts.push(contract);
-            }
-        }
-    }
-    
-    Ok(contracts)
-}
+    /// Request timeout in seconds; 0 disables the timeout
+    #[arg(long, default_value_t = 30)]
+    timeout: u64,
 
-fn load_state() -> Result<ScraperState> {
-    if Path::new(STATE_FILE).exists() {
-        let file = File::open(STATE_FILE).context("Failed to open state file")?;
-        let reader = BufReader::new(file);
-        serde_json::from_reader(reader).context("Failed to parse state file")
-    } else {
-        Ok(ScraperState {
-            processed_contracts: HashSet::new(),
-        })
-    }
-}
+    /// Proxy URL (http, https, socks5 or socks5h); defaults to HTTP_PROXY/HTTPS_PROXY
+    #[arg(long)]
+    proxy: Option<String>,
+
+    /// Also trust the root certificates in this PEM file, e.g. for a mirror with an internal CA
+    #[arg(long)]
+    ca_cert: Option<PathBuf>,
+
+    /// Accept any TLS certificate, even expired, self-signed or for another host. Dangerous:
+    /// only for explorer mirrors you control
+    #[arg(long)]
+    danger_accept_invalid_certs: bool,
 
-fn save_state(state: &ScraperState) -> Result<()> {
-    let file = File::create(STATE_FILE).context("Failed to create state file")?;
-    let writer = BufWriter::new(file);
-    serde_json::to_writer_pretty(writer, state).context("Failed to write state file")
+    /// Rotate through the user agents in this file, one per line
+    #[arg(long)]
+    user_agents_file: Option<PathBuf>,
+
+    /// Rotate through a built-in list of browser user agents
+    #[arg(long)]
+    rotate_user_agents: bool,
+
+    /// Cache fetched pages in this directory and reuse them instead of refetching
+    #[arg(long)]
+    cache_dir: Option<PathBuf>,
+
+    /// Seconds a cached page stays valid
+    #[arg(long, default_value_t = 3600, requires = "cache_dir")]
+    cache_ttl: u64,
+
+    /// Parse responses as HTML whatever their Content-Type header says
+    #[arg(long)]
+    no_content_type_check: bool,
+
+    /// HTTP error statuses to retry, comma separated; other error statuses fail at once.
+    /// Network errors are always retried.
+    #[arg(long, value_delimiter = ',', default_values_t = DEFAULT_RETRY_STATUSES, value_parser = clap::value_parser!(u16).range(400..600))]
+    retry_status: Vec<u16>,
+
+    /// Seed for every random choice: backoff jitter and user agent rotation. Runs with the
+    /// same seed choose alike; without one the seed is random
+    #[arg(long)]
+    seed: Option<u64>,
+
+    /// Log line format
+    #[arg(long, value_enum, default_value_t = LogFormat::Text)]
+    log_format: LogFormat,
 }
 
-fn append_to_output(contracts: &[VerifiedContract]) -> Result<()> {
-    let file = OpenOptions::new()
-        .create(true)
-        .append(true)
-        .open(OUTPUT_FILE)
-        .context("Failed to open output file")?;
-    
-    let mut writer = BufWriter::new(file);
-    
-    for contract in contracts {
-        serde_json::to_writer(&mut writer, contract).context("Failed to write contract to output")?;
-        writer.write_all(b"\n").context("Failed to write newline")?;
-    }
-    
-    Ok(())
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Print the contracts in the output that were scraped after an instant, one per line
+    Query(QueryArgs),
 }
 
 #[tokio::main]
-async fn main() -> Result<()> {
-    env_logger::init();
+async fn main() -> Result<ExitCode> {
+    let cli = Cli::parse();
+    init_logging("error", cli.log_format);
+    seed_rng(cli.seed);
     
-    let client = Client::builder()
-        .timeout(Duration::from_secs(30))
-        .build()
-        .context("Failed to create HTTP client")?;
+    if let Some(Command::Query(args)) = cli.command {
+        query_since(&args, &mut std::io::stdout().lock())?;
+        return Ok(ExitCode::SUCCESS);
+    }
     
-    let mut state = load_state()?;
+    let http = HttpOptions {
+        timeout: Duration::from_secs(cli.timeout),
+        proxy: cli.proxy,
+        user_agents: user_agent_pool(cli.user_agents_file.as_deref(), cli.rotate_user_agents)?,
+        cache: cli.cache_dir.map(|dir| PageCache::new(dir, Duration::from_secs(cli.cache_ttl))),
+        check_content_type: !cli.no_content_type_check,
+        retry_statuses: cli.retry_status,
+        ca_cert: cli.ca_cert,
+        accept_invalid_certs: cli.danger_accept_invalid_certs,
+        ..HttpOptions::default()
+    };
     
-    loop {
-        log::info!("Fetching verified contracts from: {}", BASE_URL);
-        
-        match fetch_page(&client, BASE_URL).await {
-            Ok(html) => {
-                match parse_contracts_table(&html) {
-                    Ok(contracts) => {
-                        let new_contracts: Vec<_> = contracts
-                            .into_iter()
-                            .filter(|contract| !state.processed_contracts.contains(&contract.contract_address))
-                            .collect();
-                        
-                        if !new_contracts.is_empty() {
-                            log::info!("Found {} new contracts", new_contracts.len());
-                            
-                            for contract in &new_contracts {
-                                state.processed_contracts.insert(contract.contract_address.clone());
-                                log::info!("New contract: {} - {}", contract.contract_address, contract.contract_name);
-                            }
-                            
-                            append_to_output(&new_contracts)?;
-                            save_state(&state)?;
-                        } else {
-                            log::info!("No new contracts found");
-                        }
-                    }
-                    Err(e) => {
-                        log::error!("Failed to parse contracts table: {}", e);
-                    }
-                }
-            }
-            Err(e) => {
-                log::error!("Failed to fetch page: {}", e);
-            }
-        }
-        
-        // Rate limiting - wait before next scrape
-        log::info!("Waiting 5 minutes before next scrape...");
-        sleep(Duration::from_secs(300)).await;
-    }
-}
\ No newline at end of file
+    Ok(run(cli.args, http).await?.into())
+}
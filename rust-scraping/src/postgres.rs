@@ -0,0 +1,71 @@
+use crate::{ScraperError, VerifiedContract};
+use sqlx::postgres::{PgPool, PgPoolOptions};
+
+const MAX_CONNECTIONS: u32 = 4;
+
+/// Upserts new contracts into a shared Postgres `verified_contracts` table
+pub struct PostgresSink {
+    pool: PgPool,
+}
+
+impl PostgresSink {
+    /// Connects and creates the table if it doesn't exist yet, so a bad URL fails at startup
+    pub async fn connect(url: &str) -> Result<Self, ScraperError> {
+        let pool = PgPoolOptions::new().max_connections(MAX_CONNECTIONS).connect(url).await?;
+        sqlx::query(
+            "CREATE TABLE IF NOT EXISTS verified_contracts (
+                chain TEXT NOT NULL,
+                contract_address TEXT NOT NULL,
+                contract_name TEXT NOT NULL,
+                compiler_version TEXT NOT NULL,
+                contract_creator TEXT NOT NULL,
+                source_code TEXT NOT NULL,
+                raw_source_code TEXT,
+                source_hash TEXT,
+                timestamp TEXT NOT NULL,
+                scraped_at TEXT NOT NULL,
+                PRIMARY KEY (chain, contract_address)
+            )",
+        )
+        .execute(&pool)
+        .await?;
+        Ok(Self { pool })
+    }
+
+    /// Upserts the batch in one transaction, replacing rows already stored for the same chain and address
+    pub async fn upsert(&self, contracts: &[VerifiedContract]) -> Result<(), ScraperError> {
+        let mut tx = self.pool.begin().await?;
+        for contract in contracts {
+            sqlx::query(
+                "INSERT INTO verified_contracts
+                    (chain, contract_address, contract_name, compiler_version, contract_creator, source_code, raw_source_code, source_hash, timestamp, scraped_at)
+                    VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
+                    ON CONFLICT (chain, contract_address) DO UPDATE SET
+                        contract_name = EXCLUDED.contract_name,
+                        compiler_version = EXCLUDED.compiler_version,
+                        contract_creator = EXCLUDED.contract_creator,
+                        source_code = EXCLUDED.source_code,
+                        raw_source_code = EXCLUDED.raw_source_code,
+                        source_hash = EXCLUDED.source_hash,
+                        timestamp = EXCLUDED.timestamp,
+                        scraped_at = EXCLUDED.scraped_at",
+            )
+            .bind(&contract.chain)
+            .bind(&contract.contract_address)
+            .bind(&contract.contract_name)
+            .bind(&contract.compiler_version)
+            .bind(&contract.contract_creator)
+            .bind(&contract.source_code)
+            .bind(&contract.raw_source_code)
+            .bind(&contract.source_hash)
+            .bind(&contract.timestamp)
+            .bind(&contract.scraped_at)
+            .execute(&mut *tx)
+            .await?;
+        }
+        tx.commit().await?;
+
+        log::info!("Upserted {} contracts into Postgres", contracts.len());
+        Ok(())
+    }
+}
@@ -0,0 +1,88 @@
+use crate::{ScraperError, OUTPUT_FILE};
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use std::fs::File;
+use std::io::{BufRead, BufReader, Write};
+use std::path::PathBuf;
+
+#[derive(clap::Args, Debug, Clone)]
+pub struct QueryArgs {
+    /// Print only contracts scraped after this RFC 3339 instant, e.g. 2024-05-01T12:00:00Z
+    #[arg(long)]
+    pub since: DateTime<Utc>,
+
+    /// Line-per-contract output to read
+    #[arg(long, default_value = OUTPUT_FILE)]
+    pub input: PathBuf,
+}
+
+/// The fields of an output line the query looks at; the rest is passed through untouched
+#[derive(Debug, Deserialize)]
+struct Stamps {
+    #[serde(default)]
+    timestamp: String,
+    #[serde(default)]
+    scraped_at: String,
+}
+
+impl Stamps {
+    /// When the record was scraped, or verified for records written before `scraped_at` existed
+    fn time(&self) -> Option<DateTime<Utc>> {
+        let stamp = if self.scraped_at.is_empty() { &self.timestamp } else { &self.scraped_at };
+        DateTime::parse_from_rfc3339(stamp).ok().map(|time| time.with_timezone(&Utc))
+    }
+}
+
+/// Copies the output lines newer than `since` to `out` one at a time, so the file is never
+/// loaded whole. Lines that don't parse, such as one cut off by a crash, are skipped.
+/// Returns how many lines were copied.
+pub fn query_since(args: &QueryArgs, out: &mut impl Write) -> Result<usize, ScraperError> {
+    let mut copied = 0;
+    for (number, line) in BufReader::new(File::open(&args.input)?).lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        if number == 0 && line.trim_start().starts_with('[') {
+            return Err(ScraperError::Config(format!(
+                "{} is a JSON array; only line-per-contract output can be queried",
+                args.input.display()
+            )));
+        }
+        match serde_json::from_str::<Stamps>(&line) {
+            Ok(stamps) if stamps.time().is_some_and(|time| time > args.since) => {
+                writeln!(out, "{}", line)?;
+                copied += 1;
+            }
+            Ok(_) => {}
+            Err(e) => log::warn!("Skipping line {} of {}: {}", number + 1, args.input.display(), e),
+        }
+    }
+    Ok(copied)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_newer_lines_are_copied() {
+        let input = std::env::temp_dir().join(format!("verified-contracts-query-{}.json", std::process::id()));
+        let lines = [
+            r#"{"contract_name":"Old","timestamp":"2024-04-30T00:00:00Z","scraped_at":"2024-05-01T00:00:00Z"}"#,
+            r#"{"contract_name":"New","timestamp":"2024-04-30T00:00:00Z","scraped_at":"2024-05-02T00:00:00Z"}"#,
+            r#"{"contract_name":"Legacy","timestamp":"2024-05-03T00:00:00Z"}"#,
+            r#"{"contract_name":"Cut"#,
+        ];
+        std::fs::write(&input, lines.join("\n")).unwrap();
+        let args = QueryArgs { since: "2024-05-01T12:00:00Z".parse().unwrap(), input: input.clone() };
+
+        let mut out = Vec::new();
+        assert_eq!(query_since(&args, &mut out).unwrap(), 2);
+        assert_eq!(String::from_utf8(out).unwrap(), format!("{}\n{}\n", lines[1], lines[2]));
+
+        std::fs::write(&input, "[\n]\n").unwrap();
+        assert!(query_since(&args, &mut Vec::new()).is_err());
+        std::fs::remove_file(input).unwrap();
+    }
+}
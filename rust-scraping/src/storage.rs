@@ -0,0 +1,36 @@
+use crate::{ScraperError, VerifiedContract};
+use scathat_core::S3Store;
+
+/// Uploads batches of new contracts to S3-compatible object storage
+pub struct S3Uploader {
+    store: S3Store,
+}
+
+impl S3Uploader {
+    /// Credentials come from the usual AWS environment variables or profile.
+    /// A custom `endpoint` targets S3-compatible stores such as MinIO and uses path-style URLs.
+    pub fn new(bucket: &str, region: &str, endpoint: Option<&str>, prefix: &str) -> Result<Self, ScraperError> {
+        Ok(Self { store: S3Store::new(bucket, region, endpoint, prefix)? })
+    }
+
+    /// Uploads the batch as NDJSON under `<prefix>/<timestamp>.ndjson`, retrying with backoff.
+    ///
+    /// Failures are logged rather than returned so a storage outage never stops the monitor.
+    pub async fn upload_batch(&self, contracts: &[VerifiedContract]) {
+        let key = self.store.key(&format!("{}.ndjson", chrono::Utc::now().format("%Y%m%dT%H%M%S%.3fZ")));
+
+        let mut content = Vec::new();
+        for contract in contracts {
+            if let Err(e) = serde_json::to_writer(&mut content, contract) {
+                log::warn!("Failed to serialize batch for S3 upload: {}", e);
+                return;
+            }
+            content.push(b'\n');
+        }
+
+        match self.store.upload(&key, &content, "application/x-ndjson").await {
+            Ok(url) => log::info!("Uploaded {} contracts to {}", contracts.len(), url),
+            Err(e) => log::warn!("Giving up on S3 upload of {}: {}", key, e),
+        }
+    }
+}
@@ -0,0 +1,57 @@
+use reqwest::header::{HeaderValue, ETAG, IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED};
+use reqwest::{RequestBuilder, Response};
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+/// The `ETag` and `Last-Modified` of a page's last full response
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Validator {
+    etag: Option<HeaderValue>,
+    last_modified: Option<HeaderValue>,
+}
+
+impl Validator {
+    pub(crate) fn from_response(response: &Response) -> Self {
+        Self {
+            etag: response.headers().get(ETAG).cloned(),
+            last_modified: response.headers().get(LAST_MODIFIED).cloned(),
+        }
+    }
+}
+
+/// Validators of the listing pages fetched so far, sent back as `If-None-Match` and
+/// `If-Modified-Since` so an unchanged page comes back as a bodyless 304. A server that
+/// sends neither header gets plain GETs.
+#[derive(Debug, Default)]
+pub struct PageValidators {
+    pages: Mutex<HashMap<String, Validator>>,
+}
+
+impl PageValidators {
+    /// Makes `request` conditional on `url` having changed since it was recorded
+    pub(crate) fn apply(&self, url: &str, mut request: RequestBuilder) -> RequestBuilder {
+        if let Some(validator) = self.pages.lock().expect("validators lock poisoned").get(url) {
+            if let Some(etag) = &validator.etag {
+                request = request.header(IF_NONE_MATCH, etag.clone());
+            }
+            if let Some(last_modified) = &validator.last_modified {
+                request = request.header(IF_MODIFIED_SINCE, last_modified.clone());
+            }
+        }
+        request
+    }
+
+    pub(crate) fn record(&self, url: &str, validator: Validator) {
+        let mut pages = self.pages.lock().expect("validators lock poisoned");
+        if validator.etag.is_some() || validator.last_modified.is_some() {
+            pages.insert(url.to_string(), validator);
+        } else {
+            pages.remove(url);
+        }
+    }
+
+    /// Drops what was recorded for `url`, so the next fetch is unconditional
+    pub(crate) fn forget(&self, url: &str) {
+        self.pages.lock().expect("validators lock poisoned").remove(url);
+    }
+}